@@ -0,0 +1,111 @@
+use crate::task::Task;
+use crate::time::Time;
+
+/// Pending tasks that matter on a day, by section. Each task is only listed in the first
+/// section it belongs to.
+#[derive(Debug, PartialEq, Default)]
+pub(crate) struct Agenda {
+    /// Tasks planned for the day.
+    pub pinned: Vec<usize>,
+    /// Tasks due before the day.
+    pub overdue: Vec<usize>,
+    pub due: Vec<usize>,
+    /// Tasks whose snooze ends during the day.
+    pub unsnoozed: Vec<usize>,
+    /// Tasks that periodic tasks created during the day.
+    pub periodic: Vec<usize>,
+}
+
+impl Agenda {
+    /// Agenda of the day of `now`.
+    pub(crate) fn new(tasks: &[Task], now: &Time) -> Self {
+        let today = now.start_of_day();
+        let tomorrow = today.add_days(1);
+        let during_today = |time: &Time| &today <= time && time < &tomorrow;
+        let mut agenda = Agenda::default();
+        for (index, task) in tasks.iter().enumerate() {
+            if task.completed() {
+                continue;
+            }
+            let section = if task.pinned_to.as_ref() == Some(&today) {
+                &mut agenda.pinned
+            } else if task.due_date.as_ref().is_some_and(|due| due < &today) {
+                &mut agenda.overdue
+            } else if task.due_date.as_ref().is_some_and(during_today) {
+                &mut agenda.due
+            } else if task
+                .snooze_information
+                .snooze_until
+                .as_ref()
+                .is_some_and(during_today)
+            {
+                &mut agenda.unsnoozed
+            } else if task.created_by_periodic_task && during_today(&task.creation_time) {
+                &mut agenda.periodic
+            } else {
+                continue;
+            };
+            section.push(index);
+        }
+        agenda
+    }
+
+    /// Whether a task is listed in some section.
+    pub(crate) fn contains(&self, index: usize) -> bool {
+        [
+            &self.pinned,
+            &self.overdue,
+            &self.due,
+            &self.unsnoozed,
+            &self.periodic,
+        ]
+        .iter()
+        .any(|section| section.contains(&index))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::FakeClock;
+    use crate::time::test_time as time;
+
+    #[test]
+    fn sections() {
+        let clock = FakeClock::new(time(14, 8));
+        let task = |change: &dyn Fn(&mut Task)| {
+            let mut task = Task::new(0, &clock);
+            change(&mut task);
+            task
+        };
+        let tasks = [
+            task(&|task| task.due_date = Some(time(13, 17))),
+            task(&|task| task.due_date = Some(time(15, 17))),
+            task(&|task| task.due_date = Some(time(15, 9))),
+            task(&|task| task.snooze(time(15, 12))),
+            task(&|task| task.created_by_periodic_task = true),
+            task(&|task| {
+                task.pinned_to = Some(time(15, 0));
+                task.due_date = Some(time(13, 17));
+            }),
+            task(&|task| {
+                task.due_date = Some(time(15, 9));
+                task.set_completed(true, &clock);
+            }),
+            task(&|task| task.pinned_to = Some(time(14, 0))),
+        ];
+
+        let agenda = Agenda::new(&tasks, &time(15, 10));
+        assert_eq!(agenda.pinned, vec![5]);
+        assert_eq!(agenda.overdue, vec![0]);
+        assert_eq!(agenda.due, vec![1, 2]);
+        assert_eq!(agenda.unsnoozed, vec![3]);
+        assert!(agenda.periodic.is_empty());
+        assert!(agenda.contains(3));
+        assert!(!agenda.contains(4));
+
+        let agenda = Agenda::new(&tasks, &time(14, 10));
+        assert_eq!(agenda.periodic, vec![4]);
+        assert_eq!(agenda.pinned, vec![7]);
+    }
+}
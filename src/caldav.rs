@@ -0,0 +1,701 @@
+use crate::clock::Clock;
+use crate::data::Data;
+use crate::ical;
+use crate::task::{new_uid, Task};
+use crate::time::Time;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+mod http;
+
+pub(crate) use http::HttpServer;
+
+/// A task as it was on the server after the last synchronization.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub(crate) struct SyncRecord {
+    pub uid: String,
+    pub href: String,
+    pub etag: String,
+    /// VTODO the task was synchronized as, to tell whether it changed locally since then.
+    pub fingerprint: String,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct SyncError {
+    pub message: String,
+    pub kind: SyncErrorKind,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum SyncErrorKind {
+    Network,
+    Server,
+    /// The resource changed on the server since its ETag was read.
+    PreconditionFailed,
+    InvalidData,
+}
+
+impl SyncError {
+    pub(crate) fn new(kind: SyncErrorKind, message: String) -> Self {
+        SyncError { message, kind }
+    }
+}
+
+/// Condition for writing a resource, so that changes made by other clients are not overwritten.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Precondition<'a> {
+    /// The resource still has this ETag.
+    IfMatch(&'a str),
+    /// The resource does not exist yet.
+    IfNoneMatch,
+}
+
+/// A CalDAV collection of VTODOs.
+pub(crate) trait CalDavServer {
+    /// Href and ETag of every VTODO resource in the collection.
+    fn list(&mut self) -> Result<Vec<(String, String)>, SyncError>;
+    /// Contents and ETag of a resource.
+    fn get(&mut self, href: &str) -> Result<(String, String), SyncError>;
+    /// Href for a new resource holding the task with this UID.
+    fn href_for(&self, uid: &str) -> String;
+    /// Writes a resource and returns its new ETag, which is empty if the server did not send it.
+    fn put(
+        &mut self,
+        href: &str,
+        calendar: &str,
+        precondition: Precondition,
+    ) -> Result<String, SyncError>;
+    fn delete(&mut self, href: &str, etag: &str) -> Result<(), SyncError>;
+}
+
+/// What a synchronization changed.
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct SyncReport {
+    pub uploaded: usize,
+    pub downloaded: usize,
+    pub deleted_on_server: usize,
+    pub deleted_locally: usize,
+    /// Descriptions of the tasks changed on both sides.
+    pub conflicts: Vec<String>,
+    /// Descriptions of the tasks that changed on the server while synchronizing, left for the
+    /// next synchronization.
+    pub retries: Vec<String>,
+}
+
+impl std::fmt::Display for SyncReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Uploaded {}, downloaded {}, deleted {} on the server and {} locally.",
+            self.uploaded, self.downloaded, self.deleted_on_server, self.deleted_locally
+        )?;
+        for conflict in &self.conflicts {
+            write!(
+                f,
+                "\nConflict: '{conflict}' changed on both sides. Kept the server version and a \
+                 local copy."
+            )?;
+        }
+        for retry in &self.retries {
+            write!(
+                f,
+                "\n'{retry}' changed on the server meanwhile and will be synchronized next time."
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Resource on the server, with its task if it was downloaded because it is new or changed.
+struct Remote {
+    href: String,
+    etag: String,
+    task: Option<Task>,
+}
+
+fn fingerprint(task: &Task) -> String {
+    ical::export(&[task], &[], &task.creation_time)
+}
+
+fn calendar(task: &Task, now: &Time) -> String {
+    ical::export(&[task], &[], now)
+}
+
+/// Synchronizes the tasks of `data` with a CalDAV collection, matching them by UID.
+///
+/// Each task is compared with its state at the last synchronization: changes on one side are
+/// copied to the other, and deletions are propagated unless the other side changed the task, in
+/// which case the change wins. A task changed on both sides keeps the server version, and the
+/// local version is kept as a new task marked as a conflict, which is uploaded on the next
+/// synchronization. Tasks are processed in UID order, so the outcome does not depend on the
+/// order of the list or of the server response.
+pub(crate) fn synchronize(
+    data: &mut Data,
+    server: &mut impl CalDavServer,
+    clock: &dyn Clock,
+) -> Result<SyncReport, SyncError> {
+    let now = clock.now();
+    for task in data.tasks.iter_mut().filter(|task| task.uid.is_none()) {
+        task.uid = Some(new_uid());
+    }
+
+    let records = data
+        .sync_records
+        .iter()
+        .map(|record| (record.uid.clone(), record.clone()))
+        .collect::<BTreeMap<_, _>>();
+    let uids_by_href = records
+        .values()
+        .map(|record| (record.href.clone(), record))
+        .collect::<BTreeMap<_, _>>();
+
+    let mut remotes = BTreeMap::new();
+    for (href, etag) in server.list()? {
+        match uids_by_href.get(&href) {
+            Some(record) if record.etag == etag && !etag.is_empty() => {
+                remotes.insert(
+                    record.uid.clone(),
+                    Remote {
+                        href,
+                        etag,
+                        task: None,
+                    },
+                );
+            }
+            _ => {
+                let (contents, etag) = server.get(&href)?;
+                let Some(mut task) = ical::parse(&contents, clock)
+                    .map_err(|error| SyncError::new(SyncErrorKind::InvalidData, error.message))?
+                    .into_iter()
+                    .next()
+                else {
+                    continue;
+                };
+                let uid = task.uid.get_or_insert_with(|| href.clone()).clone();
+                let task = Some(task);
+                remotes.insert(uid, Remote { href, etag, task });
+            }
+        }
+    }
+
+    let locals = data
+        .tasks
+        .iter()
+        .enumerate()
+        .map(|(index, task)| (task.uid.clone().unwrap(), index))
+        .collect::<BTreeMap<_, _>>();
+    let uids = records
+        .keys()
+        .chain(remotes.keys())
+        .chain(locals.keys())
+        .cloned()
+        .collect::<BTreeSet<_>>();
+
+    let archived = data
+        .archive
+        .iter()
+        .filter_map(|task| task.uid.clone())
+        .collect::<BTreeSet<_>>();
+
+    let mut report = SyncReport::default();
+    let mut new_records = vec![];
+    let mut removed = vec![];
+    let mut added = vec![];
+    for uid in uids {
+        let record = records.get(&uid);
+        if archived.contains(&uid) {
+            // Archived tasks are left as they are on the server.
+            new_records.extend(record.cloned());
+            continue;
+        }
+        let local = locals.get(&uid).copied();
+        let remote = remotes.remove(&uid);
+        let locally_changed = |index: usize| {
+            record.is_none_or(|record| record.fingerprint != fingerprint(&data.tasks[index]))
+        };
+
+        match (local, remote) {
+            (Some(index), Some(remote)) => {
+                let local_changed = locally_changed(index);
+                match (local_changed, remote.task) {
+                    (false, None) => new_records.push(record.unwrap().clone()),
+                    (true, None) => {
+                        let task = &data.tasks[index];
+                        match server.put(
+                            &remote.href,
+                            &calendar(task, &now),
+                            Precondition::IfMatch(&remote.etag),
+                        ) {
+                            Ok(etag) => {
+                                report.uploaded += 1;
+                                new_records.push(record_for(task, remote.href, etag));
+                            }
+                            Err(error) if error.kind == SyncErrorKind::PreconditionFailed => {
+                                report.retries.push(task.description().to_string());
+                                new_records.push(record.unwrap().clone());
+                            }
+                            Err(error) => return Err(error),
+                        }
+                    }
+                    (local_changed, Some(remote_task)) => {
+                        if local_changed && record.is_some() {
+                            let mut copy = data.tasks[index].clone();
+                            copy.uid = Some(new_uid());
+                            copy.edit(&format!("{} (conflict)", copy.description()));
+                            report.conflicts.push(remote_task.description().to_string());
+                            added.push(copy);
+                        }
+                        let task = &mut data.tasks[index];
+                        apply_remote(task, remote_task, clock);
+                        report.downloaded += 1;
+                        new_records.push(record_for(task, remote.href, remote.etag));
+                    }
+                }
+            }
+            (Some(index), None) => {
+                let task = &data.tasks[index];
+                if record.is_some() && !locally_changed(index) {
+                    removed.push(index);
+                    report.deleted_locally += 1;
+                    continue;
+                }
+                let href = server.href_for(&uid);
+                match server.put(&href, &calendar(task, &now), Precondition::IfNoneMatch) {
+                    Ok(etag) => {
+                        report.uploaded += 1;
+                        new_records.push(record_for(task, href, etag));
+                    }
+                    Err(error) if error.kind == SyncErrorKind::PreconditionFailed => {
+                        report.retries.push(task.description().to_string());
+                    }
+                    Err(error) => return Err(error),
+                }
+            }
+            (None, Some(remote)) => match (record, remote.task) {
+                (Some(record), None) => match server.delete(&remote.href, &remote.etag) {
+                    Ok(()) => report.deleted_on_server += 1,
+                    Err(error) if error.kind == SyncErrorKind::PreconditionFailed => {
+                        new_records.push(record.clone())
+                    }
+                    Err(error) => return Err(error),
+                },
+                (None, None) => {}
+                (_, Some(mut task)) => {
+                    task.id = data.tasks.len() + added.len();
+                    report.downloaded += 1;
+                    new_records.push(record_for(&task, remote.href, remote.etag));
+                    added.push(task);
+                }
+            },
+            (None, None) => {}
+        }
+    }
+
+    removed.sort_unstable();
+    for index in removed.into_iter().rev() {
+        data.tasks.remove(index);
+    }
+    for mut task in added {
+        task.id = data.tasks.len();
+        data.tasks.push(task);
+    }
+    data.sync_records = new_records;
+    Ok(report)
+}
+
+fn record_for(task: &Task, href: String, etag: String) -> SyncRecord {
+    SyncRecord {
+        uid: task.uid.clone().unwrap(),
+        href,
+        etag,
+        fingerprint: fingerprint(task),
+    }
+}
+
+/// Copies onto a local task the fields of its server version that VTODOs hold. The rest, such as
+/// its history, tracked time or board status, stays as it is locally.
+fn apply_remote(task: &mut Task, remote: Task, clock: &dyn Clock) {
+    if remote.completed() != task.completed() {
+        task.set_completed(remote.completed(), clock);
+    }
+    task.edit(remote.description());
+    task.creation_time = remote.creation_time;
+    task.due_date = remote.due_date;
+    task.priority = remote.priority;
+    task.completion_time = remote.completion_time;
+    task.tags = remote.tags;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::SystemClock;
+    use crate::time::Time;
+
+    /// Collection kept in memory, checking preconditions like a CalDAV server.
+    #[derive(Default)]
+    struct MockServer {
+        resources: BTreeMap<String, (String, String)>,
+        next_etag: usize,
+        requests: Vec<String>,
+    }
+
+    impl MockServer {
+        fn new_etag(&mut self) -> String {
+            self.next_etag += 1;
+            format!("\"{}\"", self.next_etag)
+        }
+
+        /// Change made by another client.
+        fn edit(&mut self, href: &str, edit: impl Fn(&mut Task)) {
+            let (_, contents) = &self.resources[href];
+            let mut task = ical::parse(contents, &SystemClock).unwrap().remove(0);
+            edit(&mut task);
+            let etag = self.new_etag();
+            self.resources.insert(
+                href.to_string(),
+                (etag, calendar(&task, &SystemClock.now())),
+            );
+        }
+
+        fn descriptions(&self) -> Vec<String> {
+            self.resources
+                .values()
+                .map(|(_, contents)| {
+                    ical::parse(contents, &SystemClock).unwrap()[0]
+                        .description()
+                        .to_string()
+                })
+                .collect()
+        }
+
+        fn precondition_failed() -> SyncError {
+            SyncError::new(SyncErrorKind::PreconditionFailed, "412".to_string())
+        }
+    }
+
+    impl CalDavServer for MockServer {
+        fn list(&mut self) -> Result<Vec<(String, String)>, SyncError> {
+            self.requests.push("REPORT".to_string());
+            Ok(self
+                .resources
+                .iter()
+                .map(|(href, (etag, _))| (href.clone(), etag.clone()))
+                .collect())
+        }
+
+        fn get(&mut self, href: &str) -> Result<(String, String), SyncError> {
+            self.requests.push(format!("GET {href}"));
+            let (etag, contents) = self
+                .resources
+                .get(href)
+                .cloned()
+                .ok_or_else(|| SyncError::new(SyncErrorKind::Server, "404".to_string()))?;
+            Ok((contents, etag))
+        }
+
+        fn href_for(&self, uid: &str) -> String {
+            format!("/calendar/{uid}.ics")
+        }
+
+        fn put(
+            &mut self,
+            href: &str,
+            calendar: &str,
+            precondition: Precondition,
+        ) -> Result<String, SyncError> {
+            self.requests.push(format!("PUT {href}"));
+            let current = self.resources.get(href).map(|(etag, _)| etag.as_str());
+            match (precondition, current) {
+                (Precondition::IfMatch(etag), Some(current)) if etag == current => {}
+                (Precondition::IfNoneMatch, None) => {}
+                _ => return Err(Self::precondition_failed()),
+            }
+            let etag = self.new_etag();
+            self.resources
+                .insert(href.to_string(), (etag.clone(), calendar.to_string()));
+            Ok(etag)
+        }
+
+        fn delete(&mut self, href: &str, etag: &str) -> Result<(), SyncError> {
+            self.requests.push(format!("DELETE {href}"));
+            match self.resources.get(href) {
+                Some((current, _)) if current == etag => {
+                    self.resources.remove(href);
+                    Ok(())
+                }
+                _ => Err(Self::precondition_failed()),
+            }
+        }
+    }
+
+    fn task_list(descriptions: &[&str]) -> Data {
+        let mut data = Data::default();
+        for (index, description) in descriptions.iter().enumerate() {
+            let mut task = Task::new(index, &SystemClock);
+            task.edit(description);
+            task.creation_time = Time::from("2024-05-15T10:30:00Z");
+            task.uid = Some(format!("task-{index}"));
+            data.tasks.push(task);
+        }
+        data
+    }
+
+    fn descriptions(data: &Data) -> Vec<&str> {
+        data.tasks.iter().map(Task::description).collect()
+    }
+
+    #[test]
+    fn first_synchronization_uploads_everything() {
+        let mut server = MockServer::default();
+        let mut data = task_list(&["A", "B"]);
+        data.tasks.push(Task::new(2, &SystemClock));
+        data.tasks[2].edit("Without UID");
+
+        let report = synchronize(&mut data, &mut server, &SystemClock).unwrap();
+        assert_eq!(report.uploaded, 3);
+        assert_eq!(server.resources.len(), 3);
+        assert!(server.resources.contains_key("/calendar/task-0.ics"));
+        assert_eq!(data.tasks[2].uid, Some(ical::uid(&data.tasks[2])));
+        assert_eq!(data.sync_records.len(), 3);
+
+        server.requests.clear();
+        let report = synchronize(&mut data, &mut server, &SystemClock).unwrap();
+        assert_eq!(report, SyncReport::default());
+        assert_eq!(server.requests, vec!["REPORT"]);
+    }
+
+    #[test]
+    fn tasks_created_at_the_same_time_stay_apart() {
+        let mut server = MockServer::default();
+        let clock = crate::clock::FakeClock::new(Time::from("2024-05-15T10:30:00Z"));
+        let mut data = Data::default();
+        for (index, description) in ["A", "B", "C"].iter().enumerate() {
+            data.tasks.push(Task::new(index, &clock));
+            data.tasks[index].edit(description);
+        }
+        // Tasks from older lists have no identifier until they are synchronized.
+        data.tasks[2].uid = None;
+
+        let report = synchronize(&mut data, &mut server, &clock).unwrap();
+        assert_eq!(report.uploaded, 3);
+        let mut descriptions = server.descriptions();
+        descriptions.sort();
+        assert_eq!(descriptions, vec!["A", "B", "C"]);
+    }
+
+    #[test]
+    fn archived_tasks_stay_on_the_server() {
+        let mut server = MockServer::default();
+        let mut data = task_list(&["A", "B"]);
+        synchronize(&mut data, &mut server, &SystemClock).unwrap();
+
+        let archived = data.tasks.remove(0);
+        data.archive.push(archived);
+        server.requests.clear();
+        let report = synchronize(&mut data, &mut server, &SystemClock).unwrap();
+        assert_eq!(report, SyncReport::default());
+        assert_eq!(server.resources.len(), 2);
+        assert_eq!(data.sync_records.len(), 2);
+        assert_eq!(descriptions(&data), vec!["B"]);
+    }
+
+    #[test]
+    fn changes_flow_both_ways() {
+        let mut server = MockServer::default();
+        let mut data = task_list(&["A", "B"]);
+        synchronize(&mut data, &mut server, &SystemClock).unwrap();
+
+        data.tasks[0].set_completed(true, &SystemClock);
+        server.edit("/calendar/task-1.ics", |task| {
+            task.edit("B, edited remotely")
+        });
+        let mut other = task_list(&["C"]);
+        other.tasks[0].uid = Some("task-2".to_string());
+        synchronize(&mut other, &mut server, &SystemClock).unwrap();
+
+        let report = synchronize(&mut data, &mut server, &SystemClock).unwrap();
+        assert_eq!(report.uploaded, 1);
+        assert_eq!(report.downloaded, 2);
+        assert_eq!(descriptions(&data), vec!["A", "B, edited remotely", "C"]);
+        assert_eq!(data.tasks[2].id, 2);
+        let uploaded =
+            ical::parse(&server.resources["/calendar/task-0.ics"].1, &SystemClock).unwrap();
+        assert!(uploaded[0].completed());
+    }
+
+    #[test]
+    fn deletions_flow_both_ways() {
+        let mut server = MockServer::default();
+        let mut data = task_list(&["A", "B", "C"]);
+        synchronize(&mut data, &mut server, &SystemClock).unwrap();
+
+        data.tasks.remove(0);
+        server.resources.remove("/calendar/task-1.ics");
+        let report = synchronize(&mut data, &mut server, &SystemClock).unwrap();
+
+        assert_eq!(report.deleted_on_server, 1);
+        assert_eq!(report.deleted_locally, 1);
+        assert_eq!(descriptions(&data), vec!["C"]);
+        assert_eq!(server.descriptions(), vec!["C"]);
+        assert_eq!(data.sync_records.len(), 1);
+    }
+
+    #[test]
+    fn changes_win_over_deletions() {
+        let mut server = MockServer::default();
+        let mut data = task_list(&["A", "B"]);
+        synchronize(&mut data, &mut server, &SystemClock).unwrap();
+
+        data.tasks.remove(0);
+        server.edit("/calendar/task-0.ics", |task| {
+            task.edit("A, edited remotely")
+        });
+        data.tasks[0].edit("B, edited locally");
+        server.resources.remove("/calendar/task-1.ics");
+        let report = synchronize(&mut data, &mut server, &SystemClock).unwrap();
+
+        assert_eq!(report.downloaded, 1);
+        assert_eq!(report.uploaded, 1);
+        assert_eq!(
+            descriptions(&data),
+            vec!["B, edited locally", "A, edited remotely"]
+        );
+        assert_eq!(
+            server.descriptions(),
+            vec!["A, edited remotely", "B, edited locally"]
+        );
+    }
+
+    #[test]
+    fn downloads_keep_what_calendars_do_not_hold() {
+        let mut server = MockServer::default();
+        let mut data = task_list(&["A"]);
+        synchronize(&mut data, &mut server, &SystemClock).unwrap();
+
+        let start = Time::from("2024-05-15T11:00:00Z");
+        let task = &mut data.tasks[0];
+        task.start_timer(&start);
+        task.stop_timer(&(&start + &crate::time::Duration::from_hours(1)));
+        task.record(crate::task::EventKind::Completed, &SystemClock);
+        task.pomodoros.push(start.clone());
+        task.status = Some("Review".to_string());
+        task.pinned_to = Some(start.start_of_day());
+        let local = task.clone();
+        synchronize(&mut data, &mut server, &SystemClock).unwrap();
+        server.edit("/calendar/task-0.ics", |task| {
+            task.edit("A, edited remotely")
+        });
+
+        let report = synchronize(&mut data, &mut server, &SystemClock).unwrap();
+        assert_eq!(report.downloaded, 1);
+        let task = &data.tasks[0];
+        assert_eq!(task.description(), "A, edited remotely");
+        assert_eq!(task.time_entries, local.time_entries);
+        assert_eq!(task.history, local.history);
+        assert_eq!(task.pomodoros, local.pomodoros);
+        assert_eq!(task.status, local.status);
+        assert_eq!(task.pinned_to, local.pinned_to);
+    }
+
+    #[test]
+    fn conflicts_keep_both_versions() {
+        let mut server = MockServer::default();
+        let mut data = task_list(&["A"]);
+        synchronize(&mut data, &mut server, &SystemClock).unwrap();
+
+        data.tasks[0].edit("A, edited locally");
+        server.edit("/calendar/task-0.ics", |task| {
+            task.edit("A, edited remotely")
+        });
+        let report = synchronize(&mut data, &mut server, &SystemClock).unwrap();
+
+        assert_eq!(report.conflicts, vec!["A, edited remotely"]);
+        assert_eq!(
+            descriptions(&data),
+            vec!["A, edited remotely", "A, edited locally (conflict)"]
+        );
+        assert_ne!(data.tasks[1].uid, data.tasks[0].uid);
+
+        synchronize(&mut data, &mut server, &SystemClock).unwrap();
+        assert_eq!(server.resources.len(), 2);
+        assert_eq!(
+            synchronize(&mut data, &mut server, &SystemClock).unwrap(),
+            SyncReport::default()
+        );
+    }
+
+    #[test]
+    fn concurrent_server_changes_are_retried() {
+        /// Server where another client edits a resource between listing and writing.
+        struct RacingServer(MockServer);
+
+        impl CalDavServer for RacingServer {
+            fn list(&mut self) -> Result<Vec<(String, String)>, SyncError> {
+                let listing = self.0.list();
+                self.0.edit("/calendar/task-0.ics", |task| {
+                    task.edit("A, edited remotely")
+                });
+                listing
+            }
+            fn get(&mut self, href: &str) -> Result<(String, String), SyncError> {
+                self.0.get(href)
+            }
+            fn href_for(&self, uid: &str) -> String {
+                self.0.href_for(uid)
+            }
+            fn put(
+                &mut self,
+                href: &str,
+                calendar: &str,
+                precondition: Precondition,
+            ) -> Result<String, SyncError> {
+                self.0.put(href, calendar, precondition)
+            }
+            fn delete(&mut self, href: &str, etag: &str) -> Result<(), SyncError> {
+                self.0.delete(href, etag)
+            }
+        }
+
+        let mut server = MockServer::default();
+        let mut data = task_list(&["A"]);
+        synchronize(&mut data, &mut server, &SystemClock).unwrap();
+
+        data.tasks[0].edit("A, edited locally");
+        let mut racing = RacingServer(server);
+        let report = synchronize(&mut data, &mut racing, &SystemClock).unwrap();
+        assert_eq!(report.retries, vec!["A, edited locally"]);
+        assert_eq!(racing.0.descriptions(), vec!["A, edited remotely"]);
+
+        let report = synchronize(&mut data, &mut racing.0, &SystemClock).unwrap();
+        assert_eq!(report.conflicts.len(), 1);
+    }
+
+    #[test]
+    fn outcome_does_not_depend_on_order() {
+        let synchronized = |reversed: bool| {
+            let mut server = MockServer::default();
+            let mut data = task_list(&["A", "B"]);
+            synchronize(&mut data, &mut server, &SystemClock).unwrap();
+            data.tasks[0].edit("A, edited locally");
+            data.tasks[1].edit("B, edited locally");
+            server.edit("/calendar/task-0.ics", |task| {
+                task.edit("A, edited remotely")
+            });
+            if reversed {
+                data.tasks.reverse();
+            }
+            let report = synchronize(&mut data, &mut server, &SystemClock).unwrap();
+            (report, server.resources)
+        };
+        let (report, resources) = synchronized(false);
+        assert_eq!(report.conflicts, vec!["A, edited remotely"]);
+        assert_eq!(report.uploaded, 1);
+        let (reversed_report, reversed_resources) = synchronized(true);
+        assert_eq!(reversed_report, report);
+        assert_eq!(
+            reversed_resources.keys().collect::<Vec<_>>(),
+            resources.keys().collect::<Vec<_>>()
+        );
+    }
+}
@@ -0,0 +1,283 @@
+use super::{CalDavServer, Precondition, SyncError, SyncErrorKind};
+
+const CALENDAR_QUERY: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<c:calendar-query xmlns:d="DAV:" xmlns:c="urn:ietf:params:xml:ns:caldav">
+  <d:prop><d:getetag/></d:prop>
+  <c:filter>
+    <c:comp-filter name="VCALENDAR"><c:comp-filter name="VTODO"/></c:comp-filter>
+  </c:filter>
+</c:calendar-query>"#;
+
+/// A CalDAV calendar collection on an HTTP server, such as
+/// `https://example.com/dav/calendars/ann/tasks/`.
+pub(crate) struct HttpServer {
+    agent: ureq::Agent,
+    collection: String,
+    authorization: Option<String>,
+}
+
+impl HttpServer {
+    pub(crate) fn new(collection: &str, user: Option<&str>, password: Option<&str>) -> Self {
+        let mut collection = collection.to_string();
+        if !collection.ends_with('/') {
+            collection.push('/');
+        }
+        let authorization = user.map(|user| {
+            let credentials = format!("{user}:{}", password.unwrap_or_default());
+            format!("Basic {}", base64(credentials.as_bytes()))
+        });
+        HttpServer {
+            agent: ureq::Agent::new(),
+            collection,
+            authorization,
+        }
+    }
+
+    /// Absolute URL of an href, which servers usually give as an absolute path.
+    fn url(&self, href: &str) -> String {
+        if href.starts_with("http://") || href.starts_with("https://") {
+            return href.to_string();
+        }
+        let path_start = self
+            .collection
+            .find("://")
+            .and_then(|scheme_end| {
+                let host_start = scheme_end + 3;
+                self.collection[host_start..]
+                    .find('/')
+                    .map(|index| host_start + index)
+            })
+            .unwrap_or(self.collection.len());
+        match href.strip_prefix('/') {
+            Some(_) => format!("{}{href}", &self.collection[..path_start]),
+            None => format!("{}{href}", self.collection),
+        }
+    }
+
+    fn path(&self) -> &str {
+        let url = self.url("/");
+        &self.collection[url.len() - 1..]
+    }
+
+    fn request(&self, method: &str, href: &str) -> ureq::Request {
+        let request = self.agent.request(method, &self.url(href));
+        match &self.authorization {
+            Some(authorization) => request.set("Authorization", authorization),
+            None => request,
+        }
+    }
+}
+
+fn error(error: ureq::Error) -> SyncError {
+    match error {
+        ureq::Error::Status(412, _) => SyncError::new(
+            SyncErrorKind::PreconditionFailed,
+            "The resource changed on the server.".to_string(),
+        ),
+        ureq::Error::Status(code, response) => SyncError::new(
+            SyncErrorKind::Server,
+            format!("The server answered {code} {}.", response.status_text()),
+        ),
+        ureq::Error::Transport(transport) => {
+            SyncError::new(SyncErrorKind::Network, transport.to_string())
+        }
+    }
+}
+
+fn body(response: ureq::Response) -> Result<String, SyncError> {
+    response
+        .into_string()
+        .map_err(|error| SyncError::new(SyncErrorKind::Network, error.to_string()))
+}
+
+impl CalDavServer for HttpServer {
+    fn list(&mut self) -> Result<Vec<(String, String)>, SyncError> {
+        let response = self
+            .request("REPORT", "")
+            .set("Depth", "1")
+            .set("Content-Type", "application/xml; charset=utf-8")
+            .send_string(CALENDAR_QUERY)
+            .map_err(error)?;
+        let collection = self.path().to_string();
+        Ok(parse_multistatus(&body(response)?)
+            .into_iter()
+            .filter(|(href, _)| href.trim_end_matches('/') != collection.trim_end_matches('/'))
+            .collect())
+    }
+
+    fn get(&mut self, href: &str) -> Result<(String, String), SyncError> {
+        let response = self.request("GET", href).call().map_err(error)?;
+        let etag = response.header("ETag").unwrap_or_default().to_string();
+        Ok((body(response)?, etag))
+    }
+
+    fn href_for(&self, uid: &str) -> String {
+        let name = uid
+            .chars()
+            .map(|c| match c {
+                'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' | '.' => c,
+                _ => '_',
+            })
+            .collect::<String>();
+        format!("{}{name}.ics", self.path())
+    }
+
+    fn put(
+        &mut self,
+        href: &str,
+        calendar: &str,
+        precondition: Precondition,
+    ) -> Result<String, SyncError> {
+        let request = self
+            .request("PUT", href)
+            .set("Content-Type", "text/calendar; charset=utf-8");
+        let request = match precondition {
+            Precondition::IfMatch(etag) => request.set("If-Match", etag),
+            Precondition::IfNoneMatch => request.set("If-None-Match", "*"),
+        };
+        let response = request.send_string(calendar).map_err(error)?;
+        Ok(response.header("ETag").unwrap_or_default().to_string())
+    }
+
+    fn delete(&mut self, href: &str, etag: &str) -> Result<(), SyncError> {
+        self.request("DELETE", href)
+            .set("If-Match", etag)
+            .call()
+            .map_err(error)?;
+        Ok(())
+    }
+}
+
+/// Href and ETag of each response in a WebDAV multistatus document, whatever the namespace
+/// prefixes. Responses without an ETag, such as the one for the collection, are skipped.
+fn parse_multistatus(xml: &str) -> Vec<(String, String)> {
+    elements(xml, "response")
+        .into_iter()
+        .filter_map(|response| {
+            let href = elements(response, "href").into_iter().next()?;
+            let etag = elements(response, "getetag").into_iter().next()?;
+            Some((unescape(href.trim()), unescape(etag.trim())))
+        })
+        .collect()
+}
+
+/// Contents of the elements named `name`, ignoring their namespace prefix.
+fn elements<'a>(xml: &'a str, name: &str) -> Vec<&'a str> {
+    let local_name = |tag: &str| -> String {
+        let tag_name = tag
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .next()
+            .unwrap_or_default();
+        tag_name.rsplit(':').next().unwrap_or_default().to_string()
+    };
+    let mut contents = vec![];
+    let mut rest = xml;
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start + 1..];
+        let Some(end) = rest.find('>') else { break };
+        let tag = &rest[..end];
+        rest = &rest[end + 1..];
+        if tag.starts_with('/') || tag.ends_with('/') || local_name(tag) != name {
+            continue;
+        }
+        let mut depth = 1;
+        let mut position = 0;
+        while let Some(next) = rest[position..].find('<') {
+            let tag_start = position + next + 1;
+            let Some(tag_end) = rest[tag_start..].find('>') else {
+                break;
+            };
+            let inner = &rest[tag_start..tag_start + tag_end];
+            if let Some(closing) = inner.strip_prefix('/') {
+                if local_name(closing) == name {
+                    depth -= 1;
+                    if depth == 0 {
+                        contents.push(&rest[..tag_start - 1]);
+                        rest = &rest[tag_start + tag_end + 1..];
+                        break;
+                    }
+                }
+            } else if !inner.ends_with('/') && local_name(inner) == name {
+                depth += 1;
+            }
+            position = tag_start + tag_end + 1;
+        }
+    }
+    contents
+}
+
+fn unescape(text: &str) -> String {
+    text.replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::new();
+    for chunk in bytes.chunks(3) {
+        let value = chunk.iter().enumerate().fold(0u32, |value, (index, byte)| {
+            value | (*byte as u32) << (16 - 8 * index)
+        });
+        for index in 0..4 {
+            if index <= chunk.len() {
+                encoded.push(ALPHABET[(value >> (18 - 6 * index) & 63) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn multistatus() {
+        let xml = r#"<?xml version="1.0" encoding="utf-8"?>
+<D:multistatus xmlns:D="DAV:">
+  <D:response>
+    <D:href>/dav/tasks/</D:href>
+    <D:propstat><D:prop><D:resourcetype><D:collection/></D:resourcetype></D:prop></D:propstat>
+  </D:response>
+  <D:response>
+    <D:href>/dav/tasks/a.ics</D:href>
+    <D:propstat>
+      <D:prop><D:getetag>&quot;1&quot;</D:getetag></D:prop>
+      <D:status>HTTP/1.1 200 OK</D:status>
+    </D:propstat>
+  </D:response>
+  <response xmlns="DAV:"><href>/dav/tasks/b%20c.ics</href>
+    <propstat><prop><getetag>"2"</getetag></prop></propstat></response>
+</D:multistatus>"#;
+        assert_eq!(
+            parse_multistatus(xml),
+            vec![
+                ("/dav/tasks/a.ics".to_string(), "\"1\"".to_string()),
+                ("/dav/tasks/b%20c.ics".to_string(), "\"2\"".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn urls() {
+        let server = HttpServer::new("https://example.com/dav/tasks", Some("ann"), Some("pw"));
+        assert_eq!(
+            server.url("/dav/tasks/a.ics"),
+            "https://example.com/dav/tasks/a.ics"
+        );
+        assert_eq!(server.url("a.ics"), "https://example.com/dav/tasks/a.ics");
+        assert_eq!(server.url("http://other/a.ics"), "http://other/a.ics");
+        assert_eq!(
+            server.href_for("abc@organizer"),
+            "/dav/tasks/abc_organizer.ics"
+        );
+        assert_eq!(server.authorization.as_deref(), Some("Basic YW5uOnB3"));
+        assert_eq!(base64(b"Ma"), "TWE=");
+        assert_eq!(base64(b"M"), "TQ==");
+    }
+}
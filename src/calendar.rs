@@ -0,0 +1,100 @@
+use crate::data::Data;
+use crate::time::Time;
+
+/// Something shown on a day of the calendar.
+#[derive(Debug, PartialEq, Clone)]
+pub(crate) enum Entry {
+    /// Index of a pending task due on the day.
+    Due(usize),
+    /// Index of a task completed on the day.
+    Completed(usize),
+    /// Index of a periodic task with a task to be created on the day.
+    Occurrence(usize),
+}
+
+/// A day of the calendar with its entries: due tasks, then completed tasks and then periodic
+/// task occurrences, each in list order.
+#[derive(Debug, PartialEq)]
+pub(crate) struct Day {
+    pub start: Time,
+    pub entries: Vec<Entry>,
+}
+
+/// The `count` days from the one of `first_day`, with the tasks and periodic task occurrences
+/// falling on each.
+pub(crate) fn days(data: &Data, first_day: &Time, count: usize) -> Vec<Day> {
+    let first_day = first_day.start_of_day();
+    let end = first_day.add_days(count as i64);
+    let mut days = (0..count as i64)
+        .map(|offset| Day {
+            start: first_day.add_days(offset),
+            entries: vec![],
+        })
+        .collect::<Vec<_>>();
+    let mut place = |time: &Time, entry: Entry| {
+        if let Some(day) = days
+            .iter_mut()
+            .rev()
+            .find(|day| &day.start <= time && time < &end)
+        {
+            day.entries.push(entry);
+        }
+    };
+
+    for (index, task) in data.tasks.iter().enumerate() {
+        if let (false, Some(due_date)) = (task.completed(), &task.due_date) {
+            place(due_date, Entry::Due(index));
+        }
+    }
+    for (index, task) in data.tasks.iter().enumerate() {
+        if let (true, Some(completion_time)) = (task.completed(), &task.completion_time) {
+            place(completion_time, Entry::Completed(index));
+        }
+    }
+    for (index, periodic_task) in data.periodic_tasks.iter().enumerate() {
+        for occurrence in periodic_task.occurrences_between(&first_day, &end) {
+            place(&occurrence, Entry::Occurrence(index));
+        }
+    }
+    days
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::FakeClock;
+    use crate::periodic_task::{PeriodicTask, TimePeriod};
+    use crate::task::Task;
+    use crate::time::test_time as time;
+
+    #[test]
+    fn entries_by_day() {
+        let clock = FakeClock::new(time(10, 9));
+        let mut data = Data::default();
+        let mut due = Task::new(0, &clock);
+        due.due_date = Some(time(14, 17));
+        let mut completed_due = Task::new(1, &clock);
+        completed_due.due_date = Some(time(14, 17));
+        completed_due.set_completed(true, &FakeClock::new(time(13, 23)));
+        let mut outside = Task::new(2, &clock);
+        outside.due_date = Some(time(20, 9));
+        data.tasks = vec![due, completed_due, outside];
+        data.periodic_tasks = vec![PeriodicTask::recurring(
+            "Review".to_string(),
+            TimePeriod::Weekly,
+            time(6, 9),
+            vec![],
+            None,
+        )];
+
+        let days = days(&data, &time(13, 12), 7);
+        assert_eq!(days.len(), 7);
+        assert_eq!(days[0].start, time(13, 0));
+        assert_eq!(
+            days[0].entries,
+            vec![Entry::Completed(1), Entry::Occurrence(0)]
+        );
+        assert_eq!(days[1].entries, vec![Entry::Due(0)]);
+        assert!(days[2..].iter().all(|day| day.entries.is_empty()));
+    }
+}
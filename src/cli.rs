@@ -0,0 +1,550 @@
+use crate::caldav::{self, HttpServer};
+use crate::clock::{Clock, SystemClock};
+use crate::data::{Data, FileError, FileErrorKind};
+use crate::export::{export_to_file, Grouping};
+use crate::import::{Import, ImportFormat};
+use crate::lock::{self, Lock};
+use crate::quick_add::QuickAdd;
+use crate::storage::{self, Storage};
+use crate::time::Time;
+use crate::Organizer;
+
+/// Environment variable holding the passphrase of encrypted lists.
+const PASSPHRASE_VARIABLE: &str = "ORGANIZER_PASSPHRASE";
+
+const USAGE: &str = "Usage:
+  organizer                             Open the task list in the working directory.
+  organizer add <quick-add text>        Add a task, e.g. 'Review PR #backend !high due:fri'.
+  organizer export <report>             Export the visible tasks to a .md, .csv, .html, .json or .ics
+                                        file. Calendars also get the periodic tasks.
+      [--from <date>] [--to <date>]     Export the tasks completed in this period instead. --to
+                                        defaults to now.
+      [--group-by none|day|tag]         Group the exported tasks.
+  organizer import <file>               Import the tasks in a todo.txt (.txt), Taskwarrior export
+                                        (.json), CSV (.csv) or iCalendar (.ics) file, skipping
+                                        duplicates.
+      [--format todotxt|taskwarrior|csv|ics] Format of the file, instead of guessing from its name.
+      [--map <field>=<column>]          CSV column holding a task field (description, completed,
+                                        creation_time, completion_time, due_date, priority or
+                                        tags). Can be repeated.
+      [--dry-run]                       Show what would be imported without saving.
+  organizer sync <collection-url>       Synchronize the tasks with a CalDAV task list, such as
+                                        https://example.com/dav/calendars/ann/tasks/.
+      [--user <name>]                   User name for the server.
+      [--password-env <variable>]       Environment variable holding the password. Defaults to
+                                        ORGANIZER_CALDAV_PASSWORD.
+  organizer merge <base> <ours> <theirs> [<path>]
+                                        Merge the changes made in two copies of a list since their
+                                        common ancestor into <ours>, in the format given by the
+                                        extension of <path> or <ours>. Fails if some tasks changed
+                                        on both sides, which are then left to resolve in the Merge
+                                        Conflicts view. To merge lists in git, add
+                                          [merge \"organizer\"] driver = organizer merge %O %A %B %P
+                                        to .git/config and '*.ogz merge=organizer' to
+                                        .gitattributes.
+
+Options:
+  --file <list.ogz|organizer.json|todo.txt|list.db>
+                                        Task list to use instead of the one in the working directory.
+                                        .json files are pretty-printed to be kept in git, a todo.txt
+                                        list keeps completed tasks in done.txt (<name>.done.txt for
+                                        other .txt lists), and .db files are SQLite databases.
+                                        .ogz and .json lists encrypted in the
+                                        application are decrypted with the passphrase in the
+                                        ORGANIZER_PASSPHRASE environment variable.";
+
+/// Runs the command line interface with the arguments following the program name. Returns the
+/// text to show to the user.
+pub fn run(arguments: &[String]) -> Result<String, String> {
+    let (file_name, arguments) = extract_file_option(arguments)?;
+
+    match arguments.split_first() {
+        Some((command, rest)) if command == "add" => {
+            let file_name = list_file_name(file_name)?;
+            let _lock = lock(&file_name)?;
+            let (mut storage, mut data) = load_or_default(&file_name)?;
+            let quick_add =
+                QuickAdd::parse(&rest.join(" "), &SystemClock).map_err(|error| error.message)?;
+            quick_add.add_to(&mut data);
+            save(storage.as_mut(), &data)?;
+            Ok(format!("Added to {file_name}."))
+        }
+        Some((command, rest)) if command == "export" => export(list_file_name(file_name)?, rest),
+        Some((command, rest)) if command == "import" => import(list_file_name(file_name)?, rest),
+        Some((command, rest)) if command == "sync" => sync(list_file_name(file_name)?, rest),
+        Some((command, rest)) if command == "merge" => merge(rest),
+        Some((command, _)) if command == "help" || command == "--help" => Ok(USAGE.to_string()),
+        Some((command, _)) => Err(format!("Unknown command '{command}'.\n\n{USAGE}")),
+        None => Err(USAGE.to_string()),
+    }
+}
+
+fn export(file_name: String, arguments: &[String]) -> Result<String, String> {
+    let mut report_name = None;
+    let mut from = None;
+    let mut to = None;
+    let mut grouping = Grouping::None;
+    let now = SystemClock.now();
+    let parse_date = |date: &str| {
+        Time::from_natural_language(date, &now).map_err(|_| format!("Unrecognized date '{date}'."))
+    };
+
+    let mut arguments = arguments.iter();
+    while let Some(argument) = arguments.next() {
+        let mut value = || {
+            arguments
+                .next()
+                .ok_or_else(|| format!("Missing value for {argument}."))
+        };
+        match argument.as_str() {
+            "--from" => from = Some(parse_date(value()?)?),
+            "--to" => {
+                let date = value()?;
+                let end = Time::end_from_natural_language(date, &now)
+                    .map_err(|_| format!("Unrecognized date '{date}'."))?;
+                to = Some(end);
+            }
+            "--group-by" => {
+                let value = value()?;
+                grouping =
+                    Grouping::parse(value).ok_or_else(|| format!("Unknown grouping '{value}'."))?;
+            }
+            _ if report_name.is_none() => report_name = Some(argument.clone()),
+            _ => return Err(format!("Unexpected argument '{argument}'.\n\n{USAGE}")),
+        }
+    }
+    let report_name = report_name.ok_or_else(|| format!("Missing export file.\n\n{USAGE}"))?;
+
+    let (_, data) = load(&file_name)?;
+    let (tasks, periodic_tasks) = if from.is_some() || to.is_some() {
+        let from = from.ok_or_else(|| "--to needs --from.".to_string())?;
+        (data.completed_between(&from, &to.unwrap_or(now)), &[][..])
+    } else {
+        let tasks = data
+            .visible_tasks(&SystemClock)
+            .into_iter()
+            .map(|(_, task)| task)
+            .collect();
+        (tasks, &data.periodic_tasks[..])
+    };
+    export_to_file(&tasks, periodic_tasks, &report_name, grouping, &SystemClock)
+        .map_err(|error| format!("{:?} problem: {}", error.kind, error.message))?;
+    Ok(format!("Exported {} tasks to {report_name}.", tasks.len()))
+}
+
+fn import(file_name: String, arguments: &[String]) -> Result<String, String> {
+    let mut source_name: Option<String> = None;
+    let mut format = None;
+    let mut mapping = vec![];
+    let mut dry_run = false;
+
+    let mut arguments = arguments.iter();
+    while let Some(argument) = arguments.next() {
+        let mut value = || {
+            arguments
+                .next()
+                .ok_or_else(|| format!("Missing value for {argument}."))
+        };
+        match argument.as_str() {
+            "--format" => {
+                let value = value()?;
+                format = Some(
+                    ImportFormat::parse(value)
+                        .ok_or_else(|| format!("Unknown import format '{value}'."))?,
+                );
+            }
+            "--map" => mapping.push(value()?.clone()),
+            "--dry-run" => dry_run = true,
+            _ if source_name.is_none() => source_name = Some(argument.clone()),
+            _ => return Err(format!("Unexpected argument '{argument}'.\n\n{USAGE}")),
+        }
+    }
+    let source_name = source_name.ok_or_else(|| format!("Missing file to import.\n\n{USAGE}"))?;
+    let format = format
+        .or_else(|| ImportFormat::from_file_name(&source_name))
+        .ok_or_else(|| format!("Unknown format of {source_name}. Use --format."))?;
+
+    let text = std::fs::read_to_string(&source_name)
+        .map_err(|error| format!("Problem reading {source_name}: {error}"))?;
+    let tasks = crate::import::parse(&text, format, &mapping, &SystemClock)
+        .map_err(|error| error.message)?;
+
+    let _lock = match dry_run {
+        true => None,
+        false => Some(lock(&file_name)?),
+    };
+    let (mut storage, mut data) = load_or_default(&file_name)?;
+    let import = Import::new(tasks, &data);
+    if dry_run {
+        return Ok(import.preview());
+    }
+    let (imported, duplicates) = (import.tasks.len(), import.duplicates.len());
+    import.add_to(&mut data);
+    save(storage.as_mut(), &data)?;
+    Ok(format!(
+        "Imported {imported} tasks into {file_name}, skipped {duplicates} duplicates."
+    ))
+}
+
+fn sync(file_name: String, arguments: &[String]) -> Result<String, String> {
+    let mut collection = None;
+    let mut user = None;
+    let mut password_variable = "ORGANIZER_CALDAV_PASSWORD".to_string();
+
+    let mut arguments = arguments.iter();
+    while let Some(argument) = arguments.next() {
+        let mut value = || {
+            arguments
+                .next()
+                .ok_or_else(|| format!("Missing value for {argument}."))
+        };
+        match argument.as_str() {
+            "--user" => user = Some(value()?.clone()),
+            "--password-env" => password_variable = value()?.clone(),
+            _ if collection.is_none() => collection = Some(argument.clone()),
+            _ => return Err(format!("Unexpected argument '{argument}'.\n\n{USAGE}")),
+        }
+    }
+    let collection = collection.ok_or_else(|| format!("Missing collection URL.\n\n{USAGE}"))?;
+    let password = std::env::var(&password_variable).ok();
+
+    let _lock = lock(&file_name)?;
+    let (mut storage, mut data) = load_or_default(&file_name)?;
+    let mut server = HttpServer::new(&collection, user.as_deref(), password.as_deref());
+    let report = caldav::synchronize(&mut data, &mut server, &SystemClock)
+        .map_err(|error| format!("{:?} problem: {}", error.kind, error.message))?;
+    save(storage.as_mut(), &data)?;
+    Ok(report.to_string())
+}
+
+fn merge(arguments: &[String]) -> Result<String, String> {
+    let [base_name, our_name, their_name, rest @ ..] = arguments else {
+        return Err(format!("Missing files to merge.\n\n{USAGE}"));
+    };
+    let format_file_name = match rest {
+        [] => our_name,
+        [path] => path,
+        [_, argument, ..] => return Err(format!("Unexpected argument '{argument}'.\n\n{USAGE}")),
+    };
+    let _lock = lock(our_name)?;
+    let load = |file_name: &str| {
+        storage::open_as(file_name, format_file_name)
+            .and_then(|mut storage| storage.load())
+            .map_err(|error| format!("{:?} problem: {}", error.kind, error.message))
+    };
+    let (base, ours, theirs) = (load(base_name)?, load(our_name)?, load(their_name)?);
+
+    let merged = crate::merge::merge(&base, &ours, &theirs);
+    storage::open_as(our_name, format_file_name)
+        .and_then(|mut storage| storage.save(&merged))
+        .map_err(|error| format!("{:?} problem: {}", error.kind, error.message))?;
+    match merged.conflicts.len() {
+        0 => Ok(format!("Merged into {our_name}.")),
+        conflicts => Err(format!(
+            "Merged into {our_name} with {conflicts} conflicts. Resolve them in the Merge \
+             Conflicts view."
+        )),
+    }
+}
+
+fn list_file_name(file_name: Option<String>) -> Result<String, String> {
+    file_name
+        .or_else(Organizer::search_for_file_in_working_directory)
+        .ok_or_else(|| {
+            "No .ogz, organizer.json or todo.txt file found in the working directory. Use --file."
+                .to_string()
+        })
+}
+
+fn extract_file_option(arguments: &[String]) -> Result<(Option<String>, Vec<String>), String> {
+    let mut file_name = None;
+    let mut remaining = vec![];
+    let mut arguments = arguments.iter();
+    while let Some(argument) = arguments.next() {
+        if argument == "--file" {
+            let value = arguments
+                .next()
+                .ok_or_else(|| "Missing value for --file.".to_string())?;
+            file_name = Some(value.clone());
+        } else {
+            remaining.push(argument.clone());
+        }
+    }
+    Ok((file_name, remaining))
+}
+
+/// Loads a list, decrypting it with the passphrase in `ORGANIZER_PASSPHRASE` if it is encrypted.
+fn load(file_name: &str) -> Result<(Box<dyn Storage>, Data), String> {
+    let problem = |error: FileError| format!("{:?} problem: {}", error.kind, error.message);
+    let mut storage = storage::open(file_name).map_err(problem)?;
+    let data = match storage.load() {
+        Err(error) if matches!(error.kind, FileErrorKind::Decryption) => {
+            let passphrase = std::env::var(PASSPHRASE_VARIABLE).map_err(|_| {
+                format!("{file_name} is encrypted. Set {PASSPHRASE_VARIABLE} to its passphrase.")
+            })?;
+            storage.set_passphrase(Some(passphrase)).map_err(problem)?;
+            storage.load().map_err(problem)?
+        }
+        result => result.map_err(problem)?,
+    };
+    data.settings.display_timezone().set();
+    Ok((storage, data))
+}
+
+fn load_or_default(file_name: &str) -> Result<(Box<dyn Storage>, Data), String> {
+    if std::path::Path::new(file_name).exists() {
+        load(file_name)
+    } else {
+        let storage = storage::open(file_name)
+            .map_err(|error| format!("{:?} problem: {}", error.kind, error.message))?;
+        Ok((storage, Data::default()))
+    }
+}
+
+/// Locks the list in `file_name` while a command changes it, failing if the application or
+/// another command has it open.
+fn lock(file_name: &str) -> Result<Lock, String> {
+    lock::acquire(file_name)
+        .map_err(|holder| format!("{file_name} is open in {holder}. Close it and try again."))
+}
+
+fn save(storage: &mut dyn Storage, data: &Data) -> Result<(), String> {
+    storage
+        .save(data)
+        .map_err(|error| format!("{:?} problem: {}", error.kind, error.message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::Task;
+
+    fn arguments(arguments: &[&str]) -> Vec<String> {
+        arguments
+            .iter()
+            .map(|argument| argument.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn add_task_to_file() {
+        let file_name = std::env::temp_dir().join("test_cli_add.ogz");
+        let file_name = file_name.to_str().unwrap();
+        let _ = std::fs::remove_file(file_name);
+
+        run(&arguments(&[
+            "add", "Buy", "milk", "#home", "--file", file_name,
+        ]))
+        .unwrap();
+        run(&arguments(&[
+            "--file",
+            file_name,
+            "add",
+            "Water plants every:week",
+        ]))
+        .unwrap();
+
+        let data = Data::load(file_name).unwrap();
+        assert_eq!(data.tasks.len(), 1);
+        assert_eq!(data.tasks[0].description(), "Buy milk");
+        assert_eq!(data.tasks[0].tags, vec!["home"]);
+        assert_eq!(data.periodic_tasks.len(), 1);
+    }
+
+    #[test]
+    fn refuse_to_change_open_lists() {
+        let file_name = std::env::temp_dir().join("test_cli_locked.ogz");
+        let file_name = file_name.to_str().unwrap();
+        Data::default().save(file_name).unwrap();
+        let holder = crate::lock::LockHolder {
+            pid: 1,
+            hostname: "other host".to_string(),
+            since: Time::now(),
+        };
+        let lock_file = format!("{file_name}.lock");
+        std::fs::write(&lock_file, serde_json::to_string(&holder).unwrap()).unwrap();
+
+        let error = run(&arguments(&["add", "Buy milk", "--file", file_name])).unwrap_err();
+        assert!(error.starts_with(&format!("{file_name} is open in process 1 on other host")));
+        assert_eq!(Data::load(file_name).unwrap(), Data::default());
+
+        std::fs::remove_file(lock_file).unwrap();
+        run(&arguments(&["add", "Buy milk", "--file", file_name])).unwrap();
+        assert_eq!(Data::load(file_name).unwrap().tasks.len(), 1);
+        assert!(!std::path::Path::new(&format!("{file_name}.lock")).exists());
+    }
+
+    #[test]
+    fn export_tasks() {
+        let file_name = std::env::temp_dir().join("test_cli_export.ogz");
+        let file_name = file_name.to_str().unwrap();
+        let report_name = std::env::temp_dir().join("test_cli_export.md");
+        let report_name = report_name.to_str().unwrap();
+        let _ = std::fs::remove_file(file_name);
+
+        run(&arguments(&["--file", file_name, "add", "Open task #home"])).unwrap();
+        run(&arguments(&["--file", file_name, "add", "Done task #work"])).unwrap();
+        let mut data = Data::load(file_name).unwrap();
+        data.tasks[1].set_completed(true, &SystemClock);
+        data.tasks[1].completion_time = Some(&Time::now() - &crate::time::Duration::from_hours(48));
+        data.save(file_name).unwrap();
+
+        let output = run(&arguments(&[
+            "--file",
+            file_name,
+            "export",
+            report_name,
+            "--group-by",
+            "tag",
+        ]))
+        .unwrap();
+        assert_eq!(output, format!("Exported 1 tasks to {report_name}."));
+        let report = std::fs::read_to_string(report_name).unwrap();
+        assert!(report.contains("## #home\n\n- [ ] Open task"));
+
+        run(&arguments(&[
+            "--file",
+            file_name,
+            "export",
+            report_name,
+            "--from",
+            "3 days ago",
+        ]))
+        .unwrap();
+        let report = std::fs::read_to_string(report_name).unwrap();
+        assert!(report.contains("- [x] Done task"));
+        assert!(!report.contains("Open task"));
+
+        assert!(run(&arguments(&["--file", file_name, "export"])).is_err());
+        assert!(run(&arguments(&[
+            "--file",
+            file_name,
+            "export",
+            report_name,
+            "--group-by",
+            "month"
+        ]))
+        .is_err());
+    }
+
+    #[test]
+    fn import_tasks() {
+        let file_name = std::env::temp_dir().join("test_cli_import.ogz");
+        let file_name = file_name.to_str().unwrap();
+        let source_name = std::env::temp_dir().join("test_cli_import.txt");
+        let source_name = source_name.to_str().unwrap();
+        let _ = std::fs::remove_file(file_name);
+        std::fs::write(source_name, "(A) Call Ann +work\nx 2024-05-15 Pay rent\n").unwrap();
+
+        let preview = run(&arguments(&[
+            "--file",
+            file_name,
+            "import",
+            source_name,
+            "--dry-run",
+        ]))
+        .unwrap();
+        assert!(preview.starts_with("Tasks to import: 2\n"));
+        assert!(!std::path::Path::new(file_name).exists());
+
+        run(&arguments(&["--file", file_name, "import", source_name])).unwrap();
+        let output = run(&arguments(&["--file", file_name, "import", source_name])).unwrap();
+        assert!(output.ends_with("skipped 2 duplicates."));
+
+        let data = Data::load(file_name).unwrap();
+        assert_eq!(data.tasks.len(), 2);
+        assert_eq!(data.tasks[0].tags, vec!["work"]);
+        assert!(data.tasks[1].completed());
+
+        assert!(run(&arguments(&["--file", file_name, "import"])).is_err());
+        assert!(run(&arguments(&[
+            "--file",
+            file_name,
+            "import",
+            source_name,
+            "--format",
+            "xml"
+        ]))
+        .is_err());
+    }
+
+    #[test]
+    fn merge_lists() {
+        let directory = std::env::temp_dir().join("test_cli_merge");
+        let _ = std::fs::remove_dir_all(&directory);
+        std::fs::create_dir_all(&directory).unwrap();
+        let file = |name: &str| directory.join(name).to_str().unwrap().to_string();
+
+        let mut base = Data {
+            tasks: vec![Task::new(0, &SystemClock), Task::new(1, &SystemClock)],
+            ..Data::default()
+        };
+        base.tasks[0].edit("A");
+        base.tasks[1].edit("B");
+        let mut ours = base.clone();
+        ours.tasks[0].edit("A, edited by us");
+        let mut theirs = base.clone();
+        theirs.tasks[1].set_completed(true, &SystemClock);
+        // Git names the copies without the extension of the merged file.
+        for (name, data) in [(".base", &base), (".ours", &ours), (".theirs", &theirs)] {
+            let mut storage = storage::open_as(&file(name), "list.json").unwrap();
+            storage.save(data).unwrap();
+        }
+
+        let merge_arguments = [".base", ".ours", ".theirs"]
+            .iter()
+            .map(|name| file(name))
+            .chain(["list.json".to_string()]);
+        let output = run(&["merge".to_string()]
+            .into_iter()
+            .chain(merge_arguments.clone())
+            .collect::<Vec<_>>())
+        .unwrap();
+        assert!(output.starts_with("Merged into"));
+        let merged = storage::open_as(&file(".ours"), "list.json")
+            .unwrap()
+            .load()
+            .unwrap();
+        assert_eq!(merged.tasks[0].description(), "A, edited by us");
+        assert!(merged.tasks[1].completed());
+
+        theirs.tasks[0].edit("A, edited by them");
+        let mut storage = storage::open_as(&file(".theirs"), "list.json").unwrap();
+        storage.save(&theirs).unwrap();
+        let mut storage = storage::open_as(&file(".ours"), "list.json").unwrap();
+        storage.save(&ours).unwrap();
+        let error = run(&["merge".to_string()]
+            .into_iter()
+            .chain(merge_arguments)
+            .collect::<Vec<_>>())
+        .unwrap_err();
+        assert!(error.contains("with 1 conflicts"));
+        assert!(run(&arguments(&["merge", ".base", ".ours"])).is_err());
+    }
+
+    #[test]
+    fn encrypted_list() {
+        let file_name = std::env::temp_dir().join("test_cli_encrypted.ogz");
+        let file_name = file_name.to_str().unwrap();
+        let mut storage = storage::open(file_name).unwrap();
+        storage.set_passphrase(Some("secret".to_string())).unwrap();
+        storage.save(&Data::default()).unwrap();
+
+        let error = run(&arguments(&["--file", file_name, "add", "Call ACME"])).unwrap_err();
+        assert!(error.contains(PASSPHRASE_VARIABLE));
+
+        std::env::set_var(PASSPHRASE_VARIABLE, "secret");
+        run(&arguments(&["--file", file_name, "add", "Call ACME"])).unwrap();
+        std::env::remove_var(PASSPHRASE_VARIABLE);
+        assert!(!String::from_utf8_lossy(&std::fs::read(file_name).unwrap()).contains("ACME"));
+        assert_eq!(storage.load().unwrap().tasks[0].description(), "Call ACME");
+    }
+
+    #[test]
+    fn invalid_arguments() {
+        assert!(run(&[]).is_err());
+        assert!(run(&arguments(&["remove"])).is_err());
+        assert!(run(&arguments(&["add", "--file"])).is_err());
+        assert!(run(&arguments(&["help"])).is_ok());
+    }
+}
@@ -0,0 +1,53 @@
+use crate::time::Time;
+
+/// Source of the current time, so that what depends on it can be tested and simulated.
+pub(crate) trait Clock {
+    fn now(&self) -> Time;
+}
+
+/// The time of the system.
+pub(crate) struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Time {
+        Time::now()
+    }
+}
+
+/// A clock that only moves when told to.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct FakeClock {
+    now: Time,
+}
+
+impl FakeClock {
+    pub(crate) fn new(now: Time) -> Self {
+        FakeClock { now }
+    }
+
+    #[cfg(test)]
+    pub(crate) fn advance(&mut self, duration: &crate::time::Duration) {
+        self.now = &self.now + duration;
+    }
+}
+
+impl Clock for FakeClock {
+    fn now(&self) -> Time {
+        self.now.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::time::Duration;
+
+    #[test]
+    fn fake_clock() {
+        let start = Time::new(13, 5, 2024, 9, 0, 0).unwrap();
+        let mut clock = FakeClock::new(start.clone());
+        assert_eq!(clock.now(), start);
+        clock.advance(&Duration::from_hours(2));
+        assert_eq!(clock.now(), Time::new(13, 5, 2024, 11, 0, 0).unwrap());
+    }
+}
@@ -1,21 +1,100 @@
+use crate::caldav::SyncRecord;
+use crate::clock::Clock;
+use crate::merge::Conflict;
 use crate::periodic_task::PeriodicTask;
-use crate::task::Task;
+use crate::pomodoro::PomodoroSettings;
+use crate::task::{EventKind, Task};
+use crate::time::{DisplayTimezone, Time};
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
 pub(crate) struct Data {
     pub(crate) tasks: Vec<Task>,
     pub(crate) periodic_tasks: Vec<PeriodicTask>,
     pub(crate) filters: Filters,
+    /// State of the tasks at the last CalDAV synchronization.
+    #[serde(default)]
+    pub(crate) sync_records: Vec<SyncRecord>,
+    /// Tasks changed in incompatible ways in two merged copies of the list.
+    #[serde(default)]
+    pub(crate) conflicts: Vec<Conflict>,
+    /// Completed tasks moved out of the list because they are old, oldest archived first.
+    #[serde(default)]
+    pub(crate) archive: Vec<Task>,
+    /// Deleted tasks, until they are restored or purged.
+    #[serde(default)]
+    pub(crate) trash: Vec<TrashedTask>,
+    #[serde(default)]
+    pub(crate) settings: Settings,
 }
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+/// A deleted task with the time it was deleted, from which its purge is scheduled.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub(crate) struct TrashedTask {
+    pub task: Task,
+    pub deletion_time: Time,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub(crate) struct Filters {
     pub complete: bool,
     pub todo: bool,
     pub snoozed: bool,
 }
 
+/// Preferences of a list that are saved with it.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct Settings {
+    /// Days after their completion when completed tasks are archived, or `None` to keep them in
+    /// the list.
+    pub archive_after_days: Option<u32>,
+    /// Days deleted tasks stay in the trash, or `None` to keep them until purged by hand.
+    pub trash_retention_days: Option<u32>,
+    pub pomodoro: PomodoroSettings,
+    /// Columns of the board, from left to right. The last one holds the completed tasks.
+    pub board_columns: Vec<BoardColumn>,
+    /// IANA name of the timezone that times are shown and entered in, such as "Europe/Madrid",
+    /// or `None` for the local timezone of the machine.
+    pub timezone: Option<String>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        let column = |name: &str, wip_limit| BoardColumn {
+            name: name.to_string(),
+            wip_limit,
+        };
+        Settings {
+            archive_after_days: Some(30),
+            trash_retention_days: Some(30),
+            pomodoro: PomodoroSettings::default(),
+            board_columns: vec![
+                column("Backlog", None),
+                column("In progress", Some(3)),
+                column("Review", Some(3)),
+                column("Done", None),
+            ],
+            timezone: None,
+        }
+    }
+}
+
+impl Settings {
+    /// The timezone the settings name, or the local one if they name none or an unknown one.
+    pub(crate) fn display_timezone(&self) -> DisplayTimezone {
+        DisplayTimezone::from_setting(self.timezone.as_deref()).unwrap_or(DisplayTimezone::Local)
+    }
+}
+
+/// A column of the board, holding the tasks whose status is its name.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub(crate) struct BoardColumn {
+    pub name: String,
+    /// Most tasks the column can hold, or `None` for no limit.
+    pub wip_limit: Option<usize>,
+}
+
 #[derive(Debug)]
 pub struct FileError {
     pub message: String,
@@ -27,6 +106,9 @@ pub enum FileErrorKind {
     Load,
     Serialization,
     Write,
+    /// An encrypted list could not be decrypted, because the passphrase is missing or wrong or
+    /// the file is corrupted.
+    Decryption,
 }
 
 impl From<serde_json::Error> for FileError {
@@ -38,81 +120,275 @@ impl From<serde_json::Error> for FileError {
     }
 }
 
+impl Default for Data {
+    fn default() -> Self {
+        Data {
+            tasks: vec![],
+            periodic_tasks: vec![],
+            filters: Filters {
+                todo: true,
+                complete: false,
+                snoozed: false,
+            },
+            sync_records: vec![],
+            conflicts: vec![],
+            archive: vec![],
+            trash: vec![],
+            settings: Settings::default(),
+        }
+    }
+}
+
 impl Data {
+    /// Saves to a new storage. The application and the command line keep the storage they load
+    /// with instead, which knows the passphrase of encrypted lists.
+    #[cfg(test)]
     pub(crate) fn save(&self, file_name: &str) -> Result<(), FileError> {
-        let serialized_data = serde_json::to_string(&self).unwrap();
-        let file_name = Self::add_extension_if_missing(file_name)?;
-
-        std::fs::write(file_name, serialized_data).map_err(|_| FileError {
-            message: "Problem saving file.".to_string(),
-            kind: FileErrorKind::Write,
-        })
+        crate::storage::open(file_name)?.save(self)
     }
+
+    #[cfg(test)]
     pub(crate) fn load(file_name: &str) -> Result<Data, FileError> {
-        let file_name = Self::add_extension_if_missing(file_name)?;
-        let serialized_data = std::fs::read_to_string(file_name).map_err(|_| FileError {
-            message: "Problem loading file".to_string(),
-            kind: FileErrorKind::Load,
-        })?;
-        let data = serde_json::from_str::<Self>(&serialized_data)?;
-        Ok(data)
+        crate::storage::open(file_name)?.load()
     }
 
-    pub(crate) fn visible_tasks(&self) -> Vec<(usize, &Task)> {
+    pub(crate) fn visible_tasks(&self, clock: &dyn Clock) -> Vec<(usize, &Task)> {
         let visible_tasks: Vec<(usize, &Task)> = self
             .tasks
             .iter()
             .enumerate()
             .filter(|(_, task)| {
                 (task.completed() && self.filters.complete)
-                    || (task.visible_as_pending() && self.filters.todo && !task.hidden_because_of_snooze())
-                    || (task.hidden_because_of_snooze() && self.filters.snoozed)
+                    || (task.visible_as_pending(clock)
+                        && self.filters.todo
+                        && !task.hidden_because_of_snooze(clock))
+                    || (task.hidden_because_of_snooze(clock) && self.filters.snoozed)
             })
             .collect::<Vec<_>>();
         visible_tasks
     }
 
-    fn add_extension_if_missing(file_name: &str) -> Result<String, FileError> {
-        let extension = std::path::Path::new(file_name).extension();
-        let mut file_name = file_name.to_string();
+    /// Tasks completed between `initial_date` and `final_date`, both included, archived or not.
+    pub(crate) fn completed_between(&self, initial_date: &Time, final_date: &Time) -> Vec<&Task> {
+        self.tasks
+            .iter()
+            .chain(&self.archive)
+            .filter(|task| task.completed())
+            .filter(|task| {
+                task.completion_time
+                    .as_ref()
+                    .is_some_and(|completion_time| {
+                        initial_date <= completion_time && completion_time <= final_date
+                    })
+            })
+            .collect()
+    }
 
-        match extension {
-            Some(extension) => {
-                if extension != "ogz" {
-                    return Err(FileError {
-                        message: "Wrong file extension. Expected '.ogz'".to_string(),
-                        kind: FileErrorKind::Write,
-                    });
-                }
-            }
-            None => file_name.push_str(".ogz"),
+    /// Moves the tasks completed more than the configured number of days before `now` to the
+    /// archive. Returns how many tasks were archived.
+    pub(crate) fn archive_old_tasks(&mut self, now: &Time) -> usize {
+        let Some(days) = self.settings.archive_after_days else {
+            return 0;
+        };
+        let limit = now.add_days(-i64::from(days));
+        let (old, tasks): (Vec<Task>, Vec<Task>) = std::mem::take(&mut self.tasks)
+            .into_iter()
+            .partition(|task| {
+                task.completed()
+                    && task
+                        .completion_time
+                        .as_ref()
+                        .is_some_and(|completion_time| completion_time < &limit)
+            });
+        self.tasks = tasks;
+        self.tasks
+            .iter_mut()
+            .enumerate()
+            .for_each(|(index, task)| task.id = index);
+        let archived = old.len();
+        self.archive.extend(old);
+        archived
+    }
+
+    /// Indices of the archived tasks whose description or tags contain `search`, ignoring case,
+    /// most recently archived first.
+    pub(crate) fn search_archive(&self, search: &str) -> Vec<usize> {
+        let search = search.to_lowercase();
+        let matches = |task: &Task| {
+            task.description().to_lowercase().contains(&search)
+                || task
+                    .tags
+                    .iter()
+                    .any(|tag| tag.to_lowercase().contains(&search))
+        };
+        (0..self.archive.len())
+            .rev()
+            .filter(|&index| matches(&self.archive[index]))
+            .collect()
+    }
+
+    /// Moves an archived task back to the end of the list as a pending task, since a completed
+    /// one would be archived again.
+    pub(crate) fn restore_archived(&mut self, index: usize, clock: &dyn Clock) {
+        let mut task = self.archive.remove(index);
+        task.record(EventKind::Reopened, clock);
+        task.set_completed(false, clock);
+        task.id = self.tasks.len();
+        self.tasks.push(task);
+    }
+
+    /// Board column of a task: the last one if it is completed, otherwise the column named by its
+    /// status, or the first one if there is no such column.
+    pub(crate) fn board_column(&self, task: &Task) -> usize {
+        let columns = &self.settings.board_columns;
+        let done = columns.len().saturating_sub(1);
+        if task.completed() {
+            return done;
         }
+        columns[..done]
+            .iter()
+            .position(|column| Some(&column.name) == task.status.as_ref())
+            .unwrap_or(0)
+    }
 
-        if file_name.contains('.') {
+    /// Indices of the tasks in a board column, in list order.
+    pub(crate) fn board_tasks(&self, column: usize) -> Vec<usize> {
+        (0..self.tasks.len())
+            .filter(|&index| self.board_column(&self.tasks[index]) == column)
+            .collect()
+    }
+
+    /// Moves a task to a board column unless the column is at its WIP limit. Moving to the last
+    /// column completes the task and moving out of it reopens it.
+    pub(crate) fn move_to_column(
+        &mut self,
+        index: usize,
+        column: usize,
+        clock: &dyn Clock,
+    ) -> Result<(), String> {
+        let current = self.board_column(&self.tasks[index]);
+        if column == current {
+            return Ok(());
+        }
+        let BoardColumn { name, wip_limit } = &self.settings.board_columns[column];
+        if let Some(limit) = *wip_limit {
+            if self.board_tasks(column).len() >= limit {
+                return Err(format!("{name} is at its limit of {limit} tasks."));
+            }
+        }
+        let done = column + 1 == self.settings.board_columns.len();
+        let task = &mut self.tasks[index];
+        task.status = Some(name.clone());
+        if done {
+            task.record(EventKind::Completed, clock);
+            task.set_completed(true, clock);
         } else {
-            file_name.push_str(".ogz")
+            if task.completed() {
+                task.record(EventKind::Reopened, clock);
+                task.set_completed(false, clock);
+            }
+            task.record(
+                EventKind::StatusChanged {
+                    status: name.clone(),
+                },
+                clock,
+            );
+        }
+        Ok(())
+    }
+
+    /// `name` without surrounding spaces if it can name a board column other than `column`:
+    /// column names cannot be empty or repeated, since they identify the status of tasks.
+    pub(crate) fn check_column_name(
+        &self,
+        name: &str,
+        column: Option<usize>,
+    ) -> Result<String, String> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err("Board columns need a name.".to_string());
+        }
+        let taken = self
+            .settings
+            .board_columns
+            .iter()
+            .enumerate()
+            .any(|(index, other)| Some(index) != column && other.name == name);
+        if taken {
+            return Err(format!("There is already a column named {name}."));
+        }
+        Ok(name.to_string())
+    }
+
+    /// Renames a board column along with the status of its tasks.
+    pub(crate) fn rename_column(&mut self, column: usize, name: &str) -> Result<(), String> {
+        let name = self.check_column_name(name, Some(column))?;
+        let previous = std::mem::replace(&mut self.settings.board_columns[column].name, name);
+        let name = &self.settings.board_columns[column].name;
+        for task in &mut self.tasks {
+            if task.status.as_ref() == Some(&previous) {
+                task.status = Some(name.clone());
+            }
         }
-        Ok(file_name)
+        Ok(())
+    }
+
+    /// Moves a task to the trash.
+    pub(crate) fn delete_task(&mut self, index: usize, now: &Time) {
+        let task = self.tasks.remove(index);
+        self.trash.push(TrashedTask {
+            task,
+            deletion_time: now.clone(),
+        });
+    }
+
+    /// Moves a task in the trash back to the end of the list.
+    pub(crate) fn restore_trashed(&mut self, index: usize) {
+        let mut task = self.trash.remove(index).task;
+        task.id = self.tasks.len();
+        self.tasks.push(task);
+    }
+
+    /// Removes the tasks deleted more than the configured number of days before `now` from the
+    /// trash. Returns how many tasks were purged.
+    pub(crate) fn purge_old_trash(&mut self, now: &Time) -> usize {
+        let Some(days) = self.settings.trash_retention_days else {
+            return 0;
+        };
+        let limit = now.add_days(-i64::from(days));
+        let size = self.trash.len();
+        self.trash.retain(|trashed| trashed.deletion_time >= limit);
+        size - self.trash.len()
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::clock::SystemClock;
 
     #[test]
     fn save_and_load_returns_same_data() {
         let data = Data {
-            tasks: vec![Task::new(0_usize), Task::new(1_usize)],
+            tasks: vec![
+                Task::new(0_usize, &SystemClock),
+                Task::new(1_usize, &SystemClock),
+            ],
             periodic_tasks: vec![],
             filters: Filters {
                 todo: true,
                 complete: true,
                 snoozed: false,
             },
+            sync_records: vec![],
+            conflicts: vec![],
+            archive: vec![],
+            trash: vec![],
+            settings: Settings::default(),
         };
 
-        let file_name = "test_data.ogz";
+        let file_name = std::env::temp_dir().join("test_data.ogz");
+        let file_name = file_name.to_str().unwrap();
 
         data.save(file_name).unwrap();
         let loaded_data = Data::load(file_name).unwrap();
@@ -129,7 +405,8 @@ mod tests {
 
     #[test]
     fn load_invalid_file() {
-        let file_name = "test_invalid_data.ogz";
+        let file_name = std::env::temp_dir().join("test_invalid_data.ogz");
+        let file_name = file_name.to_str().unwrap();
         std::fs::write(file_name, "{\"tasks\":[{{\"id\":0,\"task_completed\":false,\"description\":\"\",\"state\":\"Idle\"}]}").unwrap();
 
         let loaded_data = Data::load(file_name);
@@ -140,15 +417,129 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn completed_between() {
+        let mut data = Data::default();
+        for (id, completion_time) in ["2024-05-13T10:00:00Z", "2024-05-15T10:00:00Z", ""]
+            .into_iter()
+            .enumerate()
+        {
+            let mut task = Task::new(id, &SystemClock);
+            if !completion_time.is_empty() {
+                task.set_completed(true, &SystemClock);
+                task.completion_time = Some(Time::from(completion_time));
+            }
+            data.tasks.push(task);
+        }
+
+        let completed = data.completed_between(
+            &Time::from("2024-05-14T00:00:00Z"),
+            &Time::from("2024-05-16T00:00:00Z"),
+        );
+        assert_eq!(completed.len(), 1);
+        assert_eq!(completed[0].id, 1);
+
+        // Both ends are included, as in the statistics.
+        let completion_time = data.tasks[1].completion_time.clone().unwrap();
+        let completed = data.completed_between(&completion_time, &completion_time);
+        assert_eq!(completed.len(), 1);
+    }
+
+    #[test]
+    fn archive_old_tasks() {
+        let mut data = Data::default();
+        for (id, completion_time) in ["2024-05-01T10:00:00Z", "", "2024-05-20T10:00:00Z"]
+            .into_iter()
+            .enumerate()
+        {
+            let mut task = Task::new(id, &SystemClock);
+            task.edit(&format!("Task {id}"));
+            if !completion_time.is_empty() {
+                task.set_completed(true, &SystemClock);
+                task.completion_time = Some(Time::from(completion_time));
+            }
+            data.tasks.push(task);
+        }
+
+        let now = Time::from("2024-06-05T00:00:00Z");
+        assert_eq!(data.archive_old_tasks(&now), 1);
+        assert_eq!(data.archive[0].description(), "Task 0");
+        let ids = data.tasks.iter().map(|task| task.id).collect::<Vec<_>>();
+        assert_eq!(ids, vec![0, 1]);
+        assert_eq!(data.archive_old_tasks(&now), 0);
+
+        // Archived tasks are still summarized and can be found.
+        let completed = data.completed_between(
+            &Time::from("2024-04-30T00:00:00Z"),
+            &Time::from("2024-06-01T00:00:00Z"),
+        );
+        assert_eq!(completed.len(), 2);
+        assert_eq!(data.search_archive("task 0"), vec![0]);
+        assert!(data.search_archive("Task 2").is_empty());
+
+        data.restore_archived(0, &SystemClock);
+        assert!(data.archive.is_empty());
+        assert_eq!(data.tasks[2].description(), "Task 0");
+        assert_eq!(data.tasks[2].id, 2);
+        assert!(!data.tasks[2].completed());
+
+        data.settings.archive_after_days = None;
+        data.tasks[2].set_completed(true, &SystemClock);
+        data.tasks[2].completion_time = Some(Time::from("2024-05-01T10:00:00Z"));
+        assert_eq!(data.archive_old_tasks(&now), 0);
+    }
+
+    #[test]
+    fn trash() {
+        let mut data = Data::default();
+        for (id, description) in ["A", "B", "C"].into_iter().enumerate() {
+            let mut task = Task::new(id, &SystemClock);
+            task.edit(description);
+            data.tasks.push(task);
+        }
+
+        data.delete_task(1, &Time::from("2024-05-01T10:00:00Z"));
+        data.delete_task(0, &Time::from("2024-05-20T10:00:00Z"));
+        assert_eq!(data.tasks.len(), 1);
+        assert_eq!(data.trash[0].task.description(), "B");
+        assert_eq!(
+            data.trash[0].deletion_time,
+            Time::from("2024-05-01T10:00:00Z")
+        );
+
+        let now = Time::from("2024-06-05T00:00:00Z");
+        assert_eq!(data.purge_old_trash(&now), 1);
+        assert_eq!(data.trash.len(), 1);
+        assert_eq!(data.trash[0].task.description(), "A");
+
+        data.restore_trashed(0);
+        assert!(data.trash.is_empty());
+        assert_eq!(data.tasks[1].description(), "A");
+        assert_eq!(data.tasks[1].id, 1);
+
+        data.settings.trash_retention_days = None;
+        data.delete_task(0, &Time::from("2020-01-01T00:00:00Z"));
+        assert_eq!(data.purge_old_trash(&now), 0);
+    }
+
     #[test]
     fn save_to_inexistent_folder() {
         let data = Data {
-            tasks: vec![Task::new(0_usize), Task::new(1_usize)],
+            tasks: vec![
+                Task::new(0_usize, &SystemClock),
+                Task::new(1_usize, &SystemClock),
+            ],
             periodic_tasks: vec![],
             filters: Filters {
                 todo: true,
                 complete: true,
+                snoozed: false,
             },
+            sync_records: vec![],
+            conflicts: vec![],
+            archive: vec![],
+            trash: vec![],
+            settings: Settings::default(),
         };
 
         let save_result = data.save("./inexistent_directory/data.ogz");
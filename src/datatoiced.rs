@@ -1,5 +1,6 @@
 use std::collections::VecDeque;
 
+use crate::clock::Clock;
 use crate::data::Data;
 use crate::fonts::icons::{downwards_arrow, upwards_arrow};
 use crate::toiced::ToIced;
@@ -12,20 +13,20 @@ use iced::{
 
 pub(crate) trait DataToIced {
     type Message;
-    fn view(&self) -> iced::Element<Self::Message>;
+    fn view(&self, clock: &dyn Clock) -> iced::Element<'_, Self::Message>;
     //fn update(&mut self, message: Self::Message);
 }
 
 impl DataToIced for Data {
     type Message = Message;
-    fn view(&self) -> Element<Self::Message> {
+    fn view(&self, clock: &dyn Clock) -> Element<'_, Self::Message> {
         let create_swap_buttons = |index| {
             let up_and_down = [upwards_arrow(), downwards_arrow()];
 
             let mut up_and_down = up_and_down
                 .into_iter()
                 .map(|text| {
-                    text.width(iced::Length::try_from(10).unwrap())
+                    text.width(iced::Length::from(10))
                         .height(6)
                         .horizontal_alignment(iced::alignment::Horizontal::Center)
                         .vertical_alignment(iced::alignment::Vertical::Center)
@@ -51,10 +52,10 @@ impl DataToIced for Data {
         let mut a_column = column(vec![]);
 
         let messages = self
-            .visible_tasks()
+            .visible_tasks(clock)
             .into_iter()
             .map(move |(index, task)| {
-                task.view()
+                task.view(clock)
                     .map(move |message| Message::Task(index, message))
             })
             .collect::<Vec<_>>();
@@ -89,7 +90,7 @@ impl DataToIced for Data {
 
 fn add_task_button(a_column: Column<Message>) -> Column<Message> {
     let create_task_text = Text::new("Add a new task")
-        .width(Length::try_from(120).unwrap())
+        .width(Length::from(120))
         .horizontal_alignment(alignment::Horizontal::Center)
         .size(20);
     let crete_task_button = button(create_task_text)
@@ -0,0 +1,430 @@
+use crate::clock::Clock;
+use crate::data::{FileError, FileErrorKind};
+use crate::ical;
+use crate::periodic_task::PeriodicTask;
+use crate::task::Task;
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ExportFormat {
+    Markdown,
+    Csv,
+    Html,
+    Json,
+    ICalendar,
+}
+
+impl ExportFormat {
+    /// Format matching the extension of `file_name`: `.md`, `.csv`, `.html`, `.json` or `.ics`.
+    pub(crate) fn from_file_name(file_name: &str) -> Option<ExportFormat> {
+        let extension = std::path::Path::new(file_name).extension()?.to_str()?;
+        match extension.to_lowercase().as_str() {
+            "md" | "markdown" => Some(ExportFormat::Markdown),
+            "csv" => Some(ExportFormat::Csv),
+            "html" | "htm" => Some(ExportFormat::Html),
+            "json" => Some(ExportFormat::Json),
+            "ics" => Some(ExportFormat::ICalendar),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Grouping {
+    #[default]
+    None,
+    Day,
+    Tag,
+}
+
+impl Grouping {
+    pub const ALL: [Grouping; 3] = [Grouping::None, Grouping::Day, Grouping::Tag];
+
+    pub(crate) fn parse(text: &str) -> Option<Grouping> {
+        match text {
+            "none" => Some(Grouping::None),
+            "day" => Some(Grouping::Day),
+            "tag" => Some(Grouping::Tag),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Grouping {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Grouping::None => "No grouping",
+                Grouping::Day => "Group by day",
+                Grouping::Tag => "Group by tag",
+            }
+        )
+    }
+}
+
+/// Row of an exported report. Times are in RFC 3339, in UTC.
+#[derive(Serialize, Debug, PartialEq)]
+struct Record<'a> {
+    description: &'a str,
+    completed: bool,
+    creation_time: String,
+    completion_time: Option<String>,
+    due_date: Option<String>,
+    priority: Option<String>,
+    tags: &'a [String],
+}
+
+impl<'a> Record<'a> {
+    fn new(task: &'a Task) -> Self {
+        Record {
+            description: task.description(),
+            completed: task.completed(),
+            creation_time: task.creation_time.to_rfc3339(),
+            completion_time: task.completion_time.as_ref().map(|time| time.to_rfc3339()),
+            due_date: task.due_date.as_ref().map(|time| time.to_rfc3339()),
+            priority: task.priority.map(|priority| priority.to_string()),
+            tags: &task.tags,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Group<'a> {
+    name: String,
+    tasks: Vec<Record<'a>>,
+}
+
+/// Writes a report of `tasks` in the given format, grouped by completion day (creation day for
+/// open tasks) or by tag. Groups keep the order in which they first appear. Calendars are not
+/// grouped, and stamped with the time of `clock`.
+pub(crate) fn export(
+    tasks: &[&Task],
+    format: ExportFormat,
+    grouping: Grouping,
+    clock: &dyn Clock,
+) -> String {
+    let groups = group(tasks, grouping);
+    match format {
+        ExportFormat::Markdown => to_markdown(&groups),
+        ExportFormat::Csv => to_csv(&groups),
+        ExportFormat::Html => to_html(&groups),
+        ExportFormat::Json => to_json(&groups, grouping),
+        ExportFormat::ICalendar => ical::export(tasks, &[], &clock.now()),
+    }
+}
+
+/// Exports `tasks` to `file_name`, in the format given by its extension. Periodic tasks are only
+/// exported to calendars, as recurring events.
+pub(crate) fn export_to_file(
+    tasks: &[&Task],
+    periodic_tasks: &[PeriodicTask],
+    file_name: &str,
+    grouping: Grouping,
+    clock: &dyn Clock,
+) -> Result<(), FileError> {
+    let format = ExportFormat::from_file_name(file_name).ok_or_else(|| FileError {
+        message: "Wrong file extension. Expected '.md', '.csv', '.html', '.json' or '.ics'"
+            .to_string(),
+        kind: FileErrorKind::Write,
+    })?;
+    let contents = match format {
+        ExportFormat::ICalendar => ical::export(tasks, periodic_tasks, &clock.now()),
+        _ => export(tasks, format, grouping, clock),
+    };
+    std::fs::write(file_name, contents).map_err(|_| FileError {
+        message: "Problem writing export file.".to_string(),
+        kind: FileErrorKind::Write,
+    })
+}
+
+fn group<'a>(tasks: &[&'a Task], grouping: Grouping) -> Vec<(String, Vec<&'a Task>)> {
+    let mut groups: Vec<(String, Vec<&Task>)> = vec![];
+    let mut add = |name: String, task: &'a Task| match groups
+        .iter_mut()
+        .find(|(group_name, _)| *group_name == name)
+    {
+        Some((_, tasks)) => tasks.push(task),
+        None => groups.push((name, vec![task])),
+    };
+
+    for &task in tasks {
+        match grouping {
+            Grouping::None => add(String::new(), task),
+            Grouping::Day => {
+                let time = task.completion_time.as_ref().unwrap_or(&task.creation_time);
+                add(time.iso_date(), task)
+            }
+            Grouping::Tag if task.tags.is_empty() => add("Untagged".to_string(), task),
+            Grouping::Tag => task
+                .tags
+                .iter()
+                .for_each(|tag| add(format!("#{tag}"), task)),
+        }
+    }
+    groups
+}
+
+fn markdown_line(task: &Task) -> String {
+    let check = if task.completed() { "x" } else { " " };
+    let mut line = format!("- [{check}] {}", task.description());
+    if let Some(completion_time) = &task.completion_time {
+        line.push_str(&format!(" (completed {completion_time})"));
+    }
+    let details = task.details();
+    if !details.is_empty() {
+        line.push_str(&format!(" {details}"));
+    }
+    line
+}
+
+fn to_markdown(groups: &[(String, Vec<&Task>)]) -> String {
+    let mut markdown = "# Tasks\n".to_string();
+    for (name, tasks) in groups {
+        if !name.is_empty() {
+            markdown.push_str(&format!("\n## {name}\n"));
+        }
+        markdown.push('\n');
+        for task in tasks {
+            markdown.push_str(&markdown_line(task));
+            markdown.push('\n');
+        }
+    }
+    markdown
+}
+
+pub(crate) fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn to_csv(groups: &[(String, Vec<&Task>)]) -> String {
+    let mut csv =
+        "group,description,completed,creation_time,completion_time,due_date,priority,tags\n"
+            .to_string();
+    for (name, tasks) in groups {
+        for task in tasks {
+            let record = Record::new(task);
+            let fields = [
+                name.clone(),
+                record.description.to_string(),
+                record.completed.to_string(),
+                record.creation_time,
+                record.completion_time.unwrap_or_default(),
+                record.due_date.unwrap_or_default(),
+                record.priority.unwrap_or_default(),
+                record.tags.join(";"),
+            ];
+            let fields = fields
+                .iter()
+                .map(|field| csv_field(field))
+                .collect::<Vec<_>>();
+            csv.push_str(&fields.join(","));
+            csv.push('\n');
+        }
+    }
+    csv
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn to_html(groups: &[(String, Vec<&Task>)]) -> String {
+    let mut html = "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+                    <title>Tasks</title>\n</head>\n<body>\n<h1>Tasks</h1>\n"
+        .to_string();
+    for (name, tasks) in groups {
+        if !name.is_empty() {
+            html.push_str(&format!("<h2>{}</h2>\n", escape_html(name)));
+        }
+        html.push_str("<table>\n<tr><th>Task</th><th>Completed</th><th>Details</th></tr>\n");
+        for task in tasks {
+            let completion_time = task
+                .completion_time
+                .as_ref()
+                .map_or(String::new(), |time| time.to_string());
+            html.push_str(&format!(
+                "<tr><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+                escape_html(task.description()),
+                escape_html(&completion_time),
+                escape_html(&task.details())
+            ));
+        }
+        html.push_str("</table>\n");
+    }
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+fn records<'a>(tasks: &[&'a Task]) -> Vec<Record<'a>> {
+    tasks.iter().map(|task| Record::new(task)).collect()
+}
+
+fn to_json(groups: &[(String, Vec<&Task>)], grouping: Grouping) -> String {
+    let json = match grouping {
+        Grouping::None => serde_json::to_string_pretty(
+            &groups
+                .iter()
+                .flat_map(|(_, tasks)| records(tasks))
+                .collect::<Vec<_>>(),
+        ),
+        Grouping::Day | Grouping::Tag => serde_json::to_string_pretty(
+            &groups
+                .iter()
+                .map(|(name, tasks)| Group {
+                    name: name.clone(),
+                    tasks: records(tasks),
+                })
+                .collect::<Vec<_>>(),
+        ),
+    };
+    json.unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::SystemClock;
+    use crate::task::Priority;
+    use crate::time::Time;
+
+    fn tasks() -> Vec<Task> {
+        let mut first = Task::new(0, &SystemClock);
+        first.edit("Write report, \"final\"");
+        first.creation_time = Time::from("2024-05-13T09:00:00Z");
+        first.set_completed(true, &SystemClock);
+        first.completion_time = Some(Time::from("2024-05-14T10:00:00Z"));
+        first.tags = vec!["work".to_string(), "writing".to_string()];
+        first.priority = Some(Priority::High);
+
+        let mut second = Task::new(1, &SystemClock);
+        second.edit("Buy <milk>");
+        second.creation_time = Time::from("2024-05-15T09:00:00Z");
+
+        vec![first, second]
+    }
+
+    #[test]
+    fn format_from_file_name() {
+        assert_eq!(
+            ExportFormat::from_file_name("report.md"),
+            Some(ExportFormat::Markdown)
+        );
+        assert_eq!(
+            ExportFormat::from_file_name("dir/report.CSV"),
+            Some(ExportFormat::Csv)
+        );
+        assert_eq!(
+            ExportFormat::from_file_name("report.html"),
+            Some(ExportFormat::Html)
+        );
+        assert_eq!(
+            ExportFormat::from_file_name("report.json"),
+            Some(ExportFormat::Json)
+        );
+        assert_eq!(
+            ExportFormat::from_file_name("calendar.ics"),
+            Some(ExportFormat::ICalendar)
+        );
+        assert_eq!(ExportFormat::from_file_name("report.pdf"), None);
+        assert_eq!(ExportFormat::from_file_name("report"), None);
+    }
+
+    #[test]
+    fn export_to_file_by_extension() {
+        let tasks = tasks();
+        let tasks = tasks.iter().collect::<Vec<_>>();
+
+        let file_name = std::env::temp_dir().join("test_export.csv");
+        let file_name = file_name.to_str().unwrap();
+        export_to_file(&tasks, &[], file_name, Grouping::None, &SystemClock).unwrap();
+        let written = std::fs::read_to_string(file_name).unwrap();
+        assert!(written.starts_with("group,description,"));
+
+        let result = export_to_file(&tasks, &[], "report.pdf", Grouping::None, &SystemClock);
+        assert!(matches!(result.unwrap_err().kind, FileErrorKind::Write));
+    }
+
+    #[test]
+    fn grouping() {
+        let tasks = tasks();
+        let tasks = tasks.iter().collect::<Vec<_>>();
+
+        let by_tag = group(&tasks, Grouping::Tag);
+        let names = by_tag
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["#work", "#writing", "Untagged"]);
+
+        let by_day = group(&tasks, Grouping::Day);
+        assert_eq!(by_day.len(), 2);
+        assert_eq!(by_day[0].1[0].description(), "Write report, \"final\"");
+    }
+
+    #[test]
+    fn markdown() {
+        let tasks = tasks();
+        let tasks = tasks.iter().collect::<Vec<_>>();
+        let markdown = export(&tasks, ExportFormat::Markdown, Grouping::Tag, &SystemClock);
+
+        assert!(markdown.starts_with("# Tasks\n"));
+        assert!(markdown.contains("\n## #work\n"));
+        assert!(markdown.contains(&format!(
+            "- [x] Write report, \"final\" (completed {}) !high #work #writing\n",
+            Time::from("2024-05-14T10:00:00Z")
+        )));
+        assert!(markdown.contains("\n## Untagged\n\n- [ ] Buy <milk>\n"));
+    }
+
+    #[test]
+    fn csv() {
+        let tasks = tasks();
+        let tasks = tasks.iter().collect::<Vec<_>>();
+        let csv = export(&tasks, ExportFormat::Csv, Grouping::None, &SystemClock);
+        let lines = csv.lines().collect::<Vec<_>>();
+
+        assert_eq!(lines.len(), 3);
+        assert_eq!(
+            lines[1],
+            ",\"Write report, \"\"final\"\"\",true,2024-05-13T09:00:00Z,2024-05-14T10:00:00Z,,high,work;writing"
+        );
+        assert_eq!(lines[2], ",Buy <milk>,false,2024-05-15T09:00:00Z,,,,");
+    }
+
+    #[test]
+    fn html() {
+        let tasks = tasks();
+        let tasks = tasks.iter().collect::<Vec<_>>();
+        let html = export(&tasks, ExportFormat::Html, Grouping::None, &SystemClock);
+
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<td>Buy &lt;milk&gt;</td>"));
+        assert!(html.contains("Write report, &quot;final&quot;"));
+        assert!(!html.contains("<h2>"));
+    }
+
+    #[test]
+    fn json() {
+        let tasks = tasks();
+        let tasks = tasks.iter().collect::<Vec<_>>();
+
+        let json = export(&tasks, ExportFormat::Json, Grouping::None, &SystemClock);
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value[0]["completion_time"], "2024-05-14T10:00:00Z");
+        assert_eq!(value[1]["tags"], serde_json::json!([]));
+
+        let json = export(&tasks, ExportFormat::Json, Grouping::Tag, &SystemClock);
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value[0]["name"], "#work");
+        assert_eq!(value[2]["tasks"][0]["description"], "Buy <milk>");
+    }
+}
@@ -0,0 +1,512 @@
+use crate::clock::Clock;
+use crate::import::ImportError;
+use crate::periodic_task::{PeriodicTask, TimePeriod};
+use crate::task::{Priority, Task};
+use crate::time::Time;
+
+/// Lines longer than this many bytes are folded, as required by RFC 5545.
+const MAXIMUM_LINE_LENGTH: usize = 75;
+
+/// Writes an iCalendar file with a VTODO for each task and a recurring VEVENT for each periodic
+/// task. `now` is the time stamp of every component.
+pub(crate) fn export(tasks: &[&Task], periodic_tasks: &[PeriodicTask], now: &Time) -> String {
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//Organizer//Organizer//EN".to_string(),
+    ];
+    for task in tasks {
+        lines.extend(vtodo(task, now));
+    }
+    for (index, periodic_task) in periodic_tasks.iter().enumerate() {
+        lines.extend(vevent(periodic_task, index, now));
+    }
+    lines.push("END:VCALENDAR".to_string());
+
+    lines.iter().map(|line| fold(line) + "\r\n").collect()
+}
+
+/// Identifier of a task in calendars: its own, or for tasks from older lists that have none yet,
+/// one made from its creation time.
+pub(crate) fn uid(task: &Task) -> String {
+    if let Some(uid) = &task.uid {
+        return uid.clone();
+    }
+    let creation_time = task.creation_time.to_rfc3339();
+    let creation_time = creation_time
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .collect::<String>();
+    format!("{creation_time}@organizer")
+}
+
+fn vtodo(task: &Task, now: &Time) -> Vec<String> {
+    let mut lines = vec![
+        "BEGIN:VTODO".to_string(),
+        format!("UID:{}", uid(task)),
+        format!("DTSTAMP:{}", now.to_compact_utc()),
+        format!("CREATED:{}", task.creation_time.to_compact_utc()),
+        format!("SUMMARY:{}", escape(task.description())),
+    ];
+    if let Some(due_date) = &task.due_date {
+        lines.push(format!("DUE:{}", due_date.to_compact_utc()));
+    }
+    if let Some(priority) = task.priority {
+        let priority = match priority {
+            Priority::High => 1,
+            Priority::Medium => 5,
+            Priority::Low => 9,
+        };
+        lines.push(format!("PRIORITY:{priority}"));
+    }
+    if !task.tags.is_empty() {
+        let tags = task.tags.iter().map(|tag| escape(tag)).collect::<Vec<_>>();
+        lines.push(format!("CATEGORIES:{}", tags.join(",")));
+    }
+    if task.completed() {
+        lines.push("STATUS:COMPLETED".to_string());
+        if let Some(completion_time) = &task.completion_time {
+            lines.push(format!("COMPLETED:{}", completion_time.to_compact_utc()));
+        }
+    } else {
+        lines.push("STATUS:NEEDS-ACTION".to_string());
+    }
+    lines.push("END:VTODO".to_string());
+    lines
+}
+
+fn vevent(periodic_task: &PeriodicTask, index: usize, now: &Time) -> Vec<String> {
+    let (Some(initial_date), Some(rule)) = (periodic_task.initial_date(), rrule(periodic_task))
+    else {
+        return vec![];
+    };
+    let mut lines = vec![
+        "BEGIN:VEVENT".to_string(),
+        format!(
+            "UID:periodic-{index}-{}@organizer",
+            initial_date.to_compact_utc()
+        ),
+        format!("DTSTAMP:{}", now.to_compact_utc()),
+        format!("DTSTART:{}", initial_date.to_compact_utc()),
+        format!("SUMMARY:{}", escape(periodic_task.description())),
+        format!("RRULE:{rule}"),
+    ];
+    if !periodic_task.tags().is_empty() {
+        let tags = periodic_task
+            .tags()
+            .iter()
+            .map(|tag| escape(tag))
+            .collect::<Vec<_>>();
+        lines.push(format!("CATEGORIES:{}", tags.join(",")));
+    }
+    lines.push("END:VEVENT".to_string());
+    lines
+}
+
+/// Recurrence rule repeating at the same period as the periodic task creates tasks. Tasks
+/// created once a month or a year, or a few times a year, repeat on calendar months, and others
+/// use the largest unit that divides their period exactly.
+fn rrule(periodic_task: &PeriodicTask) -> Option<String> {
+    let calendar_rule = match (periodic_task.time_period()?, periodic_task.frequency()?) {
+        (TimePeriod::Monthly, 1) => Some(("MONTHLY", 1)),
+        (TimePeriod::Yearly, 1) => Some(("YEARLY", 1)),
+        (TimePeriod::Yearly, frequency) if frequency > 0 && 12 % frequency == 0 => {
+            Some(("MONTHLY", 12 / frequency))
+        }
+        _ => None,
+    };
+    if let Some((frequency, interval)) = calendar_rule {
+        return Some(match interval {
+            1 => format!("FREQ={frequency}"),
+            interval => format!("FREQ={frequency};INTERVAL={interval}"),
+        });
+    }
+    let period = periodic_task.period_in_seconds()?;
+    let units = [
+        ("WEEKLY", 7 * 24 * 3600),
+        ("DAILY", 24 * 3600),
+        ("HOURLY", 3600),
+        ("MINUTELY", 60),
+        ("SECONDLY", 1),
+    ];
+    let (frequency, seconds) = units
+        .into_iter()
+        .find(|(_, seconds)| period > 0 && period % seconds == 0)?;
+    Some(match period / seconds {
+        1 => format!("FREQ={frequency}"),
+        interval => format!("FREQ={frequency};INTERVAL={interval}"),
+    })
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+fn unescape(text: &str) -> String {
+    let mut unescaped = String::new();
+    let mut characters = text.chars();
+    while let Some(character) = characters.next() {
+        match (character, characters.clone().next()) {
+            ('\\', Some('n' | 'N')) => {
+                characters.next();
+                unescaped.push('\n');
+            }
+            ('\\', Some(escaped @ ('\\' | ';' | ','))) => {
+                characters.next();
+                unescaped.push(escaped);
+            }
+            _ => unescaped.push(character),
+        }
+    }
+    unescaped
+}
+
+/// Splits a line into chunks of at most `MAXIMUM_LINE_LENGTH` bytes, continued with a space.
+fn fold(line: &str) -> String {
+    let mut folded = String::new();
+    let mut length = 0;
+    for character in line.chars() {
+        if length + character.len_utf8() > MAXIMUM_LINE_LENGTH {
+            folded.push_str("\r\n ");
+            length = 1;
+        }
+        folded.push(character);
+        length += character.len_utf8();
+    }
+    folded
+}
+
+/// Content line such as `DUE;VALUE=DATE:20240520`, with its parameters.
+struct Property {
+    name: String,
+    parameters: Vec<(String, String)>,
+    value: String,
+}
+
+impl Property {
+    fn parse(line: &str) -> Option<Property> {
+        let mut quoted = false;
+        let colon = line.char_indices().find_map(|(index, character)| {
+            match character {
+                '"' => quoted = !quoted,
+                ':' if !quoted => return Some(index),
+                _ => {}
+            }
+            None
+        })?;
+        let (name_and_parameters, value) = (&line[..colon], &line[colon + 1..]);
+        let mut parts = name_and_parameters.split(';');
+        let name = parts.next()?.to_uppercase();
+        let parameters = parts
+            .filter_map(|parameter| parameter.split_once('='))
+            .map(|(name, value)| (name.to_uppercase(), value.trim_matches('"').to_string()))
+            .collect();
+        Some(Property {
+            name,
+            parameters,
+            value: value.to_string(),
+        })
+    }
+
+    fn parameter(&self, name: &str) -> Option<&str> {
+        self.parameters
+            .iter()
+            .find(|(parameter, _)| parameter == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// UTC times end in `Z`, dates have no time, and times with a `TZID` or without a timezone
+    /// are taken in the display timezone.
+    fn time(&self) -> Result<Time, ImportError> {
+        let value = self.value.trim();
+        let error = || ImportError::new(format!("Unrecognized date '{value}' in {}.", self.name));
+        if value.ends_with('Z') {
+            return Time::parse_compact_utc(value).map_err(|_| error());
+        }
+        let digits = |range: std::ops::Range<usize>| {
+            value
+                .get(range)
+                .and_then(|digits| digits.parse::<u32>().ok())
+                .ok_or_else(error)
+        };
+        let (year, month, day) = (digits(0..4)?, digits(4..6)?, digits(6..8)?);
+        let (hour, minute, second) = if self.parameter("VALUE") == Some("DATE") || value.len() == 8
+        {
+            (0, 0, 0)
+        } else {
+            if value.get(8..9) != Some("T") {
+                return Err(error());
+            }
+            (digits(9..11)?, digits(11..13)?, digits(13..15)?)
+        };
+        Time::new(day, month, year, hour, minute, second).map_err(|_| error())
+    }
+}
+
+/// Reads the VTODOs of an iCalendar file as tasks, created at the time of `clock` unless they
+/// have a `CREATED` property. Other components are ignored.
+pub(crate) fn parse(text: &str, clock: &dyn Clock) -> Result<Vec<Task>, ImportError> {
+    let unfolded = text
+        .replace("\r\n", "\n")
+        .replace("\n ", "")
+        .replace("\n\t", "");
+
+    let mut tasks = vec![];
+    let mut properties: Option<Vec<Property>> = None;
+    let mut nested_components = 0;
+    for line in unfolded.lines().filter(|line| !line.trim().is_empty()) {
+        let property = Property::parse(line)
+            .ok_or_else(|| ImportError::new(format!("Invalid iCalendar line '{line}'.")))?;
+        match (
+            property.name.as_str(),
+            property.value.to_uppercase().as_str(),
+        ) {
+            ("BEGIN", "VTODO") if properties.is_none() => properties = Some(vec![]),
+            ("END", "VTODO") if nested_components == 0 => {
+                if let Some(properties) = properties.take() {
+                    tasks.push(task(&properties, clock)?);
+                }
+            }
+            ("BEGIN", _) if properties.is_some() => nested_components += 1,
+            ("END", _) if properties.is_some() => nested_components -= 1,
+            _ if nested_components == 0 => {
+                if let Some(properties) = properties.as_mut() {
+                    properties.push(property);
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(tasks)
+}
+
+fn task(properties: &[Property], clock: &dyn Clock) -> Result<Task, ImportError> {
+    let mut task = Task::new(0, clock);
+    let mut completed = false;
+    let mut completion_time = None;
+    for property in properties {
+        match property.name.as_str() {
+            "UID" => task.uid = Some(unescape(&property.value)),
+            "SUMMARY" => task.edit(&unescape(&property.value)),
+            "CREATED" => task.creation_time = property.time()?,
+            "DUE" => task.due_date = Some(property.time()?),
+            "STATUS" => completed = property.value.eq_ignore_ascii_case("COMPLETED"),
+            "COMPLETED" => completion_time = Some(property.time()?),
+            "PRIORITY" => {
+                task.priority = match property.value.trim().parse::<u32>() {
+                    Ok(1..=4) => Some(Priority::High),
+                    Ok(5) => Some(Priority::Medium),
+                    Ok(6..=9) => Some(Priority::Low),
+                    _ => None,
+                }
+            }
+            "CATEGORIES" => task.tags.extend(
+                split_list(&property.value)
+                    .into_iter()
+                    .filter(|tag| !tag.is_empty()),
+            ),
+            _ => {}
+        }
+    }
+    if completed || completion_time.is_some() {
+        task.set_completed(true, clock);
+        task.completion_time = completion_time.or(Some(task.creation_time.clone()));
+    }
+    Ok(task)
+}
+
+/// Splits a comma separated value, keeping escaped commas.
+fn split_list(value: &str) -> Vec<String> {
+    let mut items = vec![String::new()];
+    let mut characters = value.chars();
+    while let Some(character) = characters.next() {
+        match character {
+            '\\' => {
+                items.last_mut().unwrap().push(character);
+                if let Some(escaped) = characters.next() {
+                    items.last_mut().unwrap().push(escaped);
+                }
+            }
+            ',' => items.push(String::new()),
+            _ => items.last_mut().unwrap().push(character),
+        }
+    }
+    items.iter().map(|item| unescape(item.trim())).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::SystemClock;
+    use crate::periodic_task::TimePeriod;
+    use crate::toiced::ToIced;
+
+    fn now() -> Time {
+        Time::from("2024-05-15T10:30:00Z")
+    }
+
+    fn task() -> Task {
+        let mut task = Task::new(0, &SystemClock);
+        task.edit("Write report; final, really");
+        task.creation_time = Time::from("2024-05-13T09:00:00Z");
+        task.due_date = Some(Time::from("2024-05-20T17:00:00Z"));
+        task.priority = Some(Priority::High);
+        task.tags = vec!["work".to_string(), "writing".to_string()];
+        task
+    }
+
+    #[test]
+    fn export_vtodo() {
+        let mut completed = task();
+        completed.set_completed(true, &SystemClock);
+        completed.completion_time = Some(Time::from("2024-05-14T10:00:00Z"));
+        let calendar = export(&[&completed], &[], &now());
+
+        assert!(calendar.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+        assert!(calendar.ends_with("END:VCALENDAR\r\n"));
+        assert!(calendar.contains(&format!("\r\nUID:{}\r\n", completed.uid.unwrap())));
+        for line in [
+            "DTSTAMP:20240515T103000Z",
+            "SUMMARY:Write report\\; final\\, really",
+            "DUE:20240520T170000Z",
+            "PRIORITY:1",
+            "CATEGORIES:work,writing",
+            "STATUS:COMPLETED",
+            "COMPLETED:20240514T100000Z",
+        ] {
+            assert!(calendar.contains(&format!("\r\n{line}\r\n")), "{line}");
+        }
+    }
+
+    #[test]
+    fn export_recurrences() {
+        let initial_date = Time::from("2024-05-17T08:00:00Z");
+        let weekly = PeriodicTask::recurring(
+            "Water plants".to_string(),
+            TimePeriod::Weekly,
+            initial_date.clone(),
+            vec![],
+            None,
+        );
+        let monthly = PeriodicTask::recurring(
+            "Pay rent".to_string(),
+            TimePeriod::Monthly,
+            initial_date,
+            vec!["home".to_string()],
+            None,
+        );
+        let without_period = PeriodicTask::new("Unscheduled".to_string(), &SystemClock);
+        let calendar = export(&[], &[weekly, monthly, without_period], &now());
+
+        assert_eq!(calendar.matches("BEGIN:VEVENT").count(), 2);
+        assert!(calendar.contains("\r\nDTSTART:20240517T080000Z\r\n"));
+        assert!(calendar.contains("\r\nRRULE:FREQ=WEEKLY\r\n"));
+        assert!(calendar.contains("\r\nRRULE:FREQ=MONTHLY\r\n"));
+        assert!(calendar.contains("\r\nCATEGORIES:home\r\n"));
+    }
+
+    #[test]
+    fn recurrence_rules() {
+        let rule = |time_period: TimePeriod, frequency: &str| {
+            let mut periodic_task =
+                PeriodicTask::recurring("Task".to_string(), time_period, now(), vec![], None);
+            periodic_task.update(
+                crate::periodic_task::Message::UpdateFrequency(frequency.to_string()),
+                &SystemClock,
+            );
+            rrule(&periodic_task)
+        };
+        assert_eq!(rule(TimePeriod::Daily, "1"), Some("FREQ=DAILY".to_string()));
+        assert_eq!(
+            rule(TimePeriod::Daily, "2"),
+            Some("FREQ=HOURLY;INTERVAL=12".to_string())
+        );
+        assert_eq!(
+            rule(TimePeriod::Weekly, "7"),
+            Some("FREQ=DAILY".to_string())
+        );
+        assert_eq!(
+            rule(TimePeriod::Monthly, "1"),
+            Some("FREQ=MONTHLY".to_string())
+        );
+        assert_eq!(
+            rule(TimePeriod::Monthly, "2"),
+            Some("FREQ=WEEKLY;INTERVAL=2".to_string())
+        );
+        assert_eq!(
+            rule(TimePeriod::Yearly, "1"),
+            Some("FREQ=YEARLY".to_string())
+        );
+        assert_eq!(
+            rule(TimePeriod::Yearly, "4"),
+            Some("FREQ=MONTHLY;INTERVAL=3".to_string())
+        );
+    }
+
+    #[test]
+    fn long_lines_are_folded() {
+        let mut task = task();
+        task.edit(&"é".repeat(100));
+        let calendar = export(&[&task], &[], &now());
+        assert!(calendar
+            .lines()
+            .all(|line| line.len() <= MAXIMUM_LINE_LENGTH));
+        assert_eq!(
+            parse(&calendar, &SystemClock).unwrap()[0].description(),
+            "é".repeat(100)
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut pending = task();
+        pending.uid = Some(uid(&pending));
+        let mut completed = task();
+        completed.uid = Some("completed@example.com".to_string());
+        completed.edit("Line one\nline two, with \\ backslash");
+        completed.set_completed(true, &SystemClock);
+        completed.completion_time = Some(Time::from("2024-05-14T10:00:00Z"));
+
+        let calendar = export(&[&pending, &completed], &[], &now());
+        assert_eq!(
+            parse(&calendar, &SystemClock).unwrap(),
+            vec![pending, completed]
+        );
+    }
+
+    #[test]
+    fn import_from_other_applications() {
+        let calendar = "BEGIN:VCALENDAR\n\
+            BEGIN:VEVENT\nSUMMARY:Meeting\nDTSTART:20240515T100000Z\nEND:VEVENT\n\
+            BEGIN:VTODO\n\
+            UID:abc\n\
+            SUMMARY:Call\n  Ann\n\
+            DUE;VALUE=DATE:20240520\n\
+            CREATED;TZID=\"Europe/Madrid\":20240510T093000\n\
+            PRIORITY:6\n\
+            CATEGORIES:phone\\,mobile,work\n\
+            BEGIN:VALARM\nACTION:DISPLAY\nSUMMARY:Reminder\nEND:VALARM\n\
+            X-UNKNOWN;X-PARAM=\"a:b\":value\n\
+            END:VTODO\n\
+            END:VCALENDAR\n";
+        let tasks = parse(calendar, &SystemClock).unwrap();
+
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].uid, Some("abc".to_string()));
+        assert_eq!(tasks[0].description(), "Call Ann");
+        assert_eq!(tasks[0].due_date, Time::from_iso_date("2024-05-20").ok());
+        assert_eq!(
+            tasks[0].creation_time,
+            Time::new(10, 5, 2024, 9, 30, 0).unwrap()
+        );
+        assert_eq!(tasks[0].priority, Some(Priority::Low));
+        assert_eq!(tasks[0].tags, vec!["phone,mobile", "work"]);
+        assert!(!tasks[0].completed());
+
+        assert!(parse("BEGIN:VTODO\nDUE:tomorrow\nEND:VTODO\n", &SystemClock).is_err());
+        assert!(parse("BEGIN:VTODO\nnot a property\nEND:VTODO\n", &SystemClock).is_err());
+    }
+}
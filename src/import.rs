@@ -0,0 +1,587 @@
+use crate::clock::Clock;
+use crate::data::Data;
+use crate::ical;
+use crate::task::{Priority, Task};
+use crate::time::Time;
+use crate::todo_txt;
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ImportFormat {
+    TodoTxt,
+    Taskwarrior,
+    Csv,
+    ICalendar,
+}
+
+impl ImportFormat {
+    /// Format matching the extension of `file_name`: `.txt` for todo.txt, `.json` for a
+    /// Taskwarrior export, `.csv` and `.ics`.
+    pub(crate) fn from_file_name(file_name: &str) -> Option<ImportFormat> {
+        let extension = std::path::Path::new(file_name).extension()?.to_str()?;
+        match extension.to_lowercase().as_str() {
+            "txt" => Some(ImportFormat::TodoTxt),
+            "json" => Some(ImportFormat::Taskwarrior),
+            "csv" => Some(ImportFormat::Csv),
+            "ics" => Some(ImportFormat::ICalendar),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn parse(text: &str) -> Option<ImportFormat> {
+        match text {
+            "todotxt" | "todo.txt" => Some(ImportFormat::TodoTxt),
+            "taskwarrior" => Some(ImportFormat::Taskwarrior),
+            "csv" => Some(ImportFormat::Csv),
+            "ical" | "icalendar" | "ics" => Some(ImportFormat::ICalendar),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ImportError {
+    pub message: String,
+}
+
+impl ImportError {
+    pub(crate) fn new(message: String) -> Self {
+        ImportError { message }
+    }
+}
+
+/// Columns of a CSV file holding each task field. Only the description is required.
+#[derive(Debug, PartialEq, Default)]
+pub(crate) struct CsvMapping {
+    pub description: Option<String>,
+    pub completed: Option<String>,
+    pub creation_time: Option<String>,
+    pub completion_time: Option<String>,
+    pub due_date: Option<String>,
+    pub priority: Option<String>,
+    pub tags: Option<String>,
+}
+
+impl CsvMapping {
+    /// Guesses the mapping from the column names, such as "Title" or "Due" (case insensitive).
+    /// The columns written by the CSV export are recognized.
+    pub(crate) fn guess(header: &[String]) -> Self {
+        let find = |names: &[&str]| {
+            header
+                .iter()
+                .find(|column| names.contains(&column.trim().to_lowercase().as_str()))
+                .cloned()
+        };
+        CsvMapping {
+            description: find(&["description", "task", "title", "name", "summary"]),
+            completed: find(&["completed", "done", "status"]),
+            creation_time: find(&["creation_time", "created", "entry", "created at"]),
+            completion_time: find(&["completion_time", "completed at", "end", "finished"]),
+            due_date: find(&["due_date", "due", "deadline"]),
+            priority: find(&["priority", "pri"]),
+            tags: find(&["tags", "tag", "labels"]),
+        }
+    }
+
+    /// Maps a field to a column from a `field=column` pair, such as `description=Title`.
+    pub(crate) fn set(&mut self, assignment: &str) -> Result<(), ImportError> {
+        let (field, column) = assignment.split_once('=').ok_or_else(|| {
+            ImportError::new(format!("Expected 'field=column', got '{assignment}'."))
+        })?;
+        let column = Some(column.to_string());
+        match field {
+            "description" => self.description = column,
+            "completed" => self.completed = column,
+            "creation_time" => self.creation_time = column,
+            "completion_time" => self.completion_time = column,
+            "due_date" => self.due_date = column,
+            "priority" => self.priority = column,
+            "tags" => self.tags = column,
+            _ => return Err(ImportError::new(format!("Unknown task field '{field}'."))),
+        }
+        Ok(())
+    }
+}
+
+/// Parses the tasks in `text`. For CSV, `mapping` is applied on top of the mapping guessed from
+/// the header. Tasks without a creation time are created at the time of `clock`.
+pub(crate) fn parse(
+    text: &str,
+    format: ImportFormat,
+    mapping: &[String],
+    clock: &dyn Clock,
+) -> Result<Vec<Task>, ImportError> {
+    match format {
+        ImportFormat::TodoTxt => Ok(todo_txt::parse(text, clock)),
+        ImportFormat::Taskwarrior => parse_taskwarrior(text, clock),
+        ImportFormat::Csv => parse_csv(text, mapping, clock),
+        ImportFormat::ICalendar => ical::parse(text, clock),
+    }
+}
+
+/// Tasks to add to a list, and those left out because the list, its archive, its trash or an
+/// earlier imported task has the same description (ignoring case and spacing).
+#[derive(Debug, PartialEq)]
+pub(crate) struct Import {
+    pub tasks: Vec<Task>,
+    pub duplicates: Vec<Task>,
+}
+
+impl Import {
+    pub(crate) fn new(tasks: Vec<Task>, data: &Data) -> Self {
+        let key = |task: &Task| {
+            task.description()
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" ")
+                .to_lowercase()
+        };
+        let mut known = data
+            .tasks
+            .iter()
+            .chain(&data.archive)
+            .chain(data.trash.iter().map(|trashed| &trashed.task))
+            .map(key)
+            .collect::<Vec<_>>();
+        let (tasks, duplicates) = tasks.into_iter().partition(|task| {
+            let key = key(task);
+            let new = !known.contains(&key);
+            known.push(key);
+            new
+        });
+        Import { tasks, duplicates }
+    }
+
+    /// Description of what importing would do, for dry runs.
+    pub(crate) fn preview(&self) -> String {
+        let line = |task: &Task| {
+            let check = if task.completed() { "x" } else { " " };
+            format!("  [{check}] {} {}\n", task.description(), task.details()).replace(" \n", "\n")
+        };
+        let mut preview = format!("Tasks to import: {}\n", self.tasks.len());
+        self.tasks
+            .iter()
+            .for_each(|task| preview.push_str(&line(task)));
+        if !self.duplicates.is_empty() {
+            preview.push_str(&format!("Duplicates skipped: {}\n", self.duplicates.len()));
+            self.duplicates
+                .iter()
+                .for_each(|task| preview.push_str(&line(task)));
+        }
+        preview
+    }
+
+    pub(crate) fn add_to(self, data: &mut Data) {
+        for mut task in self.tasks {
+            task.id = data.tasks.len();
+            data.tasks.push(task);
+        }
+    }
+}
+
+/// Task as written by `task export`. Dates are in the compact ISO 8601 form.
+#[derive(Deserialize)]
+struct TaskwarriorTask {
+    description: String,
+    status: String,
+    entry: Option<String>,
+    end: Option<String>,
+    due: Option<String>,
+    wait: Option<String>,
+    priority: Option<String>,
+    project: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+/// Parses the JSON array written by `task export`, or one JSON object per line as written by
+/// older Taskwarrior versions. Deleted tasks and recurrence templates are skipped.
+fn parse_taskwarrior(text: &str, clock: &dyn Clock) -> Result<Vec<Task>, ImportError> {
+    let invalid =
+        |error: serde_json::Error| ImportError::new(format!("Invalid Taskwarrior export: {error}"));
+    let exported: Vec<TaskwarriorTask> = if text.trim_start().starts_with('[') {
+        serde_json::from_str(text).map_err(invalid)?
+    } else {
+        text.lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| serde_json::from_str(line.trim().trim_end_matches(',')))
+            .collect::<Result<_, _>>()
+            .map_err(invalid)?
+    };
+
+    let date = |date: &Option<String>| -> Result<Option<Time>, ImportError> {
+        date.as_deref()
+            .map(|date| {
+                Time::parse_compact_utc(date)
+                    .map_err(|_| ImportError::new(format!("Unrecognized date '{date}'.")))
+            })
+            .transpose()
+    };
+
+    let mut tasks = vec![];
+    for exported in exported {
+        if exported.status == "deleted" || exported.status == "recurring" {
+            continue;
+        }
+        let mut task = Task::new(0, clock);
+        task.edit(&exported.description);
+        if let Some(entry) = date(&exported.entry)? {
+            task.creation_time = entry;
+        }
+        task.due_date = date(&exported.due)?;
+        task.snooze_information.snooze_until = date(&exported.wait)?;
+        task.priority = match exported.priority.as_deref() {
+            Some("H") => Some(Priority::High),
+            Some("M") => Some(Priority::Medium),
+            Some("L") => Some(Priority::Low),
+            _ => None,
+        };
+        task.tags = exported.project.into_iter().chain(exported.tags).collect();
+        if exported.status == "completed" {
+            task.set_completed(true, clock);
+            task.completion_time = date(&exported.end)?.or(Some(task.creation_time.clone()));
+        }
+        tasks.push(task);
+    }
+    Ok(tasks)
+}
+
+fn parse_csv(
+    text: &str,
+    assignments: &[String],
+    clock: &dyn Clock,
+) -> Result<Vec<Task>, ImportError> {
+    let now = &clock.now();
+    let mut records = csv_records(text).into_iter();
+    let header = records
+        .next()
+        .ok_or_else(|| ImportError::new("The CSV file is empty.".to_string()))?;
+    let mut mapping = CsvMapping::guess(&header);
+    for assignment in assignments {
+        mapping.set(assignment)?;
+    }
+
+    let index = |column: &Option<String>| -> Result<Option<usize>, ImportError> {
+        column
+            .as_ref()
+            .map(|column| {
+                header
+                    .iter()
+                    .position(|name| name.trim() == column)
+                    .ok_or_else(|| ImportError::new(format!("No column named '{column}'.")))
+            })
+            .transpose()
+    };
+    let description = index(&mapping.description)?.ok_or_else(|| {
+        ImportError::new(
+            "No description column found. Map one with description=<column>.".to_string(),
+        )
+    })?;
+    let completed = index(&mapping.completed)?;
+    let creation_time = index(&mapping.creation_time)?;
+    let completion_time = index(&mapping.completion_time)?;
+    let due_date = index(&mapping.due_date)?;
+    let priority = index(&mapping.priority)?;
+    let tags = index(&mapping.tags)?;
+
+    let mut tasks = vec![];
+    for record in records {
+        let field = |index: Option<usize>| {
+            index
+                .and_then(|index| record.get(index))
+                .map(|field| field.trim())
+                .filter(|field| !field.is_empty())
+        };
+        let Some(task_description) = field(Some(description)) else {
+            continue;
+        };
+
+        let mut task = Task::new(0, clock);
+        task.edit(task_description);
+        if let Some(creation_time) = field(creation_time) {
+            task.creation_time = parse_time(creation_time, now)?;
+        }
+        task.due_date = field(due_date)
+            .map(|due_date| parse_time(due_date, now))
+            .transpose()?;
+        task.priority = field(priority)
+            .map(|priority| match priority.to_lowercase().as_str() {
+                "high" | "h" | "a" | "3" => Ok(Priority::High),
+                "medium" | "m" | "b" | "2" => Ok(Priority::Medium),
+                "low" | "l" | "c" | "1" => Ok(Priority::Low),
+                _ => Err(ImportError::new(format!("Unknown priority '{priority}'."))),
+            })
+            .transpose()?;
+        task.tags = field(tags)
+            .map(|tags| {
+                tags.split([';', ',', ' '])
+                    .map(|tag| tag.trim_start_matches('#'))
+                    .filter(|tag| !tag.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let completion_time = field(completion_time)
+            .map(|completion_time| parse_time(completion_time, now))
+            .transpose()?;
+        let completed = match field(completed) {
+            Some(completed) => matches!(
+                completed.to_lowercase().as_str(),
+                "true" | "yes" | "x" | "1" | "done" | "completed"
+            ),
+            None => completion_time.is_some(),
+        };
+        if completed {
+            task.set_completed(true, clock);
+            task.completion_time = completion_time.or(Some(task.creation_time.clone()));
+        }
+        tasks.push(task);
+    }
+    Ok(tasks)
+}
+
+/// Parses a timestamp in RFC 3339, RFC 2822, compact ISO 8601 or natural language.
+fn parse_time(text: &str, now: &Time) -> Result<Time, ImportError> {
+    Time::parse(text)
+        .or_else(|_| Time::parse_compact_utc(text))
+        .or_else(|_| Time::from_natural_language(text, now))
+        .map_err(|_| ImportError::new(format!("Unrecognized date '{text}'.")))
+}
+
+/// Splits CSV text into records, following RFC 4180 quoting.
+fn csv_records(text: &str) -> Vec<Vec<String>> {
+    let mut records = vec![];
+    let mut record = vec![];
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut characters = text.chars().peekable();
+    while let Some(character) = characters.next() {
+        match (quoted, character) {
+            (true, '"') if characters.peek() == Some(&'"') => {
+                characters.next();
+                field.push('"');
+            }
+            (true, '"') => quoted = false,
+            (true, _) => field.push(character),
+            (false, '"') => quoted = true,
+            (false, ',') => record.push(std::mem::take(&mut field)),
+            (false, '\r') => {}
+            (false, '\n') => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            (false, _) => field.push(character),
+        }
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    records
+        .into_iter()
+        .filter(|record| record.iter().any(|field| !field.is_empty()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::{FakeClock, SystemClock};
+
+    fn now() -> Time {
+        Time::from("2024-05-15T10:30:00Z")
+    }
+
+    #[test]
+    fn format_from_file_name() {
+        assert_eq!(
+            ImportFormat::from_file_name("todo.txt"),
+            Some(ImportFormat::TodoTxt)
+        );
+        assert_eq!(
+            ImportFormat::from_file_name("export.json"),
+            Some(ImportFormat::Taskwarrior)
+        );
+        assert_eq!(
+            ImportFormat::from_file_name("tasks.CSV"),
+            Some(ImportFormat::Csv)
+        );
+        assert_eq!(
+            ImportFormat::from_file_name("calendar.ics"),
+            Some(ImportFormat::ICalendar)
+        );
+        assert_eq!(ImportFormat::from_file_name("tasks.ogz"), None);
+    }
+
+    #[test]
+    fn taskwarrior() {
+        let export = r#"[
+            {"id":1,"description":"Call Ann","status":"pending","entry":"20240510T090000Z",
+             "due":"20240520T000000Z","priority":"H","project":"work","tags":["phone"],
+             "uuid":"a"},
+            {"id":0,"description":"Pay rent","status":"completed","entry":"20240501T090000Z",
+             "end":"20240502T100000Z","uuid":"b"},
+            {"id":0,"description":"Old","status":"deleted","uuid":"c"}
+        ]"#;
+        let tasks = parse(
+            export,
+            ImportFormat::Taskwarrior,
+            &[],
+            &FakeClock::new(now()),
+        )
+        .unwrap();
+
+        assert_eq!(tasks.len(), 2);
+        assert_eq!(tasks[0].description(), "Call Ann");
+        assert_eq!(tasks[0].creation_time, Time::from("2024-05-10T09:00:00Z"));
+        assert_eq!(tasks[0].due_date, Some(Time::from("2024-05-20T00:00:00Z")));
+        assert_eq!(tasks[0].priority, Some(Priority::High));
+        assert_eq!(tasks[0].tags, vec!["work", "phone"]);
+        assert!(tasks[1].completed());
+        assert_eq!(
+            tasks[1].completion_time,
+            Some(Time::from("2024-05-02T10:00:00Z"))
+        );
+
+        let lines = "{\"description\":\"A\",\"status\":\"pending\"},\n\
+                     {\"description\":\"B\",\"status\":\"waiting\",\"wait\":\"20240601T000000Z\"}";
+        let tasks = parse(
+            lines,
+            ImportFormat::Taskwarrior,
+            &[],
+            &FakeClock::new(now()),
+        )
+        .unwrap();
+        assert_eq!(tasks.len(), 2);
+        assert_eq!(
+            tasks[1].snooze_information.snooze_until,
+            Some(Time::from("2024-06-01T00:00:00Z"))
+        );
+
+        assert!(parse(
+            "[{]",
+            ImportFormat::Taskwarrior,
+            &[],
+            &FakeClock::new(now())
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn csv_with_guessed_columns() {
+        let csv = "Title,Done,Due,Priority,Labels\r\n\
+                   \"Write report, final\",yes,2024-05-20,H,work;writing\r\n\
+                   Buy milk,,,,\r\n\
+                   ,,,,\r\n";
+        let tasks = parse(csv, ImportFormat::Csv, &[], &FakeClock::new(now())).unwrap();
+
+        assert_eq!(tasks.len(), 2);
+        assert_eq!(tasks[0].description(), "Write report, final");
+        assert!(tasks[0].completed());
+        assert_eq!(
+            tasks[0].due_date,
+            Some(Time::from_natural_language("2024-05-20", &now()).unwrap())
+        );
+        assert_eq!(tasks[0].priority, Some(Priority::High));
+        assert_eq!(tasks[0].tags, vec!["work", "writing"]);
+        assert!(!tasks[1].completed());
+    }
+
+    #[test]
+    fn csv_with_mapping() {
+        let csv = "Summary,Notes,Finished on\nCall Ann,\"said \"\"hi\"\"\",2024-05-14T10:00:00Z\n";
+        let mapping = ["description=Notes".to_string()];
+        let tasks = parse(csv, ImportFormat::Csv, &mapping, &FakeClock::new(now())).unwrap();
+        assert_eq!(tasks[0].description(), "said \"hi\"");
+        assert!(!tasks[0].completed());
+
+        let mapping = ["completion_time=Finished on".to_string()];
+        let tasks = parse(csv, ImportFormat::Csv, &mapping, &FakeClock::new(now())).unwrap();
+        assert_eq!(tasks[0].description(), "Call Ann");
+        assert_eq!(
+            tasks[0].completion_time,
+            Some(Time::from("2024-05-14T10:00:00Z"))
+        );
+
+        for mapping in ["description", "colour=Notes", "description=Missing"] {
+            let result = parse(
+                csv,
+                ImportFormat::Csv,
+                &[mapping.to_string()],
+                &FakeClock::new(now()),
+            );
+            assert!(result.is_err(), "{mapping}");
+        }
+        assert!(parse("Notes\nA\n", ImportFormat::Csv, &[], &FakeClock::new(now())).is_err());
+    }
+
+    #[test]
+    fn csv_round_trip_with_export() {
+        let mut task = Task::new(0, &SystemClock);
+        task.edit("Write report");
+        task.tags = vec!["work".to_string()];
+        task.set_completed(true, &SystemClock);
+        let exported = crate::export::export(
+            &[&task],
+            crate::export::ExportFormat::Csv,
+            crate::export::Grouping::None,
+            &SystemClock,
+        );
+
+        let tasks = parse(&exported, ImportFormat::Csv, &[], &FakeClock::new(now())).unwrap();
+        // CSV files do not hold identifiers.
+        task.uid = tasks[0].uid.clone();
+        assert_eq!(tasks, vec![task]);
+    }
+
+    #[test]
+    fn duplicates() {
+        let mut data = Data::default();
+        let task = |description: &str| {
+            let mut task = Task::new(0, &SystemClock);
+            task.edit(description);
+            task
+        };
+        data.tasks.push(task("Buy milk"));
+
+        let import = Import::new(
+            vec![task("buy  Milk"), task("Call Ann"), task("Call ann")],
+            &data,
+        );
+        assert_eq!(import.tasks.len(), 1);
+        assert_eq!(import.duplicates.len(), 2);
+        assert_eq!(
+            import.preview(),
+            "Tasks to import: 1\n  [ ] Call Ann\nDuplicates skipped: 2\n  [ ] buy  Milk\n  [ ] Call ann\n"
+        );
+
+        import.add_to(&mut data);
+        assert_eq!(data.tasks.len(), 2);
+        assert_eq!(data.tasks[1].id, 1);
+        assert_eq!(data.tasks[1].description(), "Call Ann");
+    }
+
+    #[test]
+    fn archived_and_trashed_tasks_are_duplicates() {
+        let mut data = Data::default();
+        let task = |description: &str| {
+            let mut task = Task::new(0, &SystemClock);
+            task.edit(description);
+            task
+        };
+        data.archive.push(task("Pay rent"));
+        data.trash.push(crate::data::TrashedTask {
+            task: task("Call Ann"),
+            deletion_time: now(),
+        });
+
+        let import = Import::new(
+            vec![task("Pay rent"), task("call ann"), task("Buy milk")],
+            &data,
+        );
+        assert_eq!(import.tasks.len(), 1);
+        assert_eq!(import.tasks[0].description(), "Buy milk");
+        assert_eq!(import.duplicates.len(), 2);
+    }
+}
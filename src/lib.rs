@@ -1,23 +1,48 @@
+mod agenda;
+mod caldav;
+mod calendar;
+pub mod cli;
+mod clock;
 mod data;
+use crate::views::agenda_view::{AgendaView, DayPlan};
+use crate::views::archive_view::ArchiveView;
+use crate::views::board_view::{Board, BoardView};
+use crate::views::calendar_view::{Calendar, CalendarView};
+use crate::views::conflicts_view::ConflictsView;
+use crate::views::focus_view::{Focus, FocusView};
 use crate::views::list_view::ListView;
 use crate::views::periodic_tasks_management::PeriodicTasksManagementView;
 use crate::views::summary_view::SummaryView;
+use crate::views::trash_view::TrashView;
 mod datatoiced;
+mod export;
 mod fonts;
+mod ical;
+mod import;
+mod lock;
+mod merge;
 mod periodic_task;
+mod pomodoro;
+mod quick_add;
+mod statistics;
+mod storage;
 mod task;
 mod tasktoiced;
 mod time;
+mod time_tracking;
+mod todo_txt;
 mod toiced;
 mod views;
-use std::vec;
 
 use crate::toiced::add_button;
 use crate::views::{Message, ViewType};
-use data::{Data, FileError, Filters};
+use clock::{Clock, FakeClock, SystemClock};
+use data::{Data, FileError, FileErrorKind};
+use export::Grouping;
 use iced::widget::Text;
-use iced::Element;
-use iced::Sandbox;
+use iced::{Application, Command, Element, Subscription};
+use lock::{Lock, LockHolder};
+use storage::Storage;
 use task::Task;
 use time::{Duration, Time, TimeError};
 
@@ -25,117 +50,319 @@ pub struct Organizer {
     data: Data,
     error_text: Option<String>,
     file_name: Option<String>,
+    /// Storage of the list being edited, which is saved after every change.
+    storage: Option<Box<dyn Storage>>,
+    /// The list as last loaded or saved, to tell local changes from those made by other programs.
+    saved_data: Data,
+    /// The list as another program changed it while there were unsaved local changes, until the
+    /// user chooses to merge, keep the local list or reload.
+    external_change: Option<Data>,
+    /// Lock on the list being edited, released when another list is opened.
+    lock: Option<Lock>,
+    /// Who has the list open when another instance locked it, in which case changes are not
+    /// saved.
+    read_only: Option<LockHolder>,
     view_type: Option<ViewType>,
     summary_dates: SummaryDates,
+    quick_add_input: String,
+    summary_export: SummaryExport,
+    passphrase: Passphrase,
+    archive_search: String,
+    focus: Focus,
+    board: Board,
+    calendar: Calendar,
+    day_plan: DayPlan,
+    /// Source of the current time, which is a fake clock when simulating time.
+    clock: Box<dyn Clock>,
+    /// Whether the time is simulated for debugging, as set by `ORGANIZER_SIMULATED_TIME`.
+    simulated_time: bool,
+}
+
+/// Passphrase being typed to unlock or encrypt a list.
+#[derive(Default)]
+struct Passphrase {
+    input: String,
+    /// Encrypted list waiting for its passphrase to be opened.
+    locked_file: Option<String>,
+}
+
+#[derive(Default)]
+struct SummaryExport {
+    file_name: String,
+    grouping: Grouping,
+    /// Outcome of the last export, shown below the export row.
+    result: Option<String>,
 }
 
 struct SummaryDates {
-    initial_day: u32,
-    initial_month: u32,
-    initial_year: u32,
+    initial_input: String,
     initial_date: Result<Time, TimeError>,
-    final_day: u32,
-    final_month: u32,
-    final_year: u32,
+    final_input: String,
     final_date: Result<Time, TimeError>,
 }
 
 impl SummaryDates {
     fn new(initial_date: &Time, final_date: &Time) -> Self {
         SummaryDates {
-            initial_day: initial_date.day(),
-            initial_month: initial_date.month(),
-            initial_year: initial_date.year(),
+            initial_input: initial_date.as_input_text(),
             initial_date: Ok(initial_date.clone()),
-            final_day: final_date.day(),
-            final_month: final_date.month(),
-            final_year: final_date.year(),
+            final_input: final_date.as_input_text(),
             final_date: Ok(final_date.clone()),
         }
     }
 }
 
 #[cfg(not(tarpaulin_include))]
-impl Sandbox for Organizer {
+impl Application for Organizer {
+    type Executor = iced::executor::Default;
     type Message = Message;
+    type Theme = iced::Theme;
+    type Flags = ();
 
-    fn new() -> Self {
+    fn new(_flags: ()) -> (Self, Command<Message>) {
         let file_name = Organizer::search_for_file_in_working_directory();
-        let data = {
-            if let Some(file_name) = file_name.as_ref() {
-                Data::load(file_name)
-            } else {
-                Err(FileError {
-                    message: "Invalid file".to_string(),
-                    kind: data::FileErrorKind::Load,
-                })
-            }
-        };
+        let opened = file_name
+            .as_deref()
+            .map(|file_name| Organizer::open_storage(file_name, None));
 
-        let data = match data {
-            Ok(data) => data,
-            Err(_) => Data {
-                tasks: vec![],
-                periodic_tasks: vec![],
-                filters: Filters {
-                    todo: true,
-                    complete: false,
-                    snoozed: false,
-                },
-            },
+        let simulated_clock = Organizer::simulated_clock();
+        let simulated_time = simulated_clock.is_some();
+        let clock: Box<dyn Clock> = match simulated_clock {
+            Some(clock) => Box::new(clock),
+            None => Box::new(SystemClock),
         };
-
-        let now = Time::now();
+        let now = clock.now();
         let before = &now - &Duration::from_hours(24);
-        Organizer {
-            data,
+        let mut organizer = Organizer {
+            data: Data::default(),
             error_text: None,
-            file_name,
+            file_name: file_name.clone(),
+            storage: None,
+            saved_data: Data::default(),
+            external_change: None,
+            lock: None,
+            read_only: None,
             view_type: Some(ViewType::List),
             summary_dates: SummaryDates::new(&before, &now),
+            quick_add_input: String::new(),
+            summary_export: SummaryExport::default(),
+            passphrase: Passphrase::default(),
+            archive_search: String::new(),
+            focus: Focus::default(),
+            board: Board::default(),
+            calendar: Calendar::default(),
+            day_plan: DayPlan::default(),
+            clock,
+            simulated_time,
+        };
+        match (file_name, opened) {
+            (Some(file_name), Some(Ok((storage, data)))) => {
+                organizer.open_list(storage, data, lock::acquire(&file_name))
+            }
+            (file_name, Some(Err(error))) if matches!(error.kind, FileErrorKind::Decryption) => {
+                organizer.passphrase.locked_file = file_name
+            }
+            _ => {}
         }
+        (organizer, Command::none())
     }
 
     fn title(&self) -> String {
         String::from("Organizer")
     }
 
-    fn view(&self) -> Element<Message> {
+    fn view(&self) -> Element<'_, Message> {
         let view = match self.view_type.unwrap() {
             ViewType::List => self.view_as_list().map(Message::List),
             ViewType::Summary => self.view_as_summary().map(Message::Summary),
             ViewType::PeriodicTasksManagement => self
                 .view_as_periodic_tasks_manager()
                 .map(Message::PeriodicTasksManagement),
+            ViewType::Conflicts => self.view_as_conflicts().map(Message::Conflicts),
+            ViewType::Archive => self.view_as_archive().map(Message::Archive),
+            ViewType::Trash => self.view_as_trash().map(Message::Trash),
+            ViewType::Focus => self.view_as_focus().map(Message::Focus),
+            ViewType::Board => self.view_as_board().map(Message::Board),
+            ViewType::Calendar => self.view_as_calendar().map(Message::Calendar),
+            ViewType::Agenda => self.view_as_agenda().map(Message::Agenda),
+        };
+        // The list and the board show errors among their own widgets.
+        let view = match (&self.error_text, self.view_type) {
+            (Some(error_text), Some(view_type))
+                if !matches!(view_type, ViewType::List | ViewType::Board) =>
+            {
+                iced::widget::column![
+                    Text::new(error_text).style(iced::Color::from_rgb(1., 0., 0.)),
+                    view
+                ]
+                .padding(10)
+                .align_items(iced::Alignment::Center)
+                .into()
+            }
+            _ => view,
         };
         iced::widget::scrollable(view).into()
     }
 
-    fn update(&mut self, message: Message) {
-        self.error_text = None;
+    fn update(&mut self, message: Message) -> Command<Message> {
+        // Timers fire every few seconds, which should not hide errors.
+        let tick = matches!(
+            message,
+            Message::Focus(views::focus_view::Message::Tick) | Message::CheckFile
+        );
+        if !tick {
+            self.error_text = None;
+        }
+        // Changes made by other programs are usually noticed by the file checks, but one made
+        // since the last check is loaded before the message is handled.
+        self.reload_if_changed_externally();
+        self.create_periodic_tasks();
+        let mut command = Command::none();
         match message {
             Message::List(message) => self.update_list_view(message),
             Message::Summary(message) => self.update_summary_view(message),
             Message::PeriodicTasksManagement(message) => {
                 self.update_periodic_tasks_manager(message)
             }
+            Message::Conflicts(message) => self.update_conflicts_view(message),
+            Message::Archive(message) => self.update_archive_view(message),
+            Message::Trash(message) => self.update_trash_view(message),
+            Message::Board(message) => self.update_board_view(message),
+            Message::Calendar(message) => self.update_calendar_view(message),
+            Message::Agenda(message) => self.update_agenda_view(message),
+            Message::Focus(message) => {
+                command = self
+                    .update_focus_view(message, &self.clock.now())
+                    .map(Message::Focus)
+            }
+            Message::CheckFile => {}
         }
-        let save_result = self.data.save(self.file_name.as_ref().unwrap());
-        if save_result.is_err() {
-            self.error_text = Some("Warning: Unsaved modifications.".to_string());
+        self.data.settings.display_timezone().set();
+        // Tasks changed at a simulated time must not end up in the real list.
+        let unsaved =
+            self.external_change.is_some() || self.read_only.is_some() || self.simulated_time;
+        if let (Some(storage), false) = (self.storage.as_mut(), unsaved) {
+            if self.data != self.saved_data {
+                match storage.save(&self.data) {
+                    Ok(()) => self.saved_data = self.data.clone(),
+                    Err(_) => self.error_text = Some("Warning: Unsaved modifications.".to_string()),
+                }
+            }
         }
+        command
+    }
+
+    fn subscription(&self) -> Subscription<Message> {
+        let ticks = match self.focus.session {
+            Some(_) => views::every(1).map(|()| Message::Focus(views::focus_view::Message::Tick)),
+            None => Subscription::none(),
+        };
+        let file_checks = match self.storage {
+            Some(_) => views::every(2).map(|()| Message::CheckFile),
+            None => Subscription::none(),
+        };
+        let key_presses = match self.view_type {
+            Some(ViewType::Board) => views::board_view::key_presses().map(Message::Board),
+            _ => Subscription::none(),
+        };
+        Subscription::batch([ticks, file_checks, key_presses])
     }
 }
 
 impl Organizer {
+    /// A clock starting at the time in `ORGANIZER_SIMULATED_TIME`, if set.
+    fn simulated_clock() -> Option<FakeClock> {
+        let time = std::env::var("ORGANIZER_SIMULATED_TIME").ok()?;
+        Time::from_natural_language(&time, &Time::now())
+            .ok()
+            .map(FakeClock::new)
+    }
+
     pub fn add_task(&mut self) {
-        self.data.tasks.push(Task::new(self.data.tasks.len()))
+        self.data
+            .tasks
+            .push(Task::new(self.data.tasks.len(), self.clock.as_ref()))
+    }
+
+    pub(crate) fn open_storage(
+        file_name: &str,
+        passphrase: Option<&str>,
+    ) -> Result<(Box<dyn Storage>, Data), FileError> {
+        let mut storage = storage::open(file_name)?;
+        if let Some(passphrase) = passphrase {
+            storage.set_passphrase(Some(passphrase.to_string()))?;
+        }
+        let data = storage.load()?;
+        Ok((storage, data))
+    }
+
+    /// Starts editing `data`, which `storage` holds, read-only if another instance holds the
+    /// lock. Old completed tasks are archived and old deleted tasks purged, which is saved with
+    /// the next change, unless time is simulated, when nothing is saved.
+    pub(crate) fn open_list(
+        &mut self,
+        storage: Box<dyn Storage>,
+        data: Data,
+        lock: Result<Lock, LockHolder>,
+    ) {
+        self.storage = Some(storage);
+        self.saved_data = data.clone();
+        self.data = data;
+        self.data.settings.display_timezone().set();
+        let now = self.clock.now();
+        self.data.archive_old_tasks(&now);
+        self.data.purge_old_trash(&now);
+        self.external_change = None;
+        (self.lock, self.read_only) = match lock {
+            Ok(lock) => (Some(lock), None),
+            Err(holder) => (None, Some(holder)),
+        };
+    }
+
+    /// Adds the tasks that periodic tasks created since the last update, whatever the view.
+    fn create_periodic_tasks(&mut self) {
+        self.data
+            .periodic_tasks
+            .iter_mut()
+            .map(|periodic_task| periodic_task.create_tasks(self.clock.as_ref()))
+            .enumerate()
+            .for_each(|(index, tasks)| {
+                let size = self.data.tasks.len();
+                tasks.into_iter().for_each(|mut task| {
+                    task.id = size + index + 1usize;
+                    self.data.tasks.push(task);
+                })
+            });
     }
 
+    /// Reloads the list if another program changed it and there are no local changes, or
+    /// otherwise keeps the changed list for the user to choose what to do.
+    fn reload_if_changed_externally(&mut self) {
+        let Some(storage) = self.storage.as_mut() else {
+            return;
+        };
+        if self.external_change.is_some() || !storage.changed_externally() {
+            return;
+        }
+        match storage.load() {
+            Ok(data) if self.data == self.saved_data => {
+                self.saved_data = data.clone();
+                self.data = data;
+                self.error_text = Some("The list changed on disk and was reloaded.".to_string());
+            }
+            Ok(data) => self.external_change = Some(data),
+            Err(error) => {
+                self.error_text = Some(format!("{0:?} problem: {1:?}", error.kind, error.message))
+            }
+        }
+    }
+
+    /// Finds an `.ogz` file in the working directory, or otherwise an `organizer.json` or a
+    /// `todo.txt` list.
     pub(crate) fn search_for_file_in_working_directory() -> Option<String> {
         let current_directory = std::env::current_dir().unwrap();
 
-        for entry in std::fs::read_dir(current_directory).unwrap() {
+        for entry in std::fs::read_dir(&current_directory).unwrap() {
             let entry = entry.unwrap();
             let path = entry.path();
             if let Some(extension) = path.extension() {
@@ -144,7 +371,11 @@ impl Organizer {
                 }
             }
         }
-        None
+        ["organizer.json", "todo.txt"]
+            .iter()
+            .map(|file_name| current_directory.join(file_name))
+            .find(|path| path.exists())
+            .map(|path| path.to_str().unwrap().to_string())
     }
 }
 
@@ -154,13 +385,13 @@ mod tests {
 
     #[test]
     fn check_title() {
-        let organizer = Organizer::new();
+        let organizer = Organizer::new(()).0;
         assert_eq!(organizer.title(), "Organizer");
     }
 
     #[test]
     fn add_task() {
-        let mut organizer = Organizer::new();
+        let mut organizer = Organizer::new(()).0;
         assert_eq!(organizer.data.tasks.len(), 0);
 
         organizer.add_task();
@@ -178,18 +409,18 @@ mod tests {
 
         #[test]
         fn add_task() {
-            let mut organizer = Organizer::new();
-            organizer.update(Message::List(list_view::Message::AddTask));
+            let mut organizer = Organizer::new(()).0;
+            let _ = organizer.update(Message::List(list_view::Message::AddTask));
             assert_eq!(organizer.data.tasks.len(), 1);
         }
 
         #[test]
         #[should_panic]
         fn message_to_inexisting_task() {
-            let mut organizer = Organizer::new();
-            organizer.update(Message::List(list_view::Message::AddTask));
+            let mut organizer = Organizer::new(()).0;
+            let _ = organizer.update(Message::List(list_view::Message::AddTask));
 
-            organizer.update(Message::List(list_view::Message::Task(
+            let _ = organizer.update(Message::List(list_view::Message::Task(
                 1,
                 task::Message::DeleteTask,
             )));
@@ -198,27 +429,27 @@ mod tests {
 
         #[test]
         fn task_message() {
-            let mut organizer = Organizer::new();
+            let mut organizer = Organizer::new(()).0;
 
-            organizer.update(Message::List(list_view::Message::AddTask));
-            organizer.update(Message::List(list_view::Message::AddTask));
-            organizer.update(Message::List(list_view::Message::AddTask));
+            let _ = organizer.update(Message::List(list_view::Message::AddTask));
+            let _ = organizer.update(Message::List(list_view::Message::AddTask));
+            let _ = organizer.update(Message::List(list_view::Message::AddTask));
             assert_eq!(organizer.data.tasks.len(), 3);
 
-            organizer.update(Message::List(list_view::Message::Task(
+            let _ = organizer.update(Message::List(list_view::Message::Task(
                 0,
                 task::Message::TextInput("A".to_string()),
             )));
-            organizer.update(Message::List(list_view::Message::Task(
+            let _ = organizer.update(Message::List(list_view::Message::Task(
                 1,
                 task::Message::TextInput("B".to_string()),
             )));
-            organizer.update(Message::List(list_view::Message::Task(
+            let _ = organizer.update(Message::List(list_view::Message::Task(
                 2,
                 task::Message::TextInput("C".to_string()),
             )));
 
-            organizer.update(Message::List(list_view::Message::Task(
+            let _ = organizer.update(Message::List(list_view::Message::Task(
                 1,
                 task::Message::DeleteTask,
             )));
@@ -226,6 +457,224 @@ mod tests {
 
             assert_eq!(organizer.data.tasks[0].description(), "A");
             assert_eq!(organizer.data.tasks[1].description(), "C");
+            assert_eq!(organizer.data.trash[0].task.description(), "B");
+            assert!(matches!(
+                organizer.data.tasks[0].history[..],
+                [task::Event {
+                    kind: task::EventKind::Edited { .. },
+                    ..
+                }]
+            ));
+        }
+
+        #[test]
+        fn simulated_time() {
+            let mut organizer = Organizer::new(()).0;
+            let start = Time::new(13, 5, 2024, 9, 0, 0).unwrap();
+            organizer.clock = Box::new(FakeClock::new(start.clone()));
+            organizer.simulated_time = true;
+            let _ = organizer.update(Message::List(list_view::Message::AddTask));
+            let _ = organizer.update(Message::List(list_view::Message::Task(
+                0,
+                task::Message::ToggleTaskCompletion(true),
+            )));
+            assert_eq!(organizer.data.tasks[0].creation_time, start);
+            assert_eq!(
+                organizer.data.visible_tasks(organizer.clock.as_ref()).len(),
+                1
+            );
+
+            let _ = organizer.update(Message::List(list_view::Message::AdvanceSimulatedTime(24)));
+            assert_eq!(
+                organizer.clock.now(),
+                Time::new(14, 5, 2024, 9, 0, 0).unwrap()
+            );
+            assert!(organizer
+                .data
+                .visible_tasks(organizer.clock.as_ref())
+                .is_empty());
+        }
+
+        #[test]
+        fn periodic_tasks_are_created_in_every_view() {
+            let mut organizer = Organizer::new(()).0;
+            let now = Time::new(15, 5, 2024, 9, 0, 0).unwrap();
+            organizer.clock = Box::new(FakeClock::new(now.clone()));
+            organizer
+                .data
+                .periodic_tasks
+                .push(periodic_task::PeriodicTask::recurring(
+                    "Water plants".to_string(),
+                    periodic_task::TimePeriod::Daily,
+                    Time::new(15, 5, 2024, 8, 0, 0).unwrap(),
+                    vec![],
+                    None,
+                ));
+
+            let _ = organizer.update(Message::Agenda(views::agenda_view::Message::SelectView(
+                ViewType::Agenda,
+            )));
+            assert_eq!(organizer.data.tasks.len(), 1);
+            let agenda = agenda::Agenda::new(&organizer.data.tasks, &now);
+            assert_eq!(agenda.periodic, vec![0]);
+        }
+
+        #[test]
+        fn simulated_time_does_not_save() {
+            let file_name = std::env::temp_dir().join("test_simulated_time.ogz");
+            let file_name = file_name.to_str().unwrap();
+            let mut data = Data::default();
+            data.tasks.push(Task::new(0, &SystemClock));
+            data.tasks[0].set_completed(true, &SystemClock);
+            data.settings.archive_after_days = Some(30);
+            data.save(file_name).unwrap();
+            let mut organizer = Organizer::new(()).0;
+            organizer.file_name = Some(file_name.to_string());
+            organizer.clock = Box::new(FakeClock::new(
+                Time::now()
+                    .checked_add(&Duration::from_hours(24 * 365))
+                    .unwrap(),
+            ));
+            organizer.simulated_time = true;
+            organizer.open_list(
+                storage::open(file_name).unwrap(),
+                Data::load(file_name).unwrap(),
+                lock::acquire(file_name),
+            );
+            // The completed task is archived a year later, but only in the simulation.
+            assert!(organizer.data.tasks.is_empty());
+
+            let _ = organizer.update(Message::List(list_view::Message::AddTask));
+            let _ = organizer.update(Message::List(list_view::Message::Save));
+            assert_eq!(
+                organizer.error_text.as_deref(),
+                Some("Lists are not saved while time is simulated.")
+            );
+            let _ = organizer.update(Message::List(list_view::Message::Encrypt));
+            assert_eq!(Data::load(file_name).unwrap(), data);
+        }
+
+        fn organizer_with_file(name: &str) -> (Organizer, Box<dyn Storage>) {
+            let file_name = std::env::temp_dir().join(name);
+            let file_name = file_name.to_str().unwrap();
+            let _ = std::fs::remove_file(file_name);
+            let mut organizer = Organizer::new(()).0;
+            organizer.open_list(
+                storage::open(file_name).unwrap(),
+                Data::default(),
+                lock::acquire(file_name),
+            );
+            let _ = organizer.update(Message::List(list_view::Message::AddTask));
+            let mut other = storage::open(file_name).unwrap();
+            other.load().unwrap();
+            (organizer, other)
+        }
+
+        #[test]
+        fn reload_external_changes() {
+            let (mut organizer, mut other) = organizer_with_file("test_reload.ogz");
+            let mut changed = organizer.data.clone();
+            changed.tasks[0].edit("Changed elsewhere");
+            other.save(&changed).unwrap();
+
+            let _ = organizer.update(Message::CheckFile);
+            assert_eq!(organizer.data, changed);
+            assert_eq!(
+                organizer.error_text.as_deref(),
+                Some("The list changed on disk and was reloaded.")
+            );
+            let _ = organizer.update(Message::List(list_view::Message::AddTask));
+            assert_eq!(organizer.data.tasks.len(), 2);
+            assert_eq!(other.load().unwrap(), organizer.data);
+        }
+
+        #[test]
+        fn reload_before_handling_input() {
+            let (mut organizer, mut other) = organizer_with_file("test_reload_input.ogz");
+            let mut changed = organizer.data.clone();
+            changed.tasks[0].edit("Changed elsewhere");
+            other.save(&changed).unwrap();
+
+            // The input arrives before the next file check, and is applied to the new list.
+            let _ = organizer.update(Message::List(list_view::Message::AddTask));
+            assert_eq!(organizer.data.tasks.len(), 2);
+            assert_eq!(organizer.data.tasks[0].description(), "Changed elsewhere");
+            assert_eq!(other.load().unwrap(), organizer.data);
+        }
+
+        #[test]
+        fn merge_external_changes() {
+            let (mut organizer, mut other) = organizer_with_file("test_merge_external.ogz");
+            let mut changed = organizer.data.clone();
+            changed.tasks[0].edit("Changed elsewhere");
+            other.save(&changed).unwrap();
+            // A change that could not be saved.
+            organizer.data.tasks[0].set_completed(true, &SystemClock);
+
+            let _ = organizer.update(Message::List(list_view::Message::AddTask));
+            assert_eq!(organizer.external_change, Some(changed.clone()));
+            assert_eq!(organizer.data.tasks.len(), 2);
+            assert_eq!(other.load().unwrap(), changed);
+
+            let _ = organizer.update(Message::List(list_view::Message::MergeExternalChange));
+            assert_eq!(organizer.external_change, None);
+            assert_eq!(organizer.data.tasks[0].description(), "Changed elsewhere");
+            assert!(organizer.data.tasks[0].completed());
+            assert_eq!(organizer.data.tasks.len(), 2);
+            assert_eq!(other.load().unwrap(), organizer.data);
+        }
+
+        #[test]
+        fn encrypt_and_decrypt() {
+            let (mut organizer, _) = organizer_with_file("test_encrypt_list.ogz");
+            let file_name = std::env::temp_dir().join("test_encrypt_list.ogz");
+            let _ = organizer.update(Message::List(list_view::Message::UpdatePassphrase(
+                "secret".to_string(),
+            )));
+
+            let _ = organizer.update(Message::List(list_view::Message::Encrypt));
+            assert!(std::fs::read(&file_name).unwrap().starts_with(b"OGZCRYPT"));
+            assert_eq!(
+                organizer.error_text.as_deref(),
+                Some("The list is now encrypted.")
+            );
+
+            let _ = organizer.update(Message::List(list_view::Message::Decrypt));
+            assert_eq!(
+                Data::load(file_name.to_str().unwrap()).unwrap(),
+                organizer.data
+            );
+            assert_eq!(
+                organizer.error_text.as_deref(),
+                Some("The list is no longer encrypted.")
+            );
+        }
+
+        #[test]
+        fn read_only_when_locked() {
+            let file_name = std::env::temp_dir().join("test_read_only.ogz");
+            let file_name = file_name.to_str().unwrap();
+            Data::default().save(file_name).unwrap();
+            let holder = LockHolder {
+                pid: 1,
+                hostname: "other host".to_string(),
+                since: Time::now(),
+            };
+            let mut organizer = Organizer::new(()).0;
+            organizer.file_name = Some(file_name.to_string());
+            organizer.open_list(
+                storage::open(file_name).unwrap(),
+                Data::default(),
+                Err(holder.clone()),
+            );
+
+            let _ = organizer.update(Message::List(list_view::Message::AddTask));
+            assert_eq!(organizer.read_only, Some(holder));
+            assert_eq!(Data::load(file_name).unwrap(), Data::default());
+
+            let _ = organizer.update(Message::List(list_view::Message::RetryLock));
+            assert_eq!(organizer.read_only, None);
+            assert_eq!(Data::load(file_name).unwrap(), organizer.data);
         }
     }
 }
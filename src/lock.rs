@@ -0,0 +1,243 @@
+use crate::time::Time;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Who has a list open, as written in its lock file.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub(crate) struct LockHolder {
+    pub pid: u32,
+    pub hostname: String,
+    pub since: Time,
+}
+
+impl std::fmt::Display for LockHolder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "process {} on {} since {}",
+            self.pid, self.hostname, self.since
+        )
+    }
+}
+
+impl LockHolder {
+    fn current() -> Self {
+        LockHolder {
+            pid: std::process::id(),
+            hostname: hostname(),
+            since: Time::now(),
+        }
+    }
+
+    /// Whether the holder is a process that ended without removing its lock. Processes on other
+    /// hosts cannot be checked and are assumed to be running.
+    fn is_stale(&self) -> bool {
+        self.hostname == hostname() && !process_is_running(self.pid)
+    }
+}
+
+/// Advisory lock on a list, held while the lock file next to it exists, so that two instances do
+/// not save over each other. The lock file is removed when the lock is dropped.
+#[derive(Debug)]
+pub(crate) struct Lock {
+    /// Lock file and what it holds, unless it could not be written, in which case the list is
+    /// edited without a lock rather than not at all.
+    file: Option<(PathBuf, LockHolder)>,
+}
+
+/// Lock file of the list in `file_name`, such as `list.ogz.lock`.
+fn lock_file(file_name: &str) -> PathBuf {
+    let mut lock_file = PathBuf::from(file_name).into_os_string();
+    lock_file.push(".lock");
+    PathBuf::from(lock_file)
+}
+
+fn read_holder(path: &Path) -> Option<LockHolder> {
+    serde_json::from_str(&std::fs::read_to_string(path).ok()?).ok()
+}
+
+/// Locks the list in `file_name`, replacing locks left by processes that are no longer running.
+/// Fails with the holder of the lock if another running instance has the list open.
+pub(crate) fn acquire(file_name: &str) -> Result<Lock, LockHolder> {
+    let path = lock_file(file_name);
+    let holder = LockHolder::current();
+    for _ in 0..2 {
+        let created = std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path);
+        match created {
+            Ok(mut file) => {
+                let contents = serde_json::to_string(&holder).unwrap_or_default();
+                let file = file
+                    .write_all(contents.as_bytes())
+                    .ok()
+                    .map(|_| (path, holder));
+                return Ok(Lock { file });
+            }
+            Err(error) if error.kind() == std::io::ErrorKind::AlreadyExists => {
+                match read_holder(&path) {
+                    Some(existing)
+                        if existing.pid == holder.pid && existing.hostname == holder.hostname =>
+                    {
+                        // Locked by this process, which is opening the list again.
+                        let _ = std::fs::remove_file(&path);
+                    }
+                    Some(existing) if !existing.is_stale() => return Err(existing),
+                    stale => break_lock(&path, stale)?,
+                }
+            }
+            Err(_) => break,
+        }
+    }
+    Ok(Lock { file: None })
+}
+
+/// Removes the lock file at `path`, which held `stale`, by moving it aside first, so that of two
+/// instances breaking the same stale lock, the slower one cannot remove the lock the faster one
+/// just took. A lock moved aside by mistake is put back and its holder returned.
+fn break_lock(path: &Path, stale: Option<LockHolder>) -> Result<(), LockHolder> {
+    let mut aside = path.as_os_str().to_owned();
+    aside.push(format!(".{}.stale", std::process::id()));
+    let aside = PathBuf::from(aside);
+    if std::fs::rename(path, &aside).is_err() {
+        // Someone else moved it first.
+        return Ok(());
+    }
+    let moved = read_holder(&aside);
+    if moved != stale {
+        if let Some(holder) = moved {
+            let _ = std::fs::hard_link(&aside, path);
+            let _ = std::fs::remove_file(&aside);
+            return Err(holder);
+        }
+    }
+    let _ = std::fs::remove_file(&aside);
+    Ok(())
+}
+
+impl Drop for Lock {
+    fn drop(&mut self) {
+        if let Some((path, holder)) = &self.file {
+            // The lock may have been broken and taken by someone else meanwhile.
+            if read_holder(path).as_ref() == Some(holder) {
+                let _ = std::fs::remove_file(path);
+            }
+        }
+    }
+}
+
+fn hostname() -> String {
+    std::env::var("HOSTNAME")
+        .or_else(|_| std::env::var("COMPUTERNAME"))
+        .ok()
+        .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+        .map(|hostname| hostname.trim().to_string())
+        .filter(|hostname| !hostname.is_empty())
+        .unwrap_or_else(|| "unknown host".to_string())
+}
+
+#[cfg(target_os = "linux")]
+fn process_is_running(pid: u32) -> bool {
+    Path::new(&format!("/proc/{pid}")).exists()
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+fn process_is_running(pid: u32) -> bool {
+    std::process::Command::new("kill")
+        .args(["-0", &pid.to_string()])
+        .stderr(std::process::Stdio::null())
+        .status()
+        .map_or(true, |status| status.success())
+}
+
+#[cfg(not(unix))]
+fn process_is_running(_pid: u32) -> bool {
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn list(name: &str) -> String {
+        let file_name = std::env::temp_dir().join(name);
+        let file_name = file_name.to_str().unwrap().to_string();
+        let _ = std::fs::remove_file(lock_file(&file_name));
+        file_name
+    }
+
+    fn lock_by(file_name: &str, pid: u32, hostname: String) {
+        let holder = LockHolder {
+            pid,
+            hostname,
+            since: Time::now(),
+        };
+        std::fs::write(
+            lock_file(file_name),
+            serde_json::to_string(&holder).unwrap(),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn lock_and_release() {
+        let file_name = list("test_lock.ogz");
+        let lock = acquire(&file_name).unwrap();
+        let holder = read_holder(&lock_file(&file_name)).unwrap();
+        assert_eq!(holder.pid, std::process::id());
+        assert_eq!(holder.hostname, hostname());
+
+        // Opening the list again in the same process takes over the lock.
+        let again = acquire(&file_name).unwrap();
+        drop(lock);
+        assert!(lock_file(&file_name).exists());
+        drop(again);
+        assert!(!lock_file(&file_name).exists());
+    }
+
+    #[test]
+    fn locked_by_other_process() {
+        let file_name = list("test_lock_other.ogz");
+        // The init process always runs.
+        lock_by(&file_name, 1, hostname());
+        let holder = acquire(&file_name).unwrap_err();
+        assert_eq!(holder.pid, 1);
+        assert!(holder.to_string().starts_with("process 1 on "));
+
+        lock_by(&file_name, std::process::id(), "other host".to_string());
+        assert_eq!(acquire(&file_name).unwrap_err().hostname, "other host");
+        std::fs::remove_file(lock_file(&file_name)).unwrap();
+    }
+
+    #[test]
+    fn stale_locks() {
+        let file_name = list("test_lock_stale.ogz");
+        // Above the largest process id on Linux.
+        lock_by(&file_name, 1 << 23, hostname());
+        let lock = acquire(&file_name).unwrap();
+        assert_eq!(
+            read_holder(&lock_file(&file_name)).unwrap().pid,
+            std::process::id()
+        );
+        drop(lock);
+
+        std::fs::write(lock_file(&file_name), "garbage").unwrap();
+        assert!(acquire(&file_name).is_ok());
+    }
+
+    #[test]
+    fn stale_lock_taken_meanwhile() {
+        let file_name = list("test_lock_race.ogz");
+        let path = lock_file(&file_name);
+        lock_by(&file_name, 1 << 23, hostname());
+        let stale = read_holder(&path);
+        // Another instance broke the stale lock and took it before this one.
+        lock_by(&file_name, 1, hostname());
+
+        assert_eq!(break_lock(&path, stale).unwrap_err().pid, 1);
+        assert_eq!(read_holder(&path).unwrap().pid, 1);
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -3,5 +3,16 @@ use organizer::Organizer;
 
 #[cfg(not(tarpaulin_include))]
 fn main() -> iced::Result {
+    let arguments = std::env::args().skip(1).collect::<Vec<_>>();
+    if !arguments.is_empty() {
+        match organizer::cli::run(&arguments) {
+            Ok(output) => println!("{output}"),
+            Err(message) => {
+                eprintln!("{message}");
+                std::process::exit(1);
+            }
+        }
+        return Ok(());
+    }
     Organizer::run(iced::Settings::default())
 }
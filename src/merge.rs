@@ -0,0 +1,433 @@
+use crate::data::Data;
+use crate::task::Task;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// A task changed in incompatible ways in two copies of a list, kept in the list until someone
+/// picks a version.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub(crate) struct Conflict {
+    /// Our version of the task, or `None` if we removed it.
+    pub ours: Option<Task>,
+    /// Their version of the task, or `None` if they removed it.
+    pub theirs: Option<Task>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resolution {
+    Ours,
+    Theirs,
+    /// Keep the merged task in the list, which has our value for the fields changed on both
+    /// sides and is kept if one side removed it.
+    Merged,
+}
+
+/// Identity of a task across copies of a list, since positions and ids change when tasks are
+/// added, removed or moved. Tasks created at the same time are told apart by their order.
+type Key = (String, Option<String>, usize);
+
+fn keyed(tasks: &[Task]) -> Vec<(Key, &Task)> {
+    let mut occurrences = BTreeMap::new();
+    tasks
+        .iter()
+        .map(|task| {
+            let identity = (task.creation_time.to_rfc3339(), task.uid.clone());
+            let occurrence = occurrences.entry(identity.clone()).or_insert(0);
+            *occurrence += 1;
+            ((identity.0, identity.1, *occurrence - 1), task)
+        })
+        .collect()
+}
+
+fn same_task(task: &Task, other: &Task) -> bool {
+    task.creation_time == other.creation_time && task.uid == other.uid
+}
+
+/// Whether two versions of a task are equal apart from their ids, which only reflect positions.
+fn equivalent(task: &Task, other: &Task) -> bool {
+    let mut task = task.clone();
+    task.id = other.id;
+    task == *other
+}
+
+/// Merges the changes made in `ours` and `theirs` since their common ancestor `base`.
+///
+/// Tasks are merged field by field, so that one side completing a task and the other editing its
+/// description keeps both changes. When both sides changed the same field, or one side removed a
+/// task the other changed, the merged list keeps our value, or the changed task, and records a
+/// [`Conflict`]. The order follows the side that moved tasks, with the tasks added on the other
+/// side placed after the task they followed there.
+pub(crate) fn merge(base: &Data, ours: &Data, theirs: &Data) -> Data {
+    let base_tasks = keyed(&base.tasks).into_iter().collect::<BTreeMap<_, _>>();
+    let our_tasks = keyed(&ours.tasks);
+    let their_tasks = keyed(&theirs.tasks);
+    let our_map = our_tasks.iter().cloned().collect::<BTreeMap<_, _>>();
+    let their_map = their_tasks.iter().cloned().collect::<BTreeMap<_, _>>();
+
+    let mut conflicts = merge_lists(&base.conflicts, &ours.conflicts, &theirs.conflicts);
+    let mut merged = BTreeMap::new();
+    let keys = base_tasks
+        .keys()
+        .chain(our_map.keys())
+        .chain(their_map.keys())
+        .collect::<BTreeSet<_>>();
+    for key in keys {
+        let base = base_tasks.get(key).copied();
+        let ours = our_map.get(key).copied();
+        let theirs = their_map.get(key).copied();
+        let task = match (base, ours, theirs) {
+            (_, Some(ours), Some(theirs)) if equivalent(ours, theirs) => Some(ours.clone()),
+            (Some(base), Some(ours), Some(theirs)) => {
+                let (task, conflicting) = merge_task(base, ours, theirs);
+                if conflicting {
+                    conflicts.push(Conflict {
+                        ours: Some(ours.clone()),
+                        theirs: Some(theirs.clone()),
+                    });
+                }
+                Some(task)
+            }
+            (None, Some(ours), Some(theirs)) => {
+                conflicts.push(Conflict {
+                    ours: Some(ours.clone()),
+                    theirs: Some(theirs.clone()),
+                });
+                Some(ours.clone())
+            }
+            (Some(base), Some(task), None) | (Some(base), None, Some(task))
+                if equivalent(task, base) =>
+            {
+                None
+            }
+            (Some(_), Some(task), None) | (Some(_), None, Some(task)) => {
+                conflicts.push(Conflict {
+                    ours: ours.cloned(),
+                    theirs: theirs.cloned(),
+                });
+                Some(task.clone())
+            }
+            (None, Some(task), None) | (None, None, Some(task)) => Some(task.clone()),
+            (_, None, None) => None,
+        };
+        if let Some(task) = task {
+            merged.insert(key.clone(), task);
+        }
+    }
+
+    let mut tasks = vec![];
+    for key in order(&base.tasks, &our_tasks, &their_tasks) {
+        if let Some(mut task) = merged.remove(&key) {
+            task.id = tasks.len();
+            tasks.push(task);
+        }
+    }
+
+    Data {
+        tasks,
+        periodic_tasks: merge_lists(
+            &base.periodic_tasks,
+            &ours.periodic_tasks,
+            &theirs.periodic_tasks,
+        ),
+        filters: merge_values(&base.filters, &ours.filters, &theirs.filters).clone(),
+        sync_records: merge_values(&base.sync_records, &ours.sync_records, &theirs.sync_records)
+            .clone(),
+        conflicts,
+        archive: merge_lists(&base.archive, &ours.archive, &theirs.archive),
+        trash: merge_lists(&base.trash, &ours.trash, &theirs.trash),
+        settings: merge_values(&base.settings, &ours.settings, &theirs.settings).clone(),
+    }
+}
+
+/// The value changed since `base`, or ours if both changed.
+fn merge_values<'a, T: PartialEq>(base: &T, ours: &'a T, theirs: &'a T) -> &'a T {
+    if ours == base {
+        theirs
+    } else {
+        ours
+    }
+}
+
+/// Our items that they did not remove, followed by the items they added.
+fn merge_lists<T: PartialEq + Clone>(base: &[T], ours: &[T], theirs: &[T]) -> Vec<T> {
+    let removed_by_them = |item: &&T| base.contains(item) && !theirs.contains(item);
+    let added_by_them = |item: &&T| !base.contains(item) && !ours.contains(item);
+    ours.iter()
+        .filter(|item| !removed_by_them(item))
+        .chain(theirs.iter().filter(added_by_them))
+        .cloned()
+        .collect()
+}
+
+/// Merges the fields of a task changed on both sides, returning whether some field changed on
+/// both sides in different ways. The events, time entries and pomodoros recorded on both sides are
+/// kept.
+fn merge_task(base: &Task, ours: &Task, theirs: &Task) -> (Task, bool) {
+    let object = |task: &Task| match serde_json::to_value(task) {
+        Ok(Value::Object(object)) => object,
+        _ => Map::new(),
+    };
+    let (base_fields, our_fields, their_fields) = (object(base), object(ours), object(theirs));
+    let mut conflicting = false;
+    let mut merged = Map::new();
+    for (name, our_value) in &our_fields {
+        let base_value = base_fields.get(name);
+        let their_value = their_fields.get(name);
+        // Histories, time entries and pomodoros are combined below.
+        let combined = ["id", "history", "time_entries", "pomodoros"].contains(&name.as_str());
+        let value = if combined || Some(our_value) == base_value {
+            their_value.unwrap_or(our_value)
+        } else {
+            if their_value != base_value && their_value != Some(our_value) {
+                conflicting = true;
+            }
+            our_value
+        };
+        merged.insert(name.clone(), value.clone());
+    }
+    match serde_json::from_value::<Task>(Value::Object(merged)) {
+        Ok(mut task) => {
+            task.history = merge_lists(&base.history, &ours.history, &theirs.history);
+            task.history
+                .sort_by(|event, other| event.time.cmp(&other.time));
+            task.time_entries =
+                merge_lists(&base.time_entries, &ours.time_entries, &theirs.time_entries);
+            task.time_entries
+                .sort_by(|entry, other| entry.start.cmp(&other.start));
+            task.pomodoros = merge_lists(&base.pomodoros, &ours.pomodoros, &theirs.pomodoros);
+            task.pomodoros.sort();
+            (task, conflicting)
+        }
+        Err(_) => (ours.clone(), true),
+    }
+}
+
+/// Order of the merged tasks: the order of the side that moved tasks relative to the base, or
+/// ours if both did, with the tasks only on the other side inserted after their predecessor there
+/// and after the tasks that only the first side has.
+fn order(base: &[Task], ours: &[(Key, &Task)], theirs: &[(Key, &Task)]) -> Vec<Key> {
+    let base_keys = keyed(base)
+        .into_iter()
+        .map(|(key, _)| key)
+        .collect::<Vec<_>>();
+    let keys = |tasks: &[(Key, &Task)]| tasks.iter().map(|(key, _)| key.clone()).collect();
+    let (our_keys, their_keys): (Vec<Key>, Vec<Key>) = (keys(ours), keys(theirs));
+    let moved = |keys: &[Key]| {
+        let common = keys
+            .iter()
+            .filter(|key| base_keys.contains(key))
+            .collect::<Vec<_>>();
+        let base_common = base_keys
+            .iter()
+            .filter(|key| keys.contains(key))
+            .collect::<Vec<_>>();
+        common != base_common
+    };
+    let (mut order, other) = if moved(&our_keys) || !moved(&their_keys) {
+        (our_keys, their_keys)
+    } else {
+        (their_keys, our_keys)
+    };
+
+    let mut position = 0;
+    for key in &other {
+        match order.iter().position(|existing| existing == key) {
+            Some(existing) => position = existing + 1,
+            None => {
+                // Tasks the other side does not have, added or removed on this side, come first.
+                while order
+                    .get(position)
+                    .is_some_and(|existing| !other.contains(existing))
+                {
+                    position += 1;
+                }
+                order.insert(position, key.clone());
+                position += 1;
+            }
+        }
+    }
+    order
+}
+
+impl Conflict {
+    /// A version of the task that is in the merged list, to find it there.
+    fn task(&self) -> Option<&Task> {
+        self.ours.as_ref().or(self.theirs.as_ref())
+    }
+}
+
+/// Resolves the conflict at `index`, replacing the merged task by the chosen version.
+pub(crate) fn resolve(data: &mut Data, index: usize, resolution: Resolution) {
+    let conflict = data.conflicts.remove(index);
+    let chosen = match resolution {
+        Resolution::Ours => conflict.ours.clone(),
+        Resolution::Theirs => conflict.theirs.clone(),
+        Resolution::Merged => return,
+    };
+    let position = conflict
+        .task()
+        .and_then(|task| data.tasks.iter().position(|merged| same_task(merged, task)));
+    match (position, chosen) {
+        (Some(position), Some(mut chosen)) => {
+            chosen.id = data.tasks[position].id;
+            data.tasks[position] = chosen;
+        }
+        (Some(position), None) => _ = data.tasks.remove(position),
+        (None, Some(mut chosen)) => {
+            chosen.id = data.tasks.len();
+            data.tasks.push(chosen);
+        }
+        (None, None) => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::SystemClock;
+    use crate::periodic_task::PeriodicTask;
+    use crate::task::EventKind;
+    use crate::time::{Duration, Time};
+
+    fn base(descriptions: &[&str]) -> Data {
+        let mut data = Data::default();
+        let created = Time::from("2024-05-15T10:30:00Z");
+        for (index, description) in descriptions.iter().enumerate() {
+            let mut task = Task::new(index, &SystemClock);
+            task.edit(description);
+            task.creation_time = &created + &Duration::from_minutes(index as i64);
+            data.tasks.push(task);
+        }
+        data
+    }
+
+    fn new_task(description: &str, minutes: i64) -> Task {
+        let mut task = Task::new(0, &SystemClock);
+        task.edit(description);
+        task.creation_time = &Time::from("2024-05-16T10:30:00Z") + &Duration::from_minutes(minutes);
+        task
+    }
+
+    fn descriptions(data: &Data) -> Vec<&str> {
+        data.tasks.iter().map(Task::description).collect()
+    }
+
+    #[test]
+    fn independent_changes() {
+        let base = base(&["A", "B", "C", "D"]);
+        let mut ours = base.clone();
+        let mut theirs = base.clone();
+        ours.tasks[0].set_completed(true, &SystemClock);
+        ours.tasks[0].record(EventKind::Completed, &SystemClock);
+        ours.tasks[1].edit("B, edited by us");
+        ours.tasks.insert(2, new_task("Ours", 0));
+        theirs.tasks[0].edit("A, edited by them");
+        theirs.tasks[0].record(
+            EventKind::Edited {
+                description: "A, edited by them".to_string(),
+            },
+            &SystemClock,
+        );
+        theirs.tasks.remove(3);
+        theirs.tasks.push(new_task("Theirs", 1));
+        theirs.filters.complete = true;
+
+        let merged = merge(&base, &ours, &theirs);
+        assert_eq!(
+            descriptions(&merged),
+            vec![
+                "A, edited by them",
+                "B, edited by us",
+                "Ours",
+                "C",
+                "Theirs"
+            ]
+        );
+        assert!(merged.tasks[0].completed());
+        // Both histories are kept, without a conflict.
+        assert_eq!(merged.tasks[0].history.len(), 2);
+        assert!(merged.conflicts.is_empty());
+        assert!(merged.filters.complete);
+        assert!(merged.conflicts.is_empty());
+        let ids = merged.tasks.iter().map(|task| task.id).collect::<Vec<_>>();
+        assert_eq!(ids, vec![0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn reorderings() {
+        let base = base(&["A", "B", "C"]);
+        let mut ours = base.clone();
+        let mut theirs = base.clone();
+        ours.tasks[2].edit("C, edited by us");
+        theirs.tasks.swap(0, 2);
+        theirs.tasks.insert(1, new_task("Theirs", 0));
+
+        let merged = merge(&base, &ours, &theirs);
+        assert_eq!(
+            descriptions(&merged),
+            vec!["C, edited by us", "Theirs", "B", "A"]
+        );
+        assert_eq!(merge(&base, &theirs, &ours).tasks, merged.tasks);
+    }
+
+    #[test]
+    fn conflicting_changes() {
+        let base = base(&["A", "B", "C"]);
+        let mut ours = base.clone();
+        let mut theirs = base.clone();
+        ours.tasks[0].edit("A, edited by us");
+        theirs.tasks[0].edit("A, edited by them");
+        theirs.tasks[0].tags = vec!["home".to_string()];
+        ours.tasks[1].edit("B, edited by us");
+        theirs.tasks.remove(1);
+        ours.tasks.remove(2);
+        theirs.tasks[1].set_completed(true, &SystemClock);
+
+        let merged = merge(&base, &ours, &theirs);
+        assert_eq!(
+            descriptions(&merged),
+            vec!["A, edited by us", "B, edited by us", "C"]
+        );
+        assert_eq!(merged.tasks[0].tags, vec!["home"]);
+        assert!(merged.tasks[2].completed());
+        assert_eq!(merged.conflicts.len(), 3);
+        assert_eq!(merged.conflicts[0].ours, Some(ours.tasks[0].clone()));
+        assert_eq!(merged.conflicts[0].theirs, Some(theirs.tasks[0].clone()));
+        assert_eq!(merged.conflicts[1].theirs, None);
+        assert_eq!(merged.conflicts[2].ours, None);
+
+        let mut resolved = merged.clone();
+        resolve(&mut resolved, 2, Resolution::Ours);
+        resolve(&mut resolved, 1, Resolution::Merged);
+        resolve(&mut resolved, 0, Resolution::Theirs);
+        assert_eq!(
+            descriptions(&resolved),
+            vec!["A, edited by them", "B, edited by us"]
+        );
+        assert!(resolved.conflicts.is_empty());
+
+        // Unresolved conflicts survive further merges, unless one side resolves them.
+        assert_eq!(merge(&base, &merged, &base).conflicts, merged.conflicts);
+        assert!(merge(&merged, &merged, &resolved).conflicts.is_empty());
+    }
+
+    #[test]
+    fn periodic_tasks() {
+        let mut base = base(&[]);
+        base.periodic_tasks = vec![PeriodicTask::new("A".to_string(), &SystemClock)];
+        let mut ours = base.clone();
+        let mut theirs = base.clone();
+        ours.periodic_tasks
+            .push(PeriodicTask::new("Ours".to_string(), &SystemClock));
+        theirs.periodic_tasks[0] = PeriodicTask::new("A, edited by them".to_string(), &SystemClock);
+
+        let merged = merge(&base, &ours, &theirs);
+        let descriptions = merged
+            .periodic_tasks
+            .iter()
+            .map(PeriodicTask::description)
+            .collect::<Vec<_>>();
+        assert_eq!(descriptions, vec!["Ours", "A, edited by them"]);
+    }
+}
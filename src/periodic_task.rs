@@ -1,6 +1,7 @@
 use crate::add_button;
-use crate::task::Task;
-use crate::toiced::ToIced;
+use crate::clock::Clock;
+use crate::task::{Priority, Task};
+use crate::toiced::{date_preview, ToIced};
 use crate::Duration;
 use crate::Time;
 use crate::TimeError;
@@ -11,17 +12,18 @@ pub(crate) struct PeriodicTask {
     description: String,
     frequency: Option<usize>,
     time_period: Option<TimePeriod>,
-    initial_hour: u32,
-    initial_minute: u32,
-    initial_day: u32,
-    initial_month: u32,
-    initial_year: u32,
+    #[serde(default)]
+    initial_date_input: String,
     initial_date: Result<Time, TimeError>,
     last_created: Option<Time>,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    priority: Option<Priority>,
 }
 
 #[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
-enum TimePeriod {
+pub(crate) enum TimePeriod {
     Daily,
     Weekly,
     Monthly,
@@ -29,17 +31,13 @@ enum TimePeriod {
 }
 
 impl PeriodicTask {
-    pub(crate) fn new(description: String) -> Self {
-        let now = Time::now();
+    pub(crate) fn new(description: String, clock: &dyn Clock) -> Self {
+        let now = clock.now();
         PeriodicTask {
             description,
             frequency: None,
             time_period: None,
-            initial_hour: now.hour(),
-            initial_minute: now.minute(),
-            initial_day: now.day(),
-            initial_month: now.month(),
-            initial_year: now.year(),
+            initial_date_input: String::new(),
             initial_date: Time::new(
                 now.day(),
                 now.month(),
@@ -49,17 +47,70 @@ impl PeriodicTask {
                 now.second(),
             ),
             last_created: None,
+            tags: vec![],
+            priority: None,
         }
     }
 
-    pub(crate) fn create_tasks(&mut self) -> Vec<Task> {
+    /// Periodic task repeating once per `time_period` from `initial_date`. Its tasks carry the
+    /// given tags and priority.
+    pub(crate) fn recurring(
+        description: String,
+        time_period: TimePeriod,
+        initial_date: Time,
+        tags: Vec<String>,
+        priority: Option<Priority>,
+    ) -> Self {
+        PeriodicTask {
+            description,
+            frequency: Some(1),
+            time_period: Some(time_period),
+            initial_date_input: String::new(),
+            initial_date: Ok(initial_date),
+            last_created: None,
+            tags,
+            priority,
+        }
+    }
+
+    pub(crate) fn description(&self) -> &str {
+        &self.description
+    }
+
+    pub(crate) fn initial_date(&self) -> Option<&Time> {
+        self.initial_date.as_ref().ok()
+    }
+
+    pub(crate) fn tags(&self) -> &[String] {
+        &self.tags
+    }
+
+    /// How many tasks are created in each time period.
+    pub(crate) fn frequency(&self) -> Option<usize> {
+        self.frequency
+    }
+
+    pub(crate) fn time_period(&self) -> Option<&TimePeriod> {
+        self.time_period.as_ref()
+    }
+
+    fn new_task(&self, description: &str, clock: &dyn Clock) -> Task {
+        let mut task = Task::new(0, clock);
+        task.edit(description);
+        task.tags = self.tags.clone();
+        task.priority = self.priority;
+        task.created_by_periodic_task = true;
+        task
+    }
+
+    pub(crate) fn create_tasks(&mut self, clock: &dyn Clock) -> Vec<Task> {
         if self.description.is_empty() {
             return vec![];
         }
 
         let period = self.period_in_seconds();
         if let Some(period) = period {
-            let now = Time::now();
+            let now = clock.now();
             let period = Duration::from_seconds(period as i64);
             let mut tasks = vec![];
 
@@ -67,27 +118,23 @@ impl PeriodicTask {
                 let initial_date = self.initial_date.clone().unwrap();
                 if initial_date < now {
                     let description = self.description.clone() + " - " + &initial_date.to_string();
-                    let mut task = Task::new(0);
-                    task.edit(&description);
-                    tasks.push(task);
+                    tasks.push(self.new_task(&description, clock));
                     self.last_created = Some(initial_date);
                 } else {
                     return vec![];
                 }
             }
 
-            while self.last_created.as_ref().unwrap() + &period < now {
-                let (previous, creation_time) = {
-                    (
-                        self.last_created.clone().unwrap(),
-                        self.last_created.as_ref().unwrap().to_string(),
-                    )
-                };
-                self.last_created = Some(&previous + &period);
-                let description = self.description.clone() + " - " + &creation_time;
-                let mut task = Task::new(0);
-                task.edit(&description);
-                tasks.push(task);
+            // The initial date comes from user input, so the next one may be out of range.
+            while let Some(previous) = self.last_created.clone() {
+                match previous.checked_add(&period) {
+                    Ok(next) if next < now => {
+                        self.last_created = Some(next);
+                        let description = self.description.clone() + " - " + &previous.to_string();
+                        tasks.push(self.new_task(&description, clock));
+                    }
+                    _ => break,
+                }
             }
             tasks
         } else {
@@ -95,7 +142,33 @@ impl PeriodicTask {
         }
     }
 
-    fn period_in_seconds(&self) -> Option<usize> {
+    /// Times when the tasks still to be created are due, between `initial_date` included and
+    /// `final_date` excluded.
+    pub(crate) fn occurrences_between(&self, initial_date: &Time, final_date: &Time) -> Vec<Time> {
+        let (Some(period), Ok(first)) = (self.period_in_seconds(), &self.initial_date) else {
+            return vec![];
+        };
+        if self.description.is_empty() || period == 0 {
+            return vec![];
+        }
+        let period = Duration::from_seconds(period as i64);
+        let mut occurrence = match &self.last_created {
+            Some(last_created) => last_created.checked_add(&period).ok(),
+            None => Some(first.clone()),
+        };
+        let mut occurrences = vec![];
+        while let Some(current) = occurrence.filter(|occurrence| occurrence < final_date) {
+            occurrence = current.checked_add(&period).ok();
+            if &current >= initial_date {
+                occurrences.push(current);
+            }
+        }
+        occurrences
+    }
+
+    /// Time between two created tasks: the time period divided by the frequency, where a month
+    /// is four weeks and a year twelve months.
+    pub(crate) fn period_in_seconds(&self) -> Option<usize> {
         self.frequency?;
         self.time_period.as_ref()?;
 
@@ -121,11 +194,7 @@ impl PeriodicTask {
 pub enum Message {
     TextInput(String),
     DeleteTask,
-    UpdateInitialHour(String),
-    UpdateInitialMinute(String),
-    UpdateInitialDay(String),
-    UpdateInitialMonth(String),
-    UpdateInitialYear(String),
+    UpdateInitialDate(String),
     Daily,
     Weekly,
     Monthly,
@@ -135,7 +204,7 @@ pub enum Message {
 
 impl ToIced for PeriodicTask {
     type Message = Message;
-    fn view(&self) -> iced::Element<Self::Message> {
+    fn view(&self, clock: &dyn Clock) -> iced::Element<'_, Self::Message> {
         let text_input = iced::widget::text_input(
             "Describe your task...",
             &self.description,
@@ -158,8 +227,8 @@ impl ToIced for PeriodicTask {
                 add_button("Yearly", Message::Yearly).style(iced::theme::Button::Secondary);
 
             let active_style = iced::theme::Button::Positive;
-            if self.time_period.is_some() {
-                match self.time_period.as_ref().unwrap() {
+            if let Some(time_period) = &self.time_period {
+                match time_period {
                     TimePeriod::Daily => daily = daily.style(active_style),
                     TimePeriod::Weekly => weekly = weekly.style(active_style),
                     TimePeriod::Monthly => monthly = monthly.style(active_style),
@@ -183,72 +252,38 @@ impl ToIced for PeriodicTask {
             .push(delete_button);
 
         let initial_date_row = {
-            let initial_day = self.initial_day.to_string();
-            let initial_day_input =
-                iced::widget::text_input("Initial day", &initial_day, Message::UpdateInitialDay)
-                    .padding(10);
-
-            let initial_month = self.initial_month.to_string();
-            let initial_month_input = iced::widget::text_input(
-                "Initial month",
-                &initial_month,
-                Message::UpdateInitialMonth,
-            )
-            .padding(10);
-
-            let initial_year = self.initial_year.to_string();
-            let initial_year_input =
-                iced::widget::text_input("Initial year", &initial_year, Message::UpdateInitialYear)
-                    .padding(10);
-
-            iced::widget::row![initial_day_input, initial_month_input, initial_year_input]
-        };
-
-        let initial_date_label =
-            iced::widget::row![iced::widget::text("Initial date: Day/Month/Year")];
-
-        let initial_time_row = {
-            let initial_hour = self.initial_hour.to_string();
-            let initial_hour_input =
-                iced::widget::text_input("Initial hour", &initial_hour, Message::UpdateInitialHour)
-                    .padding(10);
-
-            let initial_minute = self.initial_minute.to_string();
-            let initial_minute_input = iced::widget::text_input(
-                "Initial minute",
-                &initial_minute,
-                Message::UpdateInitialMinute,
+            let placeholder = match &self.initial_date {
+                Ok(initial_date) => initial_date.as_input_text(),
+                Err(_) => "Initial date (e.g. next monday 9am)".to_string(),
+            };
+            let initial_date_input = iced::widget::text_input(
+                &placeholder,
+                &self.initial_date_input,
+                Message::UpdateInitialDate,
             )
             .padding(10);
 
-            iced::widget::row![initial_hour_input, initial_minute_input]
+            iced::widget::row![
+                initial_date_input,
+                date_preview(&self.initial_date_input, clock)
+            ]
+            .spacing(10)
+            .align_items(iced::Alignment::Center)
         };
 
-        let initial_time_label =
-            iced::widget::row![iced::widget::text("Initial time: Hour - Minute")];
-
-        let initial_date = Time::new(
-            self.initial_day,
-            self.initial_month,
-            self.initial_year,
-            self.initial_hour,
-            self.initial_minute,
-            0,
-        );
+        let initial_date_label = iced::widget::row![iced::widget::text("Initial date")];
 
         let column = {
             let mut column = iced::widget::column![
                 frequency_row,
                 initial_date_label,
                 initial_date_row,
-                initial_time_label,
-                initial_time_row,
                 description_row
             ];
 
-            if initial_date.is_err() {
+            if self.initial_date.is_err() {
                 column = column.push(iced::widget::row![iced::widget::text(
-                    "WRONG INITIAL DATE: date does not exist!"
+                    "WRONG INITIAL DATE: date not recognized!"
                 )]);
             }
             column
@@ -257,77 +292,14 @@ impl ToIced for PeriodicTask {
         column.padding(40).into()
     }
 
-    fn update(&mut self, message: Self::Message) {
-        let handle_update = |value: &str, max_value: u32, result: &mut u32| {
-            if value.is_empty() {
-                *result = 0;
-            } else if let Ok(day) = value.parse::<u32>() {
-                if day <= max_value {
-                    *result = day
-                }
-            }
-        };
+    fn update(&mut self, message: Self::Message, clock: &dyn Clock) {
         match message {
             Message::TextInput(description) => self.description = description,
             Message::DeleteTask => panic!(), // Delete task is not used by PeriodicTask.
-            Message::UpdateInitialDay(value) => {
-                handle_update(&value, 31, &mut self.initial_day);
-                self.initial_date = Time::new(
-                    self.initial_day,
-                    self.initial_month,
-                    self.initial_year,
-                    self.initial_hour,
-                    self.initial_minute,
-                    0,
-                );
-            }
-            Message::UpdateInitialMonth(value) => {
-                handle_update(&value, 12, &mut self.initial_month);
-                self.initial_date = Time::new(
-                    self.initial_day,
-                    self.initial_month,
-                    self.initial_year,
-                    self.initial_hour,
-                    self.initial_minute,
-                    0,
-                );
-            }
-            Message::UpdateInitialYear(value) => {
-                handle_update(&value, 10000, &mut self.initial_year);
-                self.initial_date = Time::new(
-                    self.initial_day,
-                    self.initial_month,
-                    self.initial_year,
-                    self.initial_hour,
-                    self.initial_minute,
-                    0,
-                );
-            }
-
-            Message::UpdateInitialHour(value) => {
-                handle_update(&value, 24, &mut self.initial_hour);
-                self.initial_date = Time::new(
-                    self.initial_day,
-                    self.initial_month,
-                    self.initial_year,
-                    self.initial_hour,
-                    self.initial_minute,
-                    0,
-                );
+            Message::UpdateInitialDate(value) => {
+                self.initial_date = Time::from_natural_language(&value, &clock.now());
+                self.initial_date_input = value;
             }
-
-            Message::UpdateInitialMinute(value) => {
-                handle_update(&value, 60, &mut self.initial_minute);
-                self.initial_date = Time::new(
-                    self.initial_day,
-                    self.initial_month,
-                    self.initial_year,
-                    self.initial_hour,
-                    self.initial_minute,
-                    0,
-                );
-            }
-
             Message::Daily => self.time_period = Some(TimePeriod::Daily),
             Message::Weekly => self.time_period = Some(TimePeriod::Weekly),
             Message::Monthly => self.time_period = Some(TimePeriod::Monthly),
@@ -336,10 +308,8 @@ impl ToIced for PeriodicTask {
                 self.frequency = {
                     if frequency.is_empty() {
                         None
-                    } else if let Ok(frequency) = frequency.parse::<usize>() {
-                        Some(frequency)
                     } else {
-                        None
+                        frequency.parse::<usize>().ok()
                     }
                 };
             }
@@ -350,44 +320,116 @@ impl ToIced for PeriodicTask {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::clock::{FakeClock, SystemClock};
+
+    #[test]
+    fn create_tasks() {
+        let start = Time::new(13, 5, 2024, 9, 0, 0).unwrap();
+        let mut clock = FakeClock::new(&start - &Duration::from_hours(1));
+        let mut periodic_task = PeriodicTask::recurring(
+            "Stand-up".to_string(),
+            TimePeriod::Daily,
+            start,
+            vec![],
+            None,
+        );
+        assert!(periodic_task.create_tasks(&clock).is_empty());
+
+        clock.advance(&Duration::from_hours(2));
+        let tasks = periodic_task.create_tasks(&clock);
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].creation_time, clock.now());
+        assert!(tasks[0].created_by_periodic_task);
+        assert!(periodic_task.create_tasks(&clock).is_empty());
+
+        clock.advance(&Duration::from_hours(48));
+        assert_eq!(periodic_task.create_tasks(&clock).len(), 2);
+    }
+
+    #[test]
+    fn occurrences_between() {
+        let start = Time::new(13, 5, 2024, 9, 0, 0).unwrap();
+        let mut periodic_task = PeriodicTask::recurring(
+            "Stand-up".to_string(),
+            TimePeriod::Daily,
+            start.clone(),
+            vec![],
+            None,
+        );
+        let week = periodic_task.occurrences_between(&start.add_days(-3), &start.add_days(4));
+        assert_eq!(week.len(), 4);
+        assert_eq!(week[0], start);
+        assert_eq!(week[3], start.add_days(3));
+
+        periodic_task.create_tasks(&FakeClock::new(
+            &start.add_days(1) + &Duration::from_hours(1),
+        ));
+        let week = periodic_task.occurrences_between(&start, &start.add_days(4));
+        assert_eq!(week, vec![start.add_days(2), start.add_days(3)]);
+    }
+
+    #[test]
+    fn occurrences_at_the_end_of_time() {
+        let year = (262_140..=262_143)
+            .rev()
+            .find(|year| Time::new(31, 12, *year, 0, 0, 0).is_ok())
+            .unwrap();
+        let first = Time::new(1, 12, year, 0, 0, 0).unwrap();
+        let mut periodic_task = PeriodicTask::recurring(
+            "Far away".to_string(),
+            TimePeriod::Yearly,
+            first.clone(),
+            vec![],
+            None,
+        );
+        let end = Time::new(31, 12, year, 0, 0, 0).unwrap();
+        assert_eq!(periodic_task.occurrences_between(&first, &end), vec![first]);
+        assert_eq!(periodic_task.create_tasks(&FakeClock::new(end)).len(), 1);
+    }
 
     #[test]
     fn period_in_hours() {
-        let mut periodic_task = PeriodicTask::new("test".to_string());
+        let mut periodic_task = PeriodicTask::new("test".to_string(), &SystemClock);
 
         periodic_task.frequency = Some(1);
         periodic_task.time_period = Some(TimePeriod::Daily);
-        assert_eq!(periodic_task.period_in_seconds(), 3600 * 24);
+        assert_eq!(periodic_task.period_in_seconds(), Some(3600 * 24));
 
         periodic_task.frequency = Some(1);
         periodic_task.time_period = Some(TimePeriod::Weekly);
-        assert_eq!(periodic_task.period_in_seconds(), 3600 * 24 * 7);
+        assert_eq!(periodic_task.period_in_seconds(), Some(3600 * 24 * 7));
 
         periodic_task.frequency = Some(1);
         periodic_task.time_period = Some(TimePeriod::Monthly);
-        assert_eq!(periodic_task.period_in_seconds(), 3600 * 24 * 7 * 4);
+        assert_eq!(periodic_task.period_in_seconds(), Some(3600 * 24 * 7 * 4));
 
         periodic_task.frequency = Some(1);
         periodic_task.time_period = Some(TimePeriod::Yearly);
-        assert_eq!(periodic_task.period_in_seconds(), 3600 * 24 * 7 * 4 * 12);
+        assert_eq!(
+            periodic_task.period_in_seconds(),
+            Some(3600 * 24 * 7 * 4 * 12)
+        );
 
         periodic_task.frequency = Some(2);
         periodic_task.time_period = Some(TimePeriod::Daily);
-        assert_eq!(periodic_task.period_in_seconds(), 3600 * 24 / 2);
+        assert_eq!(periodic_task.period_in_seconds(), Some(3600 * 24 / 2));
 
         periodic_task.frequency = Some(2);
         periodic_task.time_period = Some(TimePeriod::Weekly);
-        assert_eq!(periodic_task.period_in_seconds(), 3600 * 24 / 2 * 7);
+        assert_eq!(periodic_task.period_in_seconds(), Some(3600 * 24 / 2 * 7));
 
         periodic_task.frequency = Some(2);
         periodic_task.time_period = Some(TimePeriod::Monthly);
-        assert_eq!(periodic_task.period_in_seconds(), 3600 * 24 / 2 * 7 * 4);
+        assert_eq!(
+            periodic_task.period_in_seconds(),
+            Some(3600 * 24 / 2 * 7 * 4)
+        );
 
         periodic_task.frequency = Some(2);
         periodic_task.time_period = Some(TimePeriod::Yearly);
         assert_eq!(
             periodic_task.period_in_seconds(),
-            3600 * 24 / 2 * 7 * 4 * 12
+            Some(3600 * 24 / 2 * 7 * 4 * 12)
         );
     }
 }
@@ -0,0 +1,170 @@
+use crate::time::{Duration, Time};
+use serde::{Deserialize, Serialize};
+
+/// Lengths of the intervals of the focus mode, in minutes.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct PomodoroSettings {
+    pub work_minutes: u32,
+    pub short_break_minutes: u32,
+    pub long_break_minutes: u32,
+    /// Work intervals before each long break.
+    pub long_break_every: u32,
+}
+
+impl Default for PomodoroSettings {
+    fn default() -> Self {
+        PomodoroSettings {
+            work_minutes: 25,
+            short_break_minutes: 5,
+            long_break_minutes: 15,
+            long_break_every: 4,
+        }
+    }
+}
+
+impl PomodoroSettings {
+    pub(crate) fn length(&self, phase: Phase) -> Duration {
+        let minutes = match phase {
+            Phase::Work => self.work_minutes,
+            Phase::ShortBreak => self.short_break_minutes,
+            Phase::LongBreak => self.long_break_minutes,
+        };
+        Duration::from_minutes(i64::from(minutes))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Phase {
+    Work,
+    ShortBreak,
+    LongBreak,
+}
+
+impl std::fmt::Display for Phase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Phase::Work => "Work",
+                Phase::ShortBreak => "Short break",
+                Phase::LongBreak => "Long break",
+            }
+        )
+    }
+}
+
+/// Work and break intervals on a task. Each interval starts when the previous one is found to
+/// have ended, so that time away from the computer does not count as work.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct FocusSession {
+    /// UID of the task, which identifies it while the list changes.
+    pub task: String,
+    pub phase: Phase,
+    pub phase_end: Time,
+    /// Work intervals completed in the session.
+    pub completed: u32,
+}
+
+impl FocusSession {
+    pub(crate) fn start(task: String, settings: &PomodoroSettings, now: &Time) -> Self {
+        FocusSession {
+            task,
+            phase: Phase::Work,
+            phase_end: now + &settings.length(Phase::Work),
+            completed: 0,
+        }
+    }
+
+    pub(crate) fn remaining(&self, now: &Time) -> Duration {
+        if now < &self.phase_end {
+            &self.phase_end - now
+        } else {
+            Duration::from_seconds(0)
+        }
+    }
+
+    /// Starts the next interval if the current one is over at `now`, returning the interval that
+    /// ended.
+    pub(crate) fn tick(&mut self, settings: &PomodoroSettings, now: &Time) -> Option<Phase> {
+        if now < &self.phase_end {
+            return None;
+        }
+        let ended = self.phase;
+        if ended == Phase::Work {
+            self.completed += 1;
+        }
+        self.next(settings, now);
+        Some(ended)
+    }
+
+    /// Ends the current interval early. Skipped work intervals are not completed.
+    pub(crate) fn skip(&mut self, settings: &PomodoroSettings, now: &Time) {
+        self.next(settings, now);
+    }
+
+    fn next(&mut self, settings: &PomodoroSettings, now: &Time) {
+        self.phase = match self.phase {
+            Phase::Work
+                if self.completed > 0
+                    && self
+                        .completed
+                        .is_multiple_of(settings.long_break_every.max(1)) =>
+            {
+                Phase::LongBreak
+            }
+            Phase::Work => Phase::ShortBreak,
+            Phase::ShortBreak | Phase::LongBreak => Phase::Work,
+        };
+        self.phase_end = now + &settings.length(self.phase);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn minute(minute: u32) -> Time {
+        Time::new(13, 5, 2024, 9 + minute / 60, minute % 60, 0).unwrap()
+    }
+
+    #[test]
+    fn intervals() {
+        let settings = PomodoroSettings {
+            long_break_every: 2,
+            ..PomodoroSettings::default()
+        };
+        let mut session = FocusSession::start("task".to_string(), &settings, &minute(0));
+        assert_eq!(session.remaining(&minute(10)), Duration::from_minutes(15));
+        assert_eq!(session.tick(&settings, &minute(24)), None);
+
+        assert_eq!(session.tick(&settings, &minute(25)), Some(Phase::Work));
+        assert_eq!(session.phase, Phase::ShortBreak);
+        assert_eq!(session.completed, 1);
+        // The break starts when the end of the work interval is noticed.
+        assert_eq!(
+            session.tick(&settings, &minute(31)),
+            Some(Phase::ShortBreak)
+        );
+        assert_eq!(session.phase_end, minute(56));
+
+        assert_eq!(session.tick(&settings, &minute(56)), Some(Phase::Work));
+        assert_eq!(session.phase, Phase::LongBreak);
+        assert_eq!(session.completed, 2);
+        assert_eq!(session.remaining(&minute(60)), Duration::from_minutes(11));
+        assert_eq!(session.remaining(&minute(90)), Duration::from_seconds(0));
+    }
+
+    #[test]
+    fn skipping() {
+        let settings = PomodoroSettings::default();
+        let mut session = FocusSession::start("task".to_string(), &settings, &minute(0));
+        session.skip(&settings, &minute(10));
+        assert_eq!(session.phase, Phase::ShortBreak);
+        assert_eq!(session.completed, 0);
+        assert_eq!(session.phase_end, minute(15));
+        session.skip(&settings, &minute(12));
+        assert_eq!(session.phase, Phase::Work);
+    }
+}
@@ -0,0 +1,259 @@
+use crate::clock::Clock;
+use crate::data::Data;
+use crate::periodic_task::{PeriodicTask, TimePeriod};
+use crate::task::{Priority, Task};
+use crate::time::{Duration, Time};
+
+/// Result of parsing a quick-add line such as
+/// `Review PR #backend !high due:fri snooze:2h estimate:1h30m` or `Water plants every:week`.
+///
+/// Words starting with `#` are tags and `!low`, `!medium` or `!high` (or `!`, `!!`, `!!!`) set
+/// the priority. `due:` and `snooze:` take a date in natural language, with underscores instead
+/// of spaces (`due:next_friday`), and `snooze:` also takes a duration (`snooze:2h`), as does
+/// `estimate:`, the expected effort. `every:day`, `every:week`, `every:month` or `every:year`
+/// make the line a periodic task starting at the due date, or now, which cannot have a snooze or
+/// an estimate. The remaining words form the description.
+#[derive(Debug, PartialEq)]
+pub(crate) enum QuickAdd {
+    Task(Box<Task>),
+    PeriodicTask(PeriodicTask),
+}
+
+#[derive(Debug, PartialEq)]
+pub struct QuickAddError {
+    pub message: String,
+}
+
+impl QuickAddError {
+    fn new(message: String) -> Self {
+        QuickAddError { message }
+    }
+}
+
+impl QuickAdd {
+    pub(crate) fn parse(line: &str, clock: &dyn Clock) -> Result<QuickAdd, QuickAddError> {
+        let now = &clock.now();
+        let mut description = vec![];
+        let mut tags = vec![];
+        let mut priority = None;
+        let mut due_date = None;
+        let mut snooze_until = None;
+        let mut time_period = None;
+        let mut estimate = None;
+
+        for word in line.split_whitespace() {
+            if let Some(tag) = word.strip_prefix('#').filter(|tag| !tag.is_empty()) {
+                tags.push(tag.to_string());
+            } else if let Some(level) = word.strip_prefix('!') {
+                priority = Some(parse_priority(level)?);
+            } else if let Some(date) = word.strip_prefix("due:") {
+                due_date = Some(parse_date(date, now)?);
+            } else if let Some(snooze) = word.strip_prefix("snooze:") {
+                snooze_until = Some(match Duration::parse(snooze) {
+                    Ok(duration) => now.checked_add(&duration).map_err(|_| {
+                        QuickAddError::new(format!("The snooze '{snooze}' is too long."))
+                    })?,
+                    Err(_) => parse_date(snooze, now)?,
+                });
+            } else if let Some(effort) = word.strip_prefix("estimate:") {
+                estimate = Some(Duration::parse(effort).map_err(|_| {
+                    QuickAddError::new(format!("Unrecognized estimate '{effort}'."))
+                })?);
+            } else if let Some(period) = word.strip_prefix("every:") {
+                time_period = Some(parse_time_period(period)?);
+            } else {
+                description.push(word);
+            }
+        }
+
+        let description = description.join(" ");
+        if description.is_empty() {
+            return Err(QuickAddError::new(
+                "The task has no description.".to_string(),
+            ));
+        }
+
+        if let Some(time_period) = time_period {
+            if snooze_until.is_some() || estimate.is_some() {
+                return Err(QuickAddError::new(
+                    "Periodic tasks cannot be snoozed or estimated.".to_string(),
+                ));
+            }
+            let initial_date = due_date.unwrap_or_else(|| now.clone());
+            return Ok(QuickAdd::PeriodicTask(PeriodicTask::recurring(
+                description,
+                time_period,
+                initial_date,
+                tags,
+                priority,
+            )));
+        }
+
+        let mut task = Task::new(0, clock);
+        task.edit(&description);
+        task.tags = tags;
+        task.priority = priority;
+        task.due_date = due_date;
+        task.estimate = estimate;
+        if let Some(snooze_until) = snooze_until {
+            task.snooze(snooze_until);
+        }
+        Ok(QuickAdd::Task(Box::new(task)))
+    }
+
+    pub(crate) fn add_to(self, data: &mut Data) {
+        match self {
+            QuickAdd::Task(mut task) => {
+                task.id = data.tasks.len();
+                data.tasks.push(*task);
+            }
+            QuickAdd::PeriodicTask(periodic_task) => data.periodic_tasks.push(periodic_task),
+        }
+    }
+}
+
+fn parse_priority(level: &str) -> Result<Priority, QuickAddError> {
+    match level.to_lowercase().as_str() {
+        "" | "low" | "l" => Ok(Priority::Low),
+        "!" | "medium" | "m" => Ok(Priority::Medium),
+        "!!" | "high" | "h" => Ok(Priority::High),
+        _ => Err(QuickAddError::new(format!("Unknown priority '!{level}'."))),
+    }
+}
+
+fn parse_date(text: &str, now: &Time) -> Result<Time, QuickAddError> {
+    Time::from_natural_language(&text.replace('_', " "), now)
+        .map_err(|_| QuickAddError::new(format!("Unrecognized date '{text}'.")))
+}
+
+fn parse_time_period(text: &str) -> Result<TimePeriod, QuickAddError> {
+    match text.to_lowercase().as_str() {
+        "day" | "daily" => Ok(TimePeriod::Daily),
+        "week" | "weekly" => Ok(TimePeriod::Weekly),
+        "month" | "monthly" => Ok(TimePeriod::Monthly),
+        "year" | "yearly" => Ok(TimePeriod::Yearly),
+        _ => Err(QuickAddError::new(format!("Unknown recurrence '{text}'."))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::FakeClock;
+
+    fn now() -> Time {
+        Time::from("2024-05-15T10:30:00Z")
+    }
+
+    fn parse_task(line: &str) -> Task {
+        match QuickAdd::parse(line, &FakeClock::new(now())).unwrap() {
+            QuickAdd::Task(task) => *task,
+            QuickAdd::PeriodicTask(_) => panic!("Expected a task"),
+        }
+    }
+
+    #[test]
+    fn plain_description() {
+        let task = parse_task("  Buy   milk ");
+        assert_eq!(task.description(), "Buy milk");
+        assert!(task.tags.is_empty());
+        assert_eq!(task.priority, None);
+        assert_eq!(task.due_date, None);
+        assert_eq!(task.snooze_information.snooze_until, None);
+    }
+
+    #[test]
+    fn tags_priority_and_dates() {
+        let task =
+            parse_task("Review PR #backend !high due:2024-05-17 snooze:2h #review estimate:1h30m");
+        assert_eq!(task.description(), "Review PR");
+        assert_eq!(task.tags, vec!["backend", "review"]);
+        assert_eq!(task.priority, Some(Priority::High));
+        assert_eq!(
+            task.due_date,
+            Some(Time::from_natural_language("2024-05-17", &now()).unwrap())
+        );
+        assert_eq!(
+            task.snooze_information.snooze_until,
+            Some(Time::from("2024-05-15T12:30:00Z"))
+        );
+        assert_eq!(task.estimate, Some(Duration::from_minutes(90)));
+    }
+
+    #[test]
+    fn priority_shorthands() {
+        assert_eq!(parse_task("a !").priority, Some(Priority::Low));
+        assert_eq!(parse_task("a !!").priority, Some(Priority::Medium));
+        assert_eq!(parse_task("a !!!").priority, Some(Priority::High));
+        assert_eq!(parse_task("a !Medium").priority, Some(Priority::Medium));
+    }
+
+    #[test]
+    fn dates_with_underscores() {
+        let task = parse_task("Call Ann due:in_3_days snooze:tomorrow_9am");
+        assert_eq!(
+            task.due_date,
+            Some(Time::from_natural_language("in 3 days", &now()).unwrap())
+        );
+        assert_eq!(
+            task.snooze_information.snooze_until,
+            Some(Time::from_natural_language("tomorrow 9am", &now()).unwrap())
+        );
+    }
+
+    #[test]
+    fn recurrence_creates_periodic_task() {
+        let parsed = QuickAdd::parse(
+            "Water plants #home every:week due:2024-05-17",
+            &FakeClock::new(now()),
+        );
+        let expected = PeriodicTask::recurring(
+            "Water plants".to_string(),
+            TimePeriod::Weekly,
+            Time::from_natural_language("2024-05-17", &now()).unwrap(),
+            vec!["home".to_string()],
+            None,
+        );
+        assert_eq!(parsed, Ok(QuickAdd::PeriodicTask(expected)));
+    }
+
+    #[test]
+    fn errors() {
+        for line in [
+            "",
+            "#only #tags",
+            "Task !urgent",
+            "Task due:someday",
+            "Task snooze:soonish",
+            "Task estimate:a_while",
+            "Task every:fortnight",
+            "Task snooze:9999999999999m",
+            "Task every:day snooze:2h",
+            "Task every:week estimate:1h",
+        ] {
+            assert!(
+                QuickAdd::parse(line, &FakeClock::new(now())).is_err(),
+                "{line}"
+            );
+        }
+    }
+
+    #[test]
+    fn add_to_data() {
+        let mut data = Data::default();
+        QuickAdd::parse("First", &FakeClock::new(now()))
+            .unwrap()
+            .add_to(&mut data);
+        QuickAdd::parse("Second", &FakeClock::new(now()))
+            .unwrap()
+            .add_to(&mut data);
+        QuickAdd::parse("Third every:day", &FakeClock::new(now()))
+            .unwrap()
+            .add_to(&mut data);
+
+        assert_eq!(data.tasks.len(), 2);
+        assert_eq!(data.tasks[1].id, 1);
+        assert_eq!(data.tasks[1].description(), "Second");
+        assert_eq!(data.periodic_tasks.len(), 1);
+    }
+}
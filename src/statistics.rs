@@ -0,0 +1,400 @@
+use crate::task::{EventKind, Task};
+use crate::time::{Duration, Time};
+use crate::time_tracking::tracked_between;
+
+const LISTED_TASKS: usize = 5;
+
+/// Productivity statistics of a list of tasks between two dates.
+#[derive(Debug, PartialEq)]
+pub(crate) struct Statistics {
+    /// Start of each day in the period, with the tasks created and completed on that day.
+    pub per_day: Vec<PeriodCount>,
+    /// Start of each week (Monday) in the period, with the tasks created and completed on it.
+    pub per_week: Vec<PeriodCount>,
+    pub created: usize,
+    pub completed: usize,
+    /// Average time from creation to completion of the tasks completed in the period.
+    pub average_time_to_completion: Option<Duration>,
+    /// Indices of the open tasks that were created first, oldest first.
+    pub oldest_open_tasks: Vec<usize>,
+    /// Total number of snoozes of the tasks created or completed in the period.
+    pub snoozes: u32,
+    /// Number of times tasks were reopened in the period, according to their history.
+    pub reopened: usize,
+    /// Indices of the tasks snoozed the most times, most snoozed first.
+    pub most_snoozed_tasks: Vec<usize>,
+    /// Time tracked in the period, running timers counting until now.
+    pub tracked: Duration,
+    /// Indices of the tasks with time tracked in the period and that time, most tracked first.
+    pub tracked_tasks: Vec<(usize, Duration)>,
+    /// Number of work intervals of the focus mode completed in the period.
+    pub pomodoros: usize,
+    /// Estimated effort of the tasks completed in the period, in order of completion.
+    pub estimates: Vec<EstimateComparison>,
+}
+
+/// Estimate of a completed task compared with the time tracked on it and the time it was open.
+#[derive(Debug, PartialEq)]
+pub(crate) struct EstimateComparison {
+    pub description: String,
+    pub estimated: Duration,
+    pub tracked: Duration,
+    /// Time from the creation to the completion of the task.
+    pub elapsed: Duration,
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) struct PeriodCount {
+    pub start: Time,
+    pub created: usize,
+    pub completed: usize,
+}
+
+impl Statistics {
+    /// Statistics of `tasks`, whose indices are listed, and of the `archived` tasks, which only
+    /// count towards the totals. Running timers count until `now`.
+    pub(crate) fn new(
+        tasks: &[Task],
+        archived: &[Task],
+        initial_date: &Time,
+        final_date: &Time,
+        now: &Time,
+    ) -> Self {
+        let in_period = |time: &Time| initial_date <= time && time <= final_date;
+        let completion_in_period =
+            |task: &Task| task.completed() && task.completion_time.as_ref().is_some_and(in_period);
+
+        let mut per_day = Self::periods(initial_date.start_of_day(), final_date, 1);
+        let mut per_week = Self::periods(initial_date.start_of_week(), final_date, 7);
+
+        let mut created = 0;
+        let mut completed = 0;
+        let mut completion_seconds = 0;
+        let mut snoozes = 0;
+        let mut reopened = 0;
+        let mut pomodoros = 0;
+        let tracked = tasks
+            .iter()
+            .chain(archived)
+            .map(|task| tracked_between(task, initial_date, final_date, now))
+            .sum();
+        let mut tracked_tasks = tasks
+            .iter()
+            .map(|task| tracked_between(task, initial_date, final_date, now))
+            .enumerate()
+            .filter(|(_, tracked)| tracked.num_seconds() > 0)
+            .collect::<Vec<_>>();
+        tracked_tasks.sort_by(|(_, tracked), (_, other)| other.cmp(tracked));
+        for task in tasks.iter().chain(archived) {
+            reopened += task
+                .history
+                .iter()
+                .filter(|event| event.kind == EventKind::Reopened && in_period(&event.time))
+                .count();
+            pomodoros += task.pomodoros.iter().filter(|time| in_period(time)).count();
+
+            let created_in_period = in_period(&task.creation_time);
+            if created_in_period {
+                created += 1;
+                Self::count(&mut per_day, &task.creation_time, |count| {
+                    count.created += 1
+                });
+                Self::count(&mut per_week, &task.creation_time, |count| {
+                    count.created += 1
+                });
+            }
+
+            let completed_in_period = completion_in_period(task);
+            if completed_in_period {
+                let completion_time = task.completion_time.as_ref().unwrap();
+                completed += 1;
+                completion_seconds += (completion_time - &task.creation_time).num_seconds();
+                Self::count(&mut per_day, completion_time, |count| count.completed += 1);
+                Self::count(&mut per_week, completion_time, |count| count.completed += 1);
+            }
+
+            if created_in_period || completed_in_period {
+                snoozes += task.snooze_information.count;
+            }
+        }
+
+        let mut estimated_tasks = tasks
+            .iter()
+            .chain(archived)
+            .filter(|task| task.estimate.is_some() && completion_in_period(task))
+            .collect::<Vec<_>>();
+        estimated_tasks.sort_by_key(|task| &task.completion_time);
+        let estimates = estimated_tasks
+            .into_iter()
+            .map(|task| EstimateComparison {
+                description: task.description().to_string(),
+                estimated: task.estimate.clone().unwrap(),
+                tracked: task.tracked(now),
+                elapsed: task.completion_time.as_ref().unwrap() - &task.creation_time,
+            })
+            .collect();
+
+        let average_time_to_completion =
+            (completed > 0).then(|| Duration::from_seconds(completion_seconds / completed as i64));
+
+        let mut oldest_open_tasks = tasks
+            .iter()
+            .enumerate()
+            .filter(|(_, task)| !task.completed())
+            .collect::<Vec<_>>();
+        oldest_open_tasks.sort_by_key(|(_, task)| &task.creation_time);
+        let oldest_open_tasks = oldest_open_tasks
+            .into_iter()
+            .take(LISTED_TASKS)
+            .map(|(index, _)| index)
+            .collect();
+
+        let mut most_snoozed_tasks = tasks
+            .iter()
+            .enumerate()
+            .filter(|(_, task)| task.snooze_information.count > 0)
+            .collect::<Vec<_>>();
+        most_snoozed_tasks
+            .sort_by_key(|(_, task)| std::cmp::Reverse(task.snooze_information.count));
+        let most_snoozed_tasks = most_snoozed_tasks
+            .into_iter()
+            .take(LISTED_TASKS)
+            .map(|(index, _)| index)
+            .collect();
+
+        Statistics {
+            per_day,
+            per_week,
+            created,
+            completed,
+            average_time_to_completion,
+            oldest_open_tasks,
+            snoozes,
+            reopened,
+            most_snoozed_tasks,
+            tracked,
+            tracked_tasks,
+            pomodoros,
+            estimates,
+        }
+    }
+
+    fn periods(first_start: Time, final_date: &Time, days: i64) -> Vec<PeriodCount> {
+        let mut periods = vec![];
+        let mut start = first_start;
+        while &start <= final_date {
+            let next_start = start.add_days(days);
+            periods.push(PeriodCount {
+                start,
+                created: 0,
+                completed: 0,
+            });
+            start = next_start;
+        }
+        periods
+    }
+
+    fn count(periods: &mut [PeriodCount], time: &Time, update: impl Fn(&mut PeriodCount)) {
+        let index = periods.partition_point(|period| &period.start <= time);
+        if index > 0 {
+            update(&mut periods[index - 1]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::SystemClock;
+    use crate::task::Event;
+    use crate::time::test_time as time;
+    use crate::time_tracking::TimeEntry;
+
+    fn task(created: Time, completed: Option<Time>, snoozes: u32) -> Task {
+        let mut task = Task::new(0, &SystemClock);
+        task.creation_time = created;
+        if let Some(completed) = completed {
+            task.set_completed(true, &SystemClock);
+            task.completion_time = Some(completed);
+        }
+        task.snooze_information.count = snoozes;
+        task
+    }
+
+    #[test]
+    fn empty_list() {
+        let statistics = Statistics::new(&[], &[], &time(13, 0), &time(14, 23), &time(14, 23));
+        assert_eq!(statistics.per_day.len(), 2);
+        assert_eq!(statistics.per_week.len(), 1);
+        assert_eq!(statistics.created, 0);
+        assert_eq!(statistics.completed, 0);
+        assert_eq!(statistics.average_time_to_completion, None);
+        assert!(statistics.oldest_open_tasks.is_empty());
+        assert!(statistics.most_snoozed_tasks.is_empty());
+    }
+
+    #[test]
+    fn counts_per_day_and_week() {
+        // 13 May 2024 is a Monday.
+        let tasks = [
+            task(time(1, 9), Some(time(13, 10)), 0),
+            task(time(13, 9), Some(time(13, 12)), 1),
+            task(time(14, 9), Some(time(20, 9)), 3),
+            task(time(15, 9), None, 2),
+            task(time(21, 9), Some(time(25, 9)), 0),
+        ];
+        let statistics = Statistics::new(&tasks, &[], &time(13, 8), &time(21, 8), &time(21, 8));
+
+        assert_eq!(statistics.per_day.len(), 9);
+        assert_eq!(statistics.per_day[0].start, time(13, 0));
+        assert_eq!(statistics.per_day[0].created, 1);
+        assert_eq!(statistics.per_day[0].completed, 2);
+        assert_eq!(statistics.per_day[7].start, time(20, 0));
+        assert_eq!(statistics.per_day[7].completed, 1);
+
+        assert_eq!(statistics.per_week.len(), 2);
+        assert_eq!(statistics.per_week[0].created, 3);
+        assert_eq!(statistics.per_week[0].completed, 2);
+        assert_eq!(statistics.per_week[1].start, time(20, 0));
+        assert_eq!(statistics.per_week[1].completed, 1);
+
+        assert_eq!(statistics.created, 3);
+        assert_eq!(statistics.completed, 3);
+        assert_eq!(statistics.snoozes, 6);
+    }
+
+    #[test]
+    fn archived_and_reopened_tasks() {
+        let mut reopened = task(time(13, 9), None, 0);
+        for day in [12, 13, 14] {
+            reopened.history.push(Event {
+                time: time(day, 10),
+                kind: EventKind::Reopened,
+            });
+        }
+        let archived = [task(time(1, 9), Some(time(13, 10)), 0)];
+        let statistics = Statistics::new(
+            &[reopened],
+            &archived,
+            &time(13, 0),
+            &time(13, 23),
+            &time(13, 23),
+        );
+        assert_eq!(statistics.reopened, 1);
+        assert_eq!(statistics.created, 1);
+        assert_eq!(statistics.completed, 1);
+        assert_eq!(statistics.oldest_open_tasks, vec![0]);
+    }
+
+    #[test]
+    fn pomodoros() {
+        let mut focused = task(time(13, 9), None, 0);
+        focused.pomodoros = vec![time(12, 10), time(13, 10), time(13, 11)];
+        let mut archived = task(time(1, 9), Some(time(13, 12)), 0);
+        archived.pomodoros = vec![time(13, 11)];
+        let statistics = Statistics::new(
+            &[focused],
+            &[archived],
+            &time(13, 0),
+            &time(13, 23),
+            &time(13, 23),
+        );
+        assert_eq!(statistics.pomodoros, 3);
+    }
+
+    #[test]
+    fn estimates() {
+        let mut tasks = [
+            task(time(13, 9), Some(time(14, 11)), 0),
+            task(time(13, 9), Some(time(13, 12)), 0),
+            task(time(13, 9), None, 0),
+            task(time(13, 9), Some(time(13, 12)), 0),
+        ];
+        for task in &mut tasks[..3] {
+            task.estimate = Some(Duration::from_hours(2));
+        }
+        tasks[1].time_entries = vec![TimeEntry {
+            start: time(13, 10),
+            end: Some(time(13, 11)),
+        }];
+
+        let statistics = Statistics::new(&tasks, &[], &time(13, 0), &time(14, 23), &time(14, 23));
+        assert_eq!(
+            statistics.estimates,
+            vec![
+                EstimateComparison {
+                    description: String::new(),
+                    estimated: Duration::from_hours(2),
+                    tracked: Duration::from_hours(1),
+                    elapsed: Duration::from_hours(3),
+                },
+                EstimateComparison {
+                    description: String::new(),
+                    estimated: Duration::from_hours(2),
+                    tracked: Duration::from_seconds(0),
+                    elapsed: Duration::from_hours(26),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn tracked_time() {
+        let mut tasks = [
+            task(time(13, 9), None, 0),
+            task(time(13, 9), None, 0),
+            task(time(13, 9), None, 0),
+        ];
+        tasks[0].time_entries = vec![TimeEntry {
+            start: time(12, 23),
+            end: Some(time(13, 2)),
+        }];
+        tasks[1].time_entries = vec![TimeEntry {
+            start: time(13, 10),
+            end: Some(time(13, 13)),
+        }];
+        let mut archived = task(time(13, 9), Some(time(13, 18)), 0);
+        archived.time_entries = vec![TimeEntry {
+            start: time(13, 15),
+            end: Some(time(13, 18)),
+        }];
+
+        let statistics = Statistics::new(
+            &tasks,
+            &[archived],
+            &time(13, 0),
+            &time(13, 23),
+            &time(13, 23),
+        );
+        assert_eq!(statistics.tracked, Duration::from_hours(8));
+        assert_eq!(
+            statistics.tracked_tasks,
+            vec![(1, Duration::from_hours(3)), (0, Duration::from_hours(2))]
+        );
+    }
+
+    #[test]
+    fn average_time_to_completion() {
+        let tasks = [
+            task(time(13, 9), Some(time(13, 10)), 0),
+            task(time(13, 9), Some(time(13, 12)), 0),
+            task(time(13, 9), None, 0),
+        ];
+        let statistics = Statistics::new(&tasks, &[], &time(13, 0), &time(13, 23), &time(13, 23));
+        assert_eq!(
+            statistics.average_time_to_completion,
+            Some(Duration::from_hours(2))
+        );
+    }
+
+    #[test]
+    fn oldest_open_and_most_snoozed_tasks() {
+        let tasks = (0..8)
+            .map(|index| task(time(20 - index, 9), None, index))
+            .collect::<Vec<_>>();
+        let statistics = Statistics::new(&tasks, &[], &time(13, 0), &time(13, 23), &time(13, 23));
+
+        assert_eq!(statistics.oldest_open_tasks, vec![7, 6, 5, 4, 3]);
+        assert_eq!(statistics.most_snoozed_tasks, vec![7, 6, 5, 4, 3]);
+    }
+}
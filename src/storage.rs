@@ -0,0 +1,725 @@
+use crate::caldav::SyncRecord;
+use crate::clock::SystemClock;
+use crate::data::{Data, FileError, FileErrorKind, Filters, Settings, TrashedTask};
+use crate::merge::Conflict;
+use crate::periodic_task::PeriodicTask;
+use crate::task::{Event, Task};
+use crate::time::Time;
+use crate::time_tracking::TimeEntry;
+use crate::todo_txt;
+use encryption::Encryption;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+mod encryption;
+mod sqlite;
+
+/// Where a task list is kept. The list is saved after every change, so implementations may write
+/// only what changed since their last load or save.
+pub(crate) trait Storage {
+    fn load(&mut self) -> Result<Data, FileError>;
+    fn save(&mut self, data: &Data) -> Result<(), FileError>;
+    /// Whether another program changed the list since the last load or save.
+    fn changed_externally(&mut self) -> bool;
+    /// Passphrase to decrypt the list with, and to encrypt it with from the next save on, or
+    /// `None` to save it unencrypted.
+    fn set_passphrase(&mut self, _passphrase: Option<String>) -> Result<(), FileError> {
+        Err(FileError {
+            message: "Only .ogz and .json lists can be encrypted.".to_string(),
+            kind: FileErrorKind::Write,
+        })
+    }
+}
+
+/// Modification times, sizes and contents hash of the files of a list, to notice changes made by
+/// other programs without reading the files while their times and sizes stay the same.
+#[derive(Default)]
+struct Stamp {
+    metadata: Vec<Option<(SystemTime, u64)>>,
+    hash: u64,
+}
+
+impl Stamp {
+    fn new(paths: &[&Path]) -> Self {
+        Stamp {
+            metadata: Stamp::metadata(paths),
+            hash: Stamp::hash(paths),
+        }
+    }
+
+    fn metadata(paths: &[&Path]) -> Vec<Option<(SystemTime, u64)>> {
+        paths
+            .iter()
+            .map(|path| {
+                let metadata = std::fs::metadata(path).ok()?;
+                Some((metadata.modified().ok()?, metadata.len()))
+            })
+            .collect()
+    }
+
+    fn hash(paths: &[&Path]) -> u64 {
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        for path in paths {
+            std::fs::read(path).ok().hash(&mut hasher);
+        }
+        hasher.finish()
+    }
+
+    fn changed(&mut self, paths: &[&Path]) -> bool {
+        let metadata = Stamp::metadata(paths);
+        if metadata == self.metadata {
+            return false;
+        }
+        if Stamp::hash(paths) != self.hash {
+            return true;
+        }
+        self.metadata = metadata;
+        false
+    }
+}
+
+/// Storage for `file_name`: a todo.txt list for `.txt` files, an SQLite database for `.db`,
+/// `.sqlite` and `.sqlite3` files, pretty-printed JSON for `.json` files, and otherwise the JSON
+/// `.ogz` format, adding the extension if it is missing.
+pub(crate) fn open(file_name: &str) -> Result<Box<dyn Storage>, FileError> {
+    open_as(file_name, file_name)
+}
+
+/// Storage for `file_name` in the format that `format_file_name` would have, for files without a
+/// meaningful name such as the temporary copies git hands to merge drivers.
+pub(crate) fn open_as(
+    file_name: &str,
+    format_file_name: &str,
+) -> Result<Box<dyn Storage>, FileError> {
+    let extension = Path::new(format_file_name)
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase());
+    match extension.as_deref() {
+        Some("txt") => Ok(Box::new(TodoTxtFiles::new(file_name))),
+        Some("db" | "sqlite" | "sqlite3") => Ok(Box::new(sqlite::SqliteFile::open(file_name)?)),
+        Some("ogz") => Ok(Box::new(JsonFile::new(file_name.to_string(), false))),
+        Some("json") => Ok(Box::new(JsonFile::new(file_name.to_string(), true))),
+        Some(_) => Err(FileError {
+            message: "Wrong file extension. Expected '.ogz', '.json', '.txt' or '.db'".to_string(),
+            kind: FileErrorKind::Write,
+        }),
+        None => Ok(Box::new(JsonFile::new(format!("{file_name}.ogz"), false))),
+    }
+}
+
+fn read(path: &Path) -> Result<String, FileError> {
+    std::fs::read_to_string(path).map_err(|_| FileError {
+        message: "Problem loading file".to_string(),
+        kind: FileErrorKind::Load,
+    })
+}
+
+fn read_bytes(path: &Path) -> Result<Vec<u8>, FileError> {
+    std::fs::read(path).map_err(|_| FileError {
+        message: "Problem loading file".to_string(),
+        kind: FileErrorKind::Load,
+    })
+}
+
+fn write(path: &Path, contents: impl AsRef<[u8]>) -> Result<(), FileError> {
+    std::fs::write(path, contents).map_err(|_| FileError {
+        message: "Problem saving file.".to_string(),
+        kind: FileErrorKind::Write,
+    })
+}
+
+/// The whole list serialized as JSON, rewritten on every save.
+///
+/// `.ogz` files hold a single line. Pretty files are meant to be kept in git: every field is on
+/// its own line, in the order the structures declare them, and the file ends with a newline, so
+/// saving unchanged data gives the same bytes and concurrent edits of different tasks merge
+/// cleanly. Both can be encrypted with a passphrase, which is detected when loading.
+struct JsonFile {
+    file_name: String,
+    pretty: bool,
+    stamp: Stamp,
+    passphrase: Option<String>,
+    encryption: Option<Encryption>,
+}
+
+impl JsonFile {
+    fn new(file_name: String, pretty: bool) -> Self {
+        JsonFile {
+            file_name,
+            pretty,
+            stamp: Stamp::default(),
+            passphrase: None,
+            encryption: None,
+        }
+    }
+}
+
+impl Storage for JsonFile {
+    fn load(&mut self) -> Result<Data, FileError> {
+        let path = Path::new(&self.file_name);
+        let mut serialized_data = read_bytes(path)?;
+        if encryption::is_encrypted(&serialized_data) {
+            let passphrase = self.passphrase.as_deref().ok_or_else(|| FileError {
+                message: "The list is encrypted. Enter its passphrase.".to_string(),
+                kind: FileErrorKind::Decryption,
+            })?;
+            let (decrypted, encryption) =
+                Encryption::decrypt(&serialized_data, passphrase, self.encryption.take())?;
+            serialized_data = decrypted;
+            self.encryption = Some(encryption);
+        }
+        self.stamp = Stamp::new(&[path]);
+        Ok(serde_json::from_slice::<Data>(&serialized_data)?)
+    }
+
+    fn save(&mut self, data: &Data) -> Result<(), FileError> {
+        let serialized_data = if self.pretty {
+            serde_json::to_string_pretty(data)? + "\n"
+        } else {
+            serde_json::to_string(data)?
+        };
+        let path = Path::new(&self.file_name);
+        match &self.passphrase {
+            Some(passphrase) => {
+                if self.encryption.is_none() {
+                    self.encryption = Some(Encryption::new(passphrase)?);
+                }
+                let encryption = self.encryption.as_ref().unwrap();
+                write(path, encryption.encrypt(serialized_data.as_bytes())?)?;
+            }
+            None => write(path, serialized_data)?,
+        }
+        self.stamp = Stamp::new(&[path]);
+        Ok(())
+    }
+
+    fn changed_externally(&mut self) -> bool {
+        self.stamp.changed(&[Path::new(&self.file_name)])
+    }
+
+    fn set_passphrase(&mut self, passphrase: Option<String>) -> Result<(), FileError> {
+        self.passphrase = passphrase;
+        self.encryption = None;
+        Ok(())
+    }
+}
+
+/// A todo.txt list: pending tasks in the given file, and completed and archived ones next to it in
+/// `<name>.done.txt` and `<name>.archive.txt`, or `done.txt` and `archive.txt` for `todo.txt`, so
+/// that other todo.txt tools can edit them. Periodic tasks, filters and what todo.txt lines cannot
+/// hold about tasks have no todo.txt equivalent and are kept, with the synchronization state,
+/// merge conflicts, trash and settings, in `<name>.organizer.json`, which is optional when
+/// loading.
+struct TodoTxtFiles {
+    todo: PathBuf,
+    done: PathBuf,
+    archive: PathBuf,
+    settings: PathBuf,
+    stamp: Stamp,
+}
+
+#[derive(Serialize, Deserialize)]
+struct TodoTxtSettings {
+    periodic_tasks: Vec<PeriodicTask>,
+    filters: Filters,
+    #[serde(default)]
+    sync_records: Vec<SyncRecord>,
+    #[serde(default)]
+    conflicts: Vec<Conflict>,
+    #[serde(default)]
+    trash: Vec<TrashedTask>,
+    #[serde(default)]
+    settings: Settings,
+    /// Fields of the listed and archived tasks that todo.txt lines do not hold, by task UID.
+    #[serde(default)]
+    tasks: BTreeMap<String, TaskExtras>,
+}
+
+/// What a todo.txt line cannot hold about a task.
+#[derive(Serialize, Deserialize, Default, PartialEq)]
+struct TaskExtras {
+    #[serde(default)]
+    history: Vec<Event>,
+    #[serde(default)]
+    time_entries: Vec<TimeEntry>,
+    #[serde(default)]
+    pomodoros: Vec<Time>,
+    #[serde(default)]
+    status: Option<String>,
+    #[serde(default)]
+    pinned_to: Option<Time>,
+    #[serde(default)]
+    created_by_periodic_task: bool,
+    #[serde(default)]
+    timer_paused: bool,
+    #[serde(default)]
+    snooze_count: u32,
+    /// Times of day that the dates on the line drop, for times that are not at midnight.
+    #[serde(default)]
+    creation_time: Option<Time>,
+    #[serde(default)]
+    completion_time: Option<Time>,
+    #[serde(default)]
+    due_date: Option<Time>,
+    #[serde(default)]
+    snooze_until: Option<Time>,
+}
+
+/// `time` if a todo.txt date cannot hold it, as it is not at midnight.
+fn time_of_day(time: &Time) -> Option<Time> {
+    match Time::from_iso_date(&time.iso_date()) {
+        Ok(midnight) if midnight == *time => None,
+        _ => Some(time.clone()),
+    }
+}
+
+/// Restores the time of day of `date` from `time`, unless another program changed the date.
+fn restore_time_of_day(date: &mut Time, time: Option<Time>) {
+    if let Some(time) = time.filter(|time| time.iso_date() == date.iso_date()) {
+        *date = time;
+    }
+}
+
+impl TaskExtras {
+    fn new(task: &Task) -> Self {
+        TaskExtras {
+            history: task.history.clone(),
+            time_entries: task.time_entries.clone(),
+            pomodoros: task.pomodoros.clone(),
+            status: task.status.clone(),
+            pinned_to: task.pinned_to.clone(),
+            created_by_periodic_task: task.created_by_periodic_task,
+            timer_paused: task.timer_paused,
+            snooze_count: task.snooze_information.count,
+            creation_time: time_of_day(&task.creation_time),
+            completion_time: task.completion_time.as_ref().and_then(time_of_day),
+            due_date: task.due_date.as_ref().and_then(time_of_day),
+            snooze_until: task
+                .snooze_information
+                .snooze_until
+                .as_ref()
+                .and_then(time_of_day),
+        }
+    }
+
+    fn apply(self, task: &mut Task) {
+        task.history = self.history;
+        task.time_entries = self.time_entries;
+        task.pomodoros = self.pomodoros;
+        task.status = self.status;
+        task.pinned_to = self.pinned_to;
+        task.created_by_periodic_task = self.created_by_periodic_task;
+        task.timer_paused = self.timer_paused;
+        task.snooze_information.count = self.snooze_count;
+        restore_time_of_day(&mut task.creation_time, self.creation_time);
+        let dates = [
+            (&mut task.completion_time, self.completion_time),
+            (&mut task.due_date, self.due_date),
+            (&mut task.snooze_information.snooze_until, self.snooze_until),
+        ];
+        for (date, time) in dates {
+            if let Some(date) = date {
+                restore_time_of_day(date, time);
+            }
+        }
+    }
+}
+
+impl TodoTxtFiles {
+    fn new(file_name: &str) -> Self {
+        let todo = PathBuf::from(file_name);
+        let stem = todo.file_stem().unwrap_or_default().to_string_lossy();
+        let (done, archive) = match stem.as_ref() {
+            "todo" => ("done.txt".to_string(), "archive.txt".to_string()),
+            _ => (format!("{stem}.done.txt"), format!("{stem}.archive.txt")),
+        };
+        TodoTxtFiles {
+            done: todo.with_file_name(done),
+            archive: todo.with_file_name(archive),
+            settings: todo.with_file_name(format!("{stem}.organizer.json")),
+            todo,
+            stamp: Stamp::default(),
+        }
+    }
+
+    fn paths(&self) -> [&Path; 4] {
+        [&self.todo, &self.done, &self.archive, &self.settings]
+    }
+}
+
+impl Storage for TodoTxtFiles {
+    fn load(&mut self) -> Result<Data, FileError> {
+        let mut tasks = todo_txt::parse(&read(&self.todo)?, &SystemClock);
+        if self.done.exists() {
+            tasks.extend(todo_txt::parse(&read(&self.done)?, &SystemClock));
+        }
+        tasks
+            .iter_mut()
+            .enumerate()
+            .for_each(|(index, task)| task.id = index);
+
+        let mut data = Data {
+            tasks,
+            ..Data::default()
+        };
+        if self.archive.exists() {
+            data.archive = todo_txt::parse(&read(&self.archive)?, &SystemClock);
+        }
+        if self.settings.exists() {
+            let settings: TodoTxtSettings = serde_json::from_str(&read(&self.settings)?)?;
+            data.periodic_tasks = settings.periodic_tasks;
+            data.filters = settings.filters;
+            data.sync_records = settings.sync_records;
+            data.conflicts = settings.conflicts;
+            data.trash = settings.trash;
+            data.settings = settings.settings;
+            let mut extras = settings.tasks;
+            for task in data.tasks.iter_mut().chain(&mut data.archive) {
+                if let Some(extras) = task.uid.as_ref().and_then(|uid| extras.remove(uid)) {
+                    extras.apply(task);
+                }
+            }
+        }
+        self.stamp = Stamp::new(&self.paths());
+        Ok(data)
+    }
+
+    fn save(&mut self, data: &Data) -> Result<(), FileError> {
+        let (done, todo): (Vec<&Task>, Vec<&Task>) =
+            data.tasks.iter().partition(|task| task.completed());
+        write(&self.todo, todo_txt::format(&todo))?;
+        if !done.is_empty() || self.done.exists() {
+            write(&self.done, todo_txt::format(&done))?;
+        }
+        if !data.archive.is_empty() || self.archive.exists() {
+            let archive = data.archive.iter().collect::<Vec<_>>();
+            write(&self.archive, todo_txt::format(&archive))?;
+        }
+
+        let settings = TodoTxtSettings {
+            periodic_tasks: data.periodic_tasks.clone(),
+            filters: data.filters.clone(),
+            sync_records: data.sync_records.clone(),
+            conflicts: data.conflicts.clone(),
+            trash: data.trash.clone(),
+            settings: data.settings.clone(),
+            tasks: data
+                .tasks
+                .iter()
+                .chain(&data.archive)
+                .filter_map(|task| Some((task.uid.clone()?, TaskExtras::new(task))))
+                .filter(|(_, extras)| *extras != TaskExtras::default())
+                .collect(),
+        };
+        write(&self.settings, serde_json::to_string(&settings)?)?;
+        self.stamp = Stamp::new(&self.paths());
+        Ok(())
+    }
+
+    fn changed_externally(&mut self) -> bool {
+        self.stamp
+            .changed(&[&self.todo, &self.done, &self.archive, &self.settings])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::SystemClock;
+    use crate::task::Priority;
+    use crate::time::Time;
+
+    fn directory(name: &str) -> PathBuf {
+        let directory = std::env::temp_dir().join(name);
+        let _ = std::fs::remove_dir_all(&directory);
+        std::fs::create_dir_all(&directory).unwrap();
+        directory
+    }
+
+    #[test]
+    fn storage_by_extension() {
+        assert!(open("list.ogz").is_ok());
+        assert!(open("list").is_ok());
+        assert!(open("todo.txt").is_ok());
+        let database = std::env::temp_dir().join("test_storage_by_extension.db");
+        assert!(open(database.to_str().unwrap()).is_ok());
+        assert!(open("list.json").is_ok());
+        assert!(matches!(
+            open("list.yaml").err().unwrap().kind,
+            FileErrorKind::Write
+        ));
+    }
+
+    #[test]
+    fn pretty_json() {
+        let directory = directory("test_storage_pretty_json");
+        let file_name = directory.join("organizer.json");
+        let mut storage = open(file_name.to_str().unwrap()).unwrap();
+
+        let mut data = Data::default();
+        for (index, description) in ["A", "B"].iter().enumerate() {
+            let mut task = Task::new(index, &SystemClock);
+            task.edit(description);
+            data.tasks.push(task);
+        }
+        storage.save(&data).unwrap();
+        let saved = std::fs::read_to_string(&file_name).unwrap();
+        assert!(saved.starts_with("{\n  \"tasks\": [\n    {\n      \"id\": 0,\n"));
+        assert!(saved.ends_with("}\n"));
+        assert_eq!(storage.load().unwrap(), data);
+
+        let loaded = storage.load().unwrap();
+        storage.save(&loaded).unwrap();
+        assert_eq!(std::fs::read_to_string(&file_name).unwrap(), saved);
+
+        // Editing one task changes only its lines.
+        data.tasks[1].edit("B, edited");
+        storage.save(&data).unwrap();
+        let edited = std::fs::read_to_string(&file_name).unwrap();
+        let changed_lines = saved
+            .lines()
+            .zip(edited.lines())
+            .filter(|(before, after)| before != after)
+            .collect::<Vec<_>>();
+        assert_eq!(saved.lines().count(), edited.lines().count());
+        assert_eq!(
+            changed_lines,
+            vec![(
+                "      \"description\": \"B\",",
+                "      \"description\": \"B, edited\","
+            )]
+        );
+    }
+
+    #[test]
+    fn external_changes() {
+        let directory = directory("test_storage_external_changes");
+        for file_name in ["list.ogz", "list.json", "todo.txt", "list.db"] {
+            let file_name = directory.join(file_name);
+            let mut storage = open(file_name.to_str().unwrap()).unwrap();
+            let mut data = Data::default();
+            data.tasks.push(Task::new(0, &SystemClock));
+            storage.save(&data).unwrap();
+            assert!(!storage.changed_externally(), "{file_name:?}");
+
+            let mut other = open(file_name.to_str().unwrap()).unwrap();
+            other.load().unwrap();
+            data.tasks[0].edit("Changed elsewhere");
+            other.save(&data).unwrap();
+            assert!(storage.changed_externally(), "{file_name:?}");
+            let loaded = storage.load().unwrap();
+            assert_eq!(loaded.tasks[0].description(), "Changed elsewhere");
+            assert!(!storage.changed_externally(), "{file_name:?}");
+
+            // Rewriting the same contents is not a change.
+            other.save(&data).unwrap();
+            assert!(!storage.changed_externally(), "{file_name:?}");
+        }
+    }
+
+    #[test]
+    fn encrypted_lists() {
+        let directory = directory("test_storage_encrypted");
+        let file_name = directory.join("list.ogz");
+        let file_name = file_name.to_str().unwrap();
+        let mut data = Data::default();
+        data.tasks.push(Task::new(0, &SystemClock));
+        data.tasks[0].edit("Call ACME about the contract");
+
+        let mut storage = open(file_name).unwrap();
+        storage.set_passphrase(Some("secret".to_string())).unwrap();
+        storage.save(&data).unwrap();
+        let contents = std::fs::read(file_name).unwrap();
+        assert!(encryption::is_encrypted(&contents));
+        assert!(!String::from_utf8_lossy(&contents).contains("ACME"));
+        assert_eq!(storage.load().unwrap(), data);
+
+        let error = open(file_name).unwrap().load().err().unwrap();
+        assert!(matches!(error.kind, FileErrorKind::Decryption));
+        let mut other = open(file_name).unwrap();
+        other.set_passphrase(Some("wrong".to_string())).unwrap();
+        let error = other.load().err().unwrap();
+        assert!(matches!(error.kind, FileErrorKind::Decryption));
+        other.set_passphrase(Some("secret".to_string())).unwrap();
+        assert_eq!(other.load().unwrap(), data);
+
+        other.set_passphrase(None).unwrap();
+        other.save(&data).unwrap();
+        assert_eq!(Data::load(file_name).unwrap(), data);
+
+        let todo_txt = directory.join("todo.txt");
+        let mut todo_txt = open(todo_txt.to_str().unwrap()).unwrap();
+        assert!(todo_txt.set_passphrase(Some("secret".to_string())).is_err());
+    }
+
+    #[test]
+    fn todo_txt_round_trip() {
+        let directory = directory("test_storage_todo_txt");
+        let file_name = directory.join("todo.txt");
+        let mut storage = open(file_name.to_str().unwrap()).unwrap();
+
+        let mut data = Data::default();
+        let mut pending = Task::new(0, &SystemClock);
+        pending.edit("Call Ann");
+        pending.tags = vec!["work".to_string()];
+        pending.priority = Some(Priority::High);
+        pending.creation_time = Time::from_iso_date("2024-05-10").unwrap();
+        pending.uid = Some("ann".to_string());
+        let mut completed = Task::new(1, &SystemClock);
+        completed.edit("Pay rent");
+        completed.uid = Some("rent".to_string());
+        completed.creation_time = Time::from_iso_date("2024-05-11").unwrap();
+        completed.set_completed(true, &SystemClock);
+        completed.completion_time = Time::from_iso_date("2024-05-15").ok();
+        data.tasks = vec![completed.clone(), pending.clone()];
+        data.filters.complete = true;
+
+        storage.save(&data).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&file_name).unwrap(),
+            "(A) 2024-05-10 Call Ann +work uid:ann\n"
+        );
+        assert_eq!(
+            std::fs::read_to_string(directory.join("done.txt")).unwrap(),
+            "x 2024-05-15 2024-05-11 Pay rent uid:rent\n"
+        );
+
+        let loaded = storage.load().unwrap();
+        pending.id = 0;
+        assert_eq!(loaded.tasks, vec![pending.clone(), completed.clone()]);
+        assert!(loaded.filters.complete);
+
+        // Archived tasks go to their own file.
+        data.tasks = vec![pending];
+        completed.id = 0;
+        data.archive = vec![completed];
+        data.settings.archive_after_days = Some(7);
+        storage.save(&data).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(directory.join("archive.txt")).unwrap(),
+            "x 2024-05-15 2024-05-11 Pay rent uid:rent\n"
+        );
+        let loaded = storage.load().unwrap();
+        assert_eq!(loaded.archive, data.archive);
+        assert_eq!(loaded.settings.archive_after_days, Some(7));
+    }
+
+    #[test]
+    fn todo_txt_keeps_what_lines_do_not_hold() {
+        let directory = directory("test_storage_todo_txt_extras");
+        let file_name = directory.join("todo.txt");
+        let mut storage = open(file_name.to_str().unwrap()).unwrap();
+
+        let start = Time::from_iso_date("2024-05-13").unwrap();
+        let clock = crate::clock::FakeClock::new(start.clone());
+        let mut task = Task::new(0, &clock);
+        task.edit("Write report");
+        task.record(crate::task::EventKind::Completed, &clock);
+        task.start_timer(&start);
+        task.pause_timer(&(&start + &crate::time::Duration::from_hours(1)));
+        task.pomodoros.push(start.clone());
+        task.status = Some("Review".to_string());
+        task.pinned_to = Some(start.clone());
+        task.created_by_periodic_task = true;
+        let mut archived = task.clone();
+        archived.uid = Some("archived".to_string());
+        archived.set_completed(true, &clock);
+        let data = Data {
+            tasks: vec![task],
+            archive: vec![archived],
+            ..Data::default()
+        };
+
+        storage.save(&data).unwrap();
+        let loaded = storage.load().unwrap();
+        assert_eq!(loaded.tasks, data.tasks);
+        assert_eq!(loaded.archive, data.archive);
+    }
+
+    #[test]
+    fn todo_txt_keeps_times_snoozes_and_blank_tasks() {
+        let directory = directory("test_storage_todo_txt_times");
+        let file_name = directory.join("todo.txt");
+        let mut storage = open(file_name.to_str().unwrap()).unwrap();
+
+        let nine = Time::new(13, 5, 2024, 9, 30, 0).unwrap();
+        let clock = crate::clock::FakeClock::new(nine.clone());
+        let mut task = Task::new(0, &clock);
+        task.edit("Call Ann");
+        task.due_date = Some(Time::new(14, 5, 2024, 9, 0, 0).unwrap());
+        task.snooze_information.snooze_until = Some(Time::new(14, 5, 2024, 8, 0, 0).unwrap());
+        task.snooze_information.count = 2;
+        let blank = Task::new(1, &clock);
+        let mut done = Task::new(2, &clock);
+        done.edit("Pay rent");
+        done.set_completed(true, &clock);
+        let data = Data {
+            tasks: vec![task, blank, done],
+            ..Data::default()
+        };
+
+        storage.save(&data).unwrap();
+        let loaded = storage.load().unwrap();
+        assert_eq!(loaded.tasks, data.tasks);
+
+        // A date changed by another todo.txt tool wins over the saved time of day.
+        let todo = std::fs::read_to_string(&file_name).unwrap();
+        std::fs::write(&file_name, todo.replace("due:2024-05-14", "due:2024-05-20")).unwrap();
+        let loaded = storage.load().unwrap();
+        assert_eq!(
+            loaded.tasks[0].due_date,
+            Time::from_iso_date("2024-05-20").ok()
+        );
+    }
+
+    #[test]
+    fn todo_txt_lists_in_one_directory() {
+        let directory = directory("test_storage_todo_txt_lists");
+        let mut data = Data::default();
+        for name in ["todo.txt", "work.txt"] {
+            let mut task = Task::new(0, &SystemClock);
+            task.edit(name);
+            task.set_completed(true, &SystemClock);
+            data.tasks = vec![task.clone()];
+            data.archive = vec![task];
+            let file_name = directory.join(name);
+            open(file_name.to_str().unwrap())
+                .unwrap()
+                .save(&data)
+                .unwrap();
+        }
+
+        let todo = open(directory.join("todo.txt").to_str().unwrap())
+            .unwrap()
+            .load();
+        let work = open(directory.join("work.txt").to_str().unwrap())
+            .unwrap()
+            .load();
+        assert_eq!(todo.unwrap().archive[0].description(), "todo.txt");
+        assert_eq!(work.unwrap().tasks[0].description(), "work.txt");
+        for name in [
+            "done.txt",
+            "archive.txt",
+            "work.done.txt",
+            "work.archive.txt",
+        ] {
+            assert!(directory.join(name).exists(), "{name}");
+        }
+    }
+
+    #[test]
+    fn todo_txt_edited_by_other_tools() {
+        let directory = directory("test_storage_todo_txt_external");
+        let file_name = directory.join("todo.txt");
+        std::fs::write(&file_name, "Buy milk @shop\n(B) Call Ann\n").unwrap();
+
+        let data = open(file_name.to_str().unwrap()).unwrap().load().unwrap();
+        assert_eq!(data.tasks.len(), 2);
+        assert_eq!(data.tasks[0].tags, vec!["shop"]);
+        assert_eq!(data.tasks[1].id, 1);
+        assert!(data.periodic_tasks.is_empty());
+    }
+}
@@ -0,0 +1,228 @@
+use crate::data::{FileError, FileErrorKind};
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, CHACHA20_POLY1305, NONCE_LEN};
+use ring::pbkdf2;
+use ring::rand::{SecureRandom, SystemRandom};
+use std::num::NonZeroU32;
+
+const MAGIC: &[u8] = b"OGZCRYPT";
+const VERSION: u8 = 1;
+const SALT_LEN: usize = 16;
+const HEADER_LEN: usize = MAGIC.len() + 1 + 4 + SALT_LEN + NONCE_LEN;
+/// PBKDF2 iterations for new files, as recommended by OWASP for PBKDF2-HMAC-SHA256.
+const ITERATIONS: u32 = 600_000;
+/// Most PBKDF2 iterations accepted from a header, which is read before it is authenticated, so
+/// that a tampered list cannot make opening it take forever.
+const MAX_ITERATIONS: u32 = 4 * ITERATIONS;
+
+fn decryption_error(message: &str) -> FileError {
+    FileError {
+        message: message.to_string(),
+        kind: FileErrorKind::Decryption,
+    }
+}
+
+fn random_error() -> FileError {
+    FileError {
+        message: "No random numbers available to encrypt the list.".to_string(),
+        kind: FileErrorKind::Write,
+    }
+}
+
+pub(super) fn is_encrypted(contents: &[u8]) -> bool {
+    contents.starts_with(MAGIC)
+}
+
+/// Passphrase encryption of JSON lists, keeping the key derived from the passphrase to save the
+/// list again without deriving it on every save.
+///
+/// An encrypted list starts with a 41-byte header, followed by the ChaCha20-Poly1305 encryption
+/// of the JSON list with its 16-byte tag:
+///
+/// | Bytes  | Contents                                              |
+/// |--------|-------------------------------------------------------|
+/// | 0..8   | `OGZCRYPT`                                            |
+/// | 8      | Format version, 1                                     |
+/// | 9..13  | PBKDF2-HMAC-SHA256 iterations, big-endian             |
+/// | 13..29 | PBKDF2 salt                                           |
+/// | 29..41 | Nonce                                                 |
+///
+/// The 256-bit key is derived from the passphrase with PBKDF2, and the header is authenticated
+/// along with the list. Every save uses a new random nonce.
+pub(super) struct Encryption {
+    iterations: u32,
+    salt: [u8; SALT_LEN],
+    key: [u8; 32],
+}
+
+impl Encryption {
+    /// Encryption with a new random salt.
+    pub(super) fn new(passphrase: &str) -> Result<Self, FileError> {
+        let mut salt = [0; SALT_LEN];
+        SystemRandom::new()
+            .fill(&mut salt)
+            .map_err(|_| random_error())?;
+        Ok(Encryption::derive(passphrase, ITERATIONS, salt))
+    }
+
+    fn derive(passphrase: &str, iterations: u32, salt: [u8; SALT_LEN]) -> Self {
+        let mut key = [0; 32];
+        pbkdf2::derive(
+            pbkdf2::PBKDF2_HMAC_SHA256,
+            NonZeroU32::new(iterations).unwrap_or(NonZeroU32::MIN),
+            &salt,
+            passphrase.as_bytes(),
+            &mut key,
+        );
+        Encryption {
+            iterations,
+            salt,
+            key,
+        }
+    }
+
+    fn key(&self) -> LessSafeKey {
+        LessSafeKey::new(UnboundKey::new(&CHACHA20_POLY1305, &self.key).unwrap())
+    }
+
+    pub(super) fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>, FileError> {
+        let mut nonce = [0; NONCE_LEN];
+        SystemRandom::new()
+            .fill(&mut nonce)
+            .map_err(|_| random_error())?;
+
+        let mut contents = MAGIC.to_vec();
+        contents.push(VERSION);
+        contents.extend(self.iterations.to_be_bytes());
+        contents.extend(self.salt);
+        contents.extend(nonce);
+        let mut encrypted = plaintext.to_vec();
+        self.key()
+            .seal_in_place_append_tag(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::from(&contents),
+                &mut encrypted,
+            )
+            .map_err(|_| random_error())?;
+        contents.extend(encrypted);
+        Ok(contents)
+    }
+
+    /// Decrypts an encrypted list, reusing `known` if the list was encrypted with its key and
+    /// otherwise deriving the key from `passphrase`. Returns the list and its encryption.
+    pub(super) fn decrypt(
+        contents: &[u8],
+        passphrase: &str,
+        known: Option<Encryption>,
+    ) -> Result<(Vec<u8>, Encryption), FileError> {
+        if contents.len() < HEADER_LEN || !is_encrypted(contents) {
+            return Err(decryption_error("The encrypted list is truncated."));
+        }
+        let (header, encrypted) = contents.split_at(HEADER_LEN);
+        if header[MAGIC.len()] != VERSION {
+            return Err(decryption_error(
+                "The list was encrypted by a newer version of Organizer.",
+            ));
+        }
+        let iterations = u32::from_be_bytes(header[9..13].try_into().unwrap());
+        if iterations > MAX_ITERATIONS {
+            return Err(decryption_error(
+                "The list is corrupted: it asks for too many key derivation iterations.",
+            ));
+        }
+        let salt: [u8; SALT_LEN] = header[13..29].try_into().unwrap();
+        let nonce: [u8; NONCE_LEN] = header[29..HEADER_LEN].try_into().unwrap();
+
+        let encryption = match known {
+            Some(known) if known.iterations == iterations && known.salt == salt => known,
+            _ => Encryption::derive(passphrase, iterations, salt),
+        };
+        let mut plaintext = encrypted.to_vec();
+        let length = encryption
+            .key()
+            .open_in_place(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::from(header),
+                &mut plaintext,
+            )
+            .map_err(|_| decryption_error("Wrong passphrase, or the list is corrupted."))?
+            .len();
+        plaintext.truncate(length);
+        Ok((plaintext, encryption))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encryption(passphrase: &str) -> Encryption {
+        Encryption::derive(passphrase, 1000, [7; SALT_LEN])
+    }
+
+    #[test]
+    fn round_trip() {
+        let encrypted = encryption("secret").encrypt(b"{\"tasks\":[]}").unwrap();
+        assert!(is_encrypted(&encrypted));
+        assert_eq!(&encrypted[..9], b"OGZCRYPT\x01");
+        assert_eq!(encrypted.len(), HEADER_LEN + 12 + 16);
+
+        let (plaintext, encryption) = Encryption::decrypt(&encrypted, "secret", None).unwrap();
+        assert_eq!(plaintext, b"{\"tasks\":[]}");
+        assert_eq!(encryption.iterations, 1000);
+
+        // New nonces make every save different.
+        assert_ne!(encryption.encrypt(b"{\"tasks\":[]}").unwrap(), encrypted);
+    }
+
+    #[test]
+    fn wrong_passphrase_and_corruption() {
+        let encrypted = encryption("secret").encrypt(b"{\"tasks\":[]}").unwrap();
+        let decrypt = |contents: &[u8], passphrase| {
+            Encryption::decrypt(contents, passphrase, None)
+                .err()
+                .unwrap()
+                .kind
+        };
+        assert!(matches!(
+            decrypt(&encrypted, "wrong"),
+            FileErrorKind::Decryption
+        ));
+
+        let mut corrupted = encrypted.clone();
+        *corrupted.last_mut().unwrap() ^= 1;
+        assert!(matches!(
+            decrypt(&corrupted, "secret"),
+            FileErrorKind::Decryption
+        ));
+        // The header is authenticated too.
+        let mut corrupted = encrypted.clone();
+        corrupted[12] ^= 1;
+        assert!(matches!(
+            decrypt(&corrupted, "secret"),
+            FileErrorKind::Decryption
+        ));
+        assert!(matches!(
+            decrypt(&encrypted[..20], "secret"),
+            FileErrorKind::Decryption
+        ));
+        // Tampered iterations are rejected before deriving a key with them.
+        let mut corrupted = encrypted.clone();
+        corrupted[9..13].copy_from_slice(&u32::MAX.to_be_bytes());
+        assert!(matches!(
+            decrypt(&corrupted, "secret"),
+            FileErrorKind::Decryption
+        ));
+    }
+
+    #[test]
+    fn known_keys_are_reused() {
+        let encrypted = encryption("secret").encrypt(b"[]").unwrap();
+        // With the same salt, the known key is used and the passphrase is not needed.
+        let (plaintext, _) =
+            Encryption::decrypt(&encrypted, "", Some(encryption("secret"))).unwrap();
+        assert_eq!(plaintext, b"[]");
+
+        let other_salt = Encryption::derive("secret", 1000, [8; SALT_LEN]);
+        assert!(Encryption::decrypt(&encrypted, "", Some(other_salt)).is_err());
+    }
+}
@@ -0,0 +1,460 @@
+use super::{Stamp, Storage};
+use crate::data::{Data, FileError, FileErrorKind, TrashedTask};
+use crate::ical;
+use crate::task::Task;
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::PathBuf;
+
+/// Tables holding one row per task of the list, of the archive and of the trash.
+const TABLES: [&str; 3] = ["tasks", "archive", "trash"];
+
+/// An SQLite database with one row per task, keyed by its UID, in a table for the list, one for
+/// the archive and one for the trash. Each task is stored as JSON so that new task fields need no
+/// schema migration, and the order of each table is kept apart as a list of keys. Saving writes
+/// only the tasks that differ from the last load or save, so editing, adding or deleting one task
+/// in a long list is cheap.
+pub(super) struct SqliteFile {
+    connection: Connection,
+    saved: Option<Data>,
+    /// Rows of the tables as last loaded or saved, so that a save only serializes the new data.
+    saved_rows: Option<[Vec<(String, String)>; 3]>,
+    path: PathBuf,
+    stamp: Stamp,
+}
+
+fn load_error(error: impl ToString) -> FileError {
+    FileError {
+        message: error.to_string(),
+        kind: FileErrorKind::Load,
+    }
+}
+
+fn write_error(error: impl ToString) -> FileError {
+    FileError {
+        message: error.to_string(),
+        kind: FileErrorKind::Write,
+    }
+}
+
+/// Keys of the rows of `tasks`: their UIDs, or for tasks from older lists that have none, one
+/// made from their creation time, numbered if several tasks share it.
+fn keys<'a>(tasks: impl Iterator<Item = &'a Task>) -> Vec<String> {
+    let mut used = HashSet::new();
+    tasks
+        .map(|task| {
+            let uid = task.uid.clone().unwrap_or_else(|| ical::uid(task));
+            let key = (1..)
+                .map(|number| match number {
+                    1 => uid.clone(),
+                    _ => format!("{uid}-{number}"),
+                })
+                .find(|key| !used.contains(key))
+                .unwrap_or(uid);
+            used.insert(key.clone());
+            key
+        })
+        .collect()
+}
+
+/// Rows of each table, by key, in list order.
+fn rows(data: &Data) -> [Vec<(String, String)>; 3] {
+    fn to_rows<T: Serialize>(items: &[T], keys: Vec<String>) -> Vec<(String, String)> {
+        keys.into_iter()
+            .zip(items)
+            .map(|(key, item)| (key, serde_json::to_string(item).unwrap_or_default()))
+            .collect()
+    }
+    [
+        to_rows(&data.tasks, keys(data.tasks.iter())),
+        to_rows(&data.archive, keys(data.archive.iter())),
+        to_rows(
+            &data.trash,
+            keys(data.trash.iter().map(|trashed| &trashed.task)),
+        ),
+    ]
+}
+
+fn save_setting(transaction: &Transaction, name: &str, value: String) -> Result<(), FileError> {
+    transaction
+        .execute(
+            "INSERT OR REPLACE INTO settings (name, value) VALUES (?1, ?2)",
+            [name, &value],
+        )
+        .map_err(write_error)?;
+    Ok(())
+}
+
+/// Writes the rows of `table` that differ from `saved`, deletes the ones that are gone and
+/// updates the order of the table if it changed.
+fn save_table(
+    transaction: &Transaction,
+    table: &str,
+    rows: &[(String, String)],
+    saved: &[(String, String)],
+) -> Result<(), FileError> {
+    let saved_rows = saved
+        .iter()
+        .map(|(key, value)| (key, value))
+        .collect::<HashMap<_, _>>();
+    for (key, value) in rows {
+        if saved_rows.get(key) != Some(&value) {
+            transaction
+                .execute(
+                    &format!("INSERT OR REPLACE INTO {table} (key, value) VALUES (?1, ?2)"),
+                    params![key, value],
+                )
+                .map_err(write_error)?;
+        }
+    }
+    let keys = rows.iter().map(|(key, _)| key).collect::<Vec<_>>();
+    let current_keys = keys.iter().copied().collect::<HashSet<_>>();
+    for (key, _) in saved {
+        if !current_keys.contains(key) {
+            transaction
+                .execute(&format!("DELETE FROM {table} WHERE key = ?1"), [key])
+                .map_err(write_error)?;
+        }
+    }
+    if saved.iter().map(|(key, _)| key).ne(keys.iter().copied()) {
+        save_setting(
+            transaction,
+            &format!("{table}_order"),
+            serde_json::to_string(&keys)?,
+        )?;
+    }
+    Ok(())
+}
+
+impl SqliteFile {
+    pub(super) fn open(file_name: &str) -> Result<Self, FileError> {
+        let connection = Connection::open(file_name).map_err(load_error)?;
+        let tables = TABLES
+            .iter()
+            .map(|table| {
+                format!(
+                    "CREATE TABLE IF NOT EXISTS {table} (
+                        key TEXT PRIMARY KEY,
+                        value TEXT NOT NULL
+                    );"
+                )
+            })
+            .collect::<String>();
+        connection
+            .execute_batch(&format!(
+                "{tables}
+                CREATE TABLE IF NOT EXISTS settings (
+                    name TEXT PRIMARY KEY,
+                    value TEXT NOT NULL
+                );"
+            ))
+            .map_err(load_error)?;
+        Ok(SqliteFile {
+            connection,
+            saved: None,
+            saved_rows: None,
+            path: PathBuf::from(file_name),
+            stamp: Stamp::default(),
+        })
+    }
+
+    fn setting<T: DeserializeOwned>(&self, name: &str) -> Result<Option<T>, FileError> {
+        let value: Option<String> = self
+            .connection
+            .query_row(
+                "SELECT value FROM settings WHERE name = ?1",
+                [name],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(load_error)?;
+        Ok(value
+            .map(|value| serde_json::from_str(&value))
+            .transpose()?)
+    }
+
+    /// The items of `table` in their saved order. Rows missing from the order follow, by key.
+    fn table<T: DeserializeOwned>(&self, table: &str) -> Result<Vec<T>, FileError> {
+        let mut statement = self
+            .connection
+            .prepare(&format!("SELECT key, value FROM {table}"))
+            .map_err(load_error)?;
+        let mut rows = statement
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })
+            .map_err(load_error)?
+            .collect::<Result<BTreeMap<_, _>, _>>()
+            .map_err(load_error)?;
+        let order: Vec<String> = self.setting(&format!("{table}_order"))?.unwrap_or_default();
+        let mut values = order
+            .iter()
+            .filter_map(|key| rows.remove(key))
+            .collect::<Vec<_>>();
+        values.extend(rows.into_values());
+        values
+            .iter()
+            .map(|value| Ok(serde_json::from_str(value)?))
+            .collect()
+    }
+}
+
+impl Storage for SqliteFile {
+    fn load(&mut self) -> Result<Data, FileError> {
+        let default = Data::default();
+        let data = Data {
+            tasks: self.table::<Task>("tasks")?,
+            periodic_tasks: self
+                .setting("periodic_tasks")?
+                .unwrap_or(default.periodic_tasks),
+            filters: self.setting("filters")?.unwrap_or(default.filters),
+            sync_records: self
+                .setting("sync_records")?
+                .unwrap_or(default.sync_records),
+            conflicts: self.setting("conflicts")?.unwrap_or(default.conflicts),
+            archive: self.table::<Task>("archive")?,
+            trash: self.table::<TrashedTask>("trash")?,
+            settings: self.setting("settings")?.unwrap_or(default.settings),
+        };
+        self.saved_rows = Some(rows(&data));
+        self.saved = Some(data.clone());
+        self.stamp = Stamp::new(&[&self.path]);
+        Ok(data)
+    }
+
+    fn save(&mut self, data: &Data) -> Result<(), FileError> {
+        let transaction = self.connection.transaction().map_err(write_error)?;
+        let saved = self.saved.as_ref();
+
+        let saved_rows = match &self.saved_rows {
+            Some(saved_rows) => saved_rows,
+            None => {
+                // Nothing is known about the rows in the file, which are replaced.
+                for table in TABLES {
+                    transaction
+                        .execute(&format!("DELETE FROM {table}"), [])
+                        .map_err(write_error)?;
+                }
+                &Default::default()
+            }
+        };
+        let data_rows = rows(data);
+        for ((table, rows), saved_rows) in TABLES.iter().zip(&data_rows).zip(saved_rows) {
+            save_table(&transaction, table, rows, saved_rows)?;
+        }
+
+        if saved.is_none_or(|saved| saved.periodic_tasks != data.periodic_tasks) {
+            save_setting(
+                &transaction,
+                "periodic_tasks",
+                serde_json::to_string(&data.periodic_tasks)?,
+            )?;
+        }
+        if saved.is_none_or(|saved| saved.filters != data.filters) {
+            save_setting(
+                &transaction,
+                "filters",
+                serde_json::to_string(&data.filters)?,
+            )?;
+        }
+        if saved.is_none_or(|saved| saved.sync_records != data.sync_records) {
+            save_setting(
+                &transaction,
+                "sync_records",
+                serde_json::to_string(&data.sync_records)?,
+            )?;
+        }
+        if saved.is_none_or(|saved| saved.conflicts != data.conflicts) {
+            save_setting(
+                &transaction,
+                "conflicts",
+                serde_json::to_string(&data.conflicts)?,
+            )?;
+        }
+        if saved.is_none_or(|saved| saved.settings != data.settings) {
+            save_setting(
+                &transaction,
+                "settings",
+                serde_json::to_string(&data.settings)?,
+            )?;
+        }
+
+        transaction.commit().map_err(write_error)?;
+        self.saved_rows = Some(data_rows);
+        self.saved = Some(data.clone());
+        self.stamp = Stamp::new(&[&self.path]);
+        Ok(())
+    }
+
+    fn changed_externally(&mut self) -> bool {
+        self.stamp.changed(&[&self.path])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::{Clock, SystemClock};
+
+    fn database(name: &str) -> String {
+        let file_name = std::env::temp_dir().join(name);
+        let _ = std::fs::remove_file(&file_name);
+        file_name.to_str().unwrap().to_string()
+    }
+
+    fn data(descriptions: &[&str]) -> Data {
+        let mut data = Data::default();
+        for (index, description) in descriptions.iter().enumerate() {
+            let mut task = Task::new(index, &SystemClock);
+            task.edit(description);
+            data.tasks.push(task);
+        }
+        data
+    }
+
+    #[test]
+    fn save_and_load() {
+        let file_name = database("test_sqlite_save_and_load.db");
+        let mut data = data(&["A", "B", "C"]);
+        data.filters.snoozed = true;
+        SqliteFile::open(&file_name).unwrap().save(&data).unwrap();
+
+        let loaded = SqliteFile::open(&file_name).unwrap().load().unwrap();
+        assert_eq!(loaded, data);
+    }
+
+    #[test]
+    fn empty_database() {
+        let file_name = database("test_sqlite_empty.db");
+        let loaded = SqliteFile::open(&file_name).unwrap().load().unwrap();
+        assert_eq!(loaded, Data::default());
+    }
+
+    #[test]
+    fn saves_only_changed_tasks() {
+        let file_name = database("test_sqlite_incremental.db");
+        let mut storage = SqliteFile::open(&file_name).unwrap();
+        let mut data = data(&["A", "B", "C"]);
+        storage.save(&data).unwrap();
+
+        // A change made behind the storage's back survives as long as that task is not
+        // modified, which shows that unchanged rows are not rewritten.
+        let mut external = data.tasks[1].clone();
+        external.edit("Changed elsewhere");
+        storage
+            .connection
+            .execute(
+                "UPDATE tasks SET value = ?1 WHERE key = ?2",
+                [
+                    serde_json::to_string(&external).unwrap(),
+                    external.uid.clone().unwrap(),
+                ],
+            )
+            .unwrap();
+
+        data.tasks[0].edit("A, edited");
+        storage.save(&data).unwrap();
+        let loaded = storage.load().unwrap();
+        assert_eq!(loaded.tasks[0].description(), "A, edited");
+        assert_eq!(loaded.tasks[1].description(), "Changed elsewhere");
+    }
+
+    #[test]
+    fn removed_tasks() {
+        let file_name = database("test_sqlite_removed.db");
+        let mut storage = SqliteFile::open(&file_name).unwrap();
+        let mut data = data(&["A", "B", "C"]);
+        storage.save(&data).unwrap();
+
+        data.tasks.remove(1);
+        storage.save(&data).unwrap();
+        let loaded = SqliteFile::open(&file_name).unwrap().load().unwrap();
+        let descriptions = loaded
+            .tasks
+            .iter()
+            .map(Task::description)
+            .collect::<Vec<_>>();
+        assert_eq!(descriptions, vec!["A", "C"]);
+    }
+
+    #[test]
+    fn removing_a_task_leaves_later_rows_alone() {
+        let file_name = database("test_sqlite_removed_rows.db");
+        let mut storage = SqliteFile::open(&file_name).unwrap();
+        let mut data = data(&["A", "B", "C"]);
+        storage.save(&data).unwrap();
+
+        let mut external = data.tasks[2].clone();
+        external.edit("Changed elsewhere");
+        storage
+            .connection
+            .execute(
+                "UPDATE tasks SET value = ?1 WHERE key = ?2",
+                [
+                    serde_json::to_string(&external).unwrap(),
+                    external.uid.clone().unwrap(),
+                ],
+            )
+            .unwrap();
+
+        data.tasks.remove(0);
+        storage.save(&data).unwrap();
+        let loaded = storage.load().unwrap();
+        let descriptions = loaded
+            .tasks
+            .iter()
+            .map(Task::description)
+            .collect::<Vec<_>>();
+        assert_eq!(descriptions, vec!["B", "Changed elsewhere"]);
+    }
+
+    #[test]
+    fn archive_and_trash_tables() {
+        let file_name = database("test_sqlite_archive_trash.db");
+        let mut storage = SqliteFile::open(&file_name).unwrap();
+        let mut data = data(&["A", "B", "C", "D"]);
+        data.archive.push(data.tasks.remove(1));
+        data.trash.push(TrashedTask {
+            task: data.tasks.remove(1),
+            deletion_time: SystemClock.now(),
+        });
+        storage.save(&data).unwrap();
+
+        let count = |table: &str| -> usize {
+            storage
+                .connection
+                .query_row(&format!("SELECT COUNT(*) FROM {table}"), [], |row| {
+                    row.get(0)
+                })
+                .unwrap()
+        };
+        assert_eq!(
+            (count("tasks"), count("archive"), count("trash")),
+            (2, 1, 1)
+        );
+        assert_eq!(SqliteFile::open(&file_name).unwrap().load().unwrap(), data);
+    }
+
+    #[test]
+    fn tasks_without_uid() {
+        let file_name = database("test_sqlite_without_uid.db");
+        let mut data = data(&["A", "B"]);
+        let creation_time = data.tasks[0].creation_time.clone();
+        for task in &mut data.tasks {
+            task.uid = None;
+            task.creation_time = creation_time.clone();
+        }
+        SqliteFile::open(&file_name).unwrap().save(&data).unwrap();
+        assert_eq!(SqliteFile::open(&file_name).unwrap().load().unwrap(), data);
+    }
+
+    #[test]
+    fn invalid_database() {
+        let file_name = database("test_sqlite_invalid.db");
+        std::fs::write(&file_name, "not a database").unwrap();
+        let result = SqliteFile::open(&file_name);
+        assert!(matches!(result.err().unwrap().kind, FileErrorKind::Load));
+    }
+}
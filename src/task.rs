@@ -1,6 +1,10 @@
+use crate::clock::Clock;
 use crate::tasktoiced::FADE_OUT_TIME;
 use crate::time::{Duration, Time};
+use crate::time_tracking::TimeEntry;
+use ring::rand::{SecureRandom, SystemRandom};
 use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
 
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
 pub(crate) struct Task {
@@ -11,6 +15,127 @@ pub(crate) struct Task {
     pub completion_time: Option<Time>,
     #[serde(default)]
     pub snooze_information: SnoozeInformation,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub priority: Option<Priority>,
+    #[serde(default)]
+    pub due_date: Option<Time>,
+    /// Identifier in calendars, CalDAV servers and todo.txt lists: a random one given when the
+    /// task is created, or the one it was imported with. Tasks from older lists may have none.
+    #[serde(default)]
+    pub uid: Option<String>,
+    /// What happened to the task since it was created, oldest first. Events are only appended.
+    #[serde(default)]
+    pub history: Vec<Event>,
+    /// Whether the history is shown below the task.
+    #[serde(skip)]
+    pub history_visible: bool,
+    /// Time worked on the task, in the order it was tracked.
+    #[serde(default)]
+    pub time_entries: Vec<TimeEntry>,
+    /// Effort expected to complete the task.
+    #[serde(default)]
+    pub estimate: Option<Duration>,
+    /// End of each work interval completed on the task in the focus mode.
+    #[serde(default)]
+    pub pomodoros: Vec<Time>,
+    /// Name of the board column of the task, if it was moved on the board.
+    #[serde(default)]
+    pub status: Option<String>,
+    /// Start of the day the task was planned for in the agenda.
+    #[serde(default)]
+    pub pinned_to: Option<Time>,
+    /// Whether a periodic task created the task.
+    #[serde(default)]
+    pub created_by_periodic_task: bool,
+    /// Whether the timer was paused, to be resumed, rather than stopped.
+    #[serde(default)]
+    pub timer_paused: bool,
+    /// Time entry being added by hand.
+    #[serde(skip)]
+    pub time_entry_input: TimeEntryInput,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub(crate) struct TimeEntryInput {
+    pub visible: bool,
+    pub start: String,
+    pub end: String,
+    pub estimate: String,
+}
+
+/// Something that happened to a task.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub(crate) struct Event {
+    pub time: Time,
+    pub kind: EventKind,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub(crate) enum EventKind {
+    /// Not recorded, since the creation time is kept in the task, but listed first in its
+    /// history.
+    Created,
+    Edited {
+        description: String,
+    },
+    Completed,
+    Reopened,
+    Snoozed {
+        until: Time,
+    },
+    Unsnoozed,
+    /// Moved between two positions in the list, counted from zero.
+    Moved {
+        from: usize,
+        to: usize,
+    },
+    /// Moved to another column of the board.
+    StatusChanged {
+        status: String,
+    },
+}
+
+impl std::fmt::Display for Event {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: ", self.time)?;
+        match &self.kind {
+            EventKind::Created => write!(f, "Created"),
+            EventKind::Edited { description } => write!(f, "Edited to \"{description}\""),
+            EventKind::Completed => write!(f, "Completed"),
+            EventKind::Reopened => write!(f, "Reopened"),
+            EventKind::Snoozed { until } => {
+                write!(f, "Snoozed until {until} (for {})", until - &self.time)
+            }
+            EventKind::Unsnoozed => write!(f, "Unsnoozed"),
+            EventKind::Moved { from, to } => {
+                write!(f, "Moved from position {} to {}", from + 1, to + 1)
+            }
+            EventKind::StatusChanged { status } => write!(f, "Moved to {status}"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum Priority {
+    Low,
+    Medium,
+    High,
+}
+
+impl std::fmt::Display for Priority {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Priority::Low => "low",
+                Priority::Medium => "medium",
+                Priority::High => "high",
+            }
+        )
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -19,39 +144,81 @@ pub enum Message {
     TextInput(String),
     DeleteTask,
     AddSnoozeTime,
-    SetSnoozeDuration(SnoozeDuration),
-    SetSnoozeQuantity(String),
+    SetSnoozeInput(String),
+    ConfirmSnooze,
     Unsnooze,
-}
-
-#[derive(Debug, Clone)]
-pub enum SnoozeDuration {
-    Hour,
-    Day,
-    Week,
-    Month,
+    ToggleHistory,
+    StartTimer,
+    PauseTimer,
+    StopTimer,
+    ToggleTimeEntries,
+    SetTimeEntryStart(String),
+    SetTimeEntryEnd(String),
+    AddTimeEntry,
+    /// Removes a time entry and puts it in the inputs to be changed and added again.
+    EditTimeEntry(usize),
+    DeleteTimeEntry(usize),
+    SetEstimateInput(String),
+    ConfirmEstimate,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Default)]
 pub(crate) struct SnoozeInformation {
     pub visible: bool,
-    pub quantity: u32,
+    #[serde(default)]
+    pub input: String,
     pub snooze_until: Option<Time>,
+    #[serde(default)]
+    pub count: u32,
+}
+
+/// A new random identifier for a task, unique even among tasks created at the same time.
+pub(crate) fn new_uid() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let mut bytes = [0; 16];
+    if SystemRandom::new().fill(&mut bytes).is_err() {
+        // Without randomness, the time and a counter keep identifiers unique in this process.
+        let nanoseconds = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_nanos() as u64);
+        bytes[..8].copy_from_slice(&nanoseconds.to_be_bytes());
+        bytes[8..].copy_from_slice(&COUNTER.fetch_add(1, Ordering::Relaxed).to_be_bytes());
+    }
+    let hex = bytes
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect::<String>();
+    format!("{hex}@organizer")
 }
 
 impl Task {
-    pub fn new(id: usize) -> Task {
+    pub fn new(id: usize, clock: &dyn Clock) -> Task {
         Task {
             id,
             task_completed: false,
             description: "".to_string(),
-            creation_time: Time::now(),
+            creation_time: clock.now(),
             completion_time: None,
             snooze_information: SnoozeInformation {
-                quantity: 1,
                 visible: false,
+                input: String::new(),
                 snooze_until: None,
+                count: 0,
             },
+            tags: vec![],
+            priority: None,
+            due_date: None,
+            uid: Some(new_uid()),
+            history: vec![],
+            history_visible: false,
+            time_entries: vec![],
+            estimate: None,
+            pomodoros: vec![],
+            status: None,
+            pinned_to: None,
+            created_by_periodic_task: false,
+            timer_paused: false,
+            time_entry_input: TimeEntryInput::default(),
         }
     }
 
@@ -59,26 +226,31 @@ impl Task {
         self.task_completed
     }
 
-    pub fn visible_as_pending(&self) -> bool {
+    pub fn visible_as_pending(&self, clock: &dyn Clock) -> bool {
         if !self.task_completed {
             true
         } else if let Some(completion_time) = self.completion_time.as_ref() {
-            &Time::now() - completion_time < Duration::from_minutes(FADE_OUT_TIME)
+            &clock.now() - completion_time < Duration::from_minutes(FADE_OUT_TIME)
                 && self.task_completed
         } else {
             true
         }
     }
 
-    pub fn hidden_because_of_snooze(&self) -> bool {
+    pub fn hidden_because_of_snooze(&self, clock: &dyn Clock) -> bool {
         self.snooze_information
             .snooze_until
             .as_ref()
-            .is_some_and(|snooze_until| snooze_until > &Time::now())
+            .is_some_and(|snooze_until| snooze_until > &clock.now())
+    }
+
+    pub fn snooze(&mut self, snooze_until: Time) {
+        self.snooze_information.snooze_until = Some(snooze_until);
+        self.snooze_information.count += 1;
     }
 
-    pub fn set_completed(&mut self, completed: bool) {
-        self.completion_time = Some(Time::now());
+    pub fn set_completed(&mut self, completed: bool, clock: &dyn Clock) {
+        self.completion_time = Some(clock.now());
         self.task_completed = completed;
     }
 
@@ -89,15 +261,105 @@ impl Task {
     pub fn description(&self) -> &str {
         &self.description
     }
+
+    /// Appends an event that happens now to the history. Consecutive edits less than a minute
+    /// apart, as when typing a description, are recorded as one.
+    pub fn record(&mut self, kind: EventKind, clock: &dyn Clock) {
+        let now = clock.now();
+        if let (EventKind::Edited { .. }, Some(last)) = (&kind, self.history.last_mut()) {
+            if matches!(last.kind, EventKind::Edited { .. })
+                && &now - &last.time < Duration::from_minutes(1)
+            {
+                *last = Event { time: now, kind };
+                return;
+            }
+        }
+        self.history.push(Event { time: now, kind });
+    }
+
+    /// The recorded history, starting with the creation of the task.
+    pub fn history(&self) -> Vec<Event> {
+        let created = Event {
+            time: self.creation_time.clone(),
+            kind: EventKind::Created,
+        };
+        std::iter::once(created)
+            .chain(self.history.iter().cloned())
+            .collect()
+    }
+
+    pub fn timer_running(&self) -> bool {
+        self.time_entries
+            .last()
+            .is_some_and(|entry| entry.end.is_none())
+    }
+
+    pub fn start_timer(&mut self, now: &Time) {
+        if !self.timer_running() {
+            self.time_entries.push(TimeEntry {
+                start: now.clone(),
+                end: None,
+            });
+        }
+        self.timer_paused = false;
+    }
+
+    /// Stops the timer until it is resumed with [`Task::start_timer`].
+    pub fn pause_timer(&mut self, now: &Time) {
+        if self.timer_running() {
+            self.stop_timer(now);
+            self.timer_paused = true;
+        }
+    }
+
+    pub fn stop_timer(&mut self, now: &Time) {
+        if let Some(entry) = self
+            .time_entries
+            .last_mut()
+            .filter(|entry| entry.end.is_none())
+        {
+            entry.end = Some(now.clone());
+        }
+        self.timer_paused = false;
+    }
+
+    /// Total time tracked on the task, counting a running timer until `now`.
+    pub fn tracked(&self, now: &Time) -> Duration {
+        self.time_entries
+            .iter()
+            .map(|entry| entry.duration(now))
+            .sum()
+    }
+
+    /// Priority, tags, due date and estimate, as shown next to the description.
+    pub fn details(&self) -> String {
+        let priority = self.priority.iter().map(|priority| format!("!{priority}"));
+        let tags = self.tags.iter().map(|tag| format!("#{tag}"));
+        let due_date = self
+            .due_date
+            .iter()
+            .map(|due_date| format!("due {due_date}"));
+        let estimate = self
+            .estimate
+            .iter()
+            .map(|estimate| format!("estimated {estimate}"));
+        priority
+            .chain(tags)
+            .chain(due_date)
+            .chain(estimate)
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::clock::{FakeClock, SystemClock};
 
     #[test]
     fn correct_task_description() {
-        let mut task = Task::new(1);
+        let mut task = Task::new(1, &SystemClock);
 
         task.edit("This is a test task");
         assert_eq!("This is a test task", task.description());
@@ -105,13 +367,13 @@ mod tests {
 
     #[test]
     fn empty_task_description() {
-        let task = Task::new(1);
+        let task = Task::new(1, &SystemClock);
         assert_eq!("", task.description());
     }
 
     #[test]
     fn edit_description() {
-        let mut task = Task::new(1);
+        let mut task = Task::new(1, &SystemClock);
         task.edit("This is a test task");
 
         task.edit("Edited task description");
@@ -120,7 +382,7 @@ mod tests {
 
     #[test]
     fn edit_to_empty() {
-        let mut task = Task::new(1);
+        let mut task = Task::new(1, &SystemClock);
         task.edit("This is a test task");
 
         task.edit("");
@@ -129,55 +391,185 @@ mod tests {
 
     #[test]
     fn edit_from_empty() {
-        let mut task = Task::new(1);
+        let mut task = Task::new(1, &SystemClock);
 
         task.edit("A description");
         assert_eq!("A description", task.description());
     }
 
+    #[test]
+    fn history() {
+        let mut clock = FakeClock::new(Time::new(17, 5, 2024, 9, 0, 0).unwrap());
+        let mut task = Task::new(1, &clock);
+        let edited = |description: &str| EventKind::Edited {
+            description: description.to_string(),
+        };
+        task.record(edited("C"), &clock);
+        clock.advance(&Duration::from_seconds(30));
+        task.record(edited("Ca"), &clock);
+        task.record(EventKind::Completed, &clock);
+        task.record(edited("Cal"), &clock);
+        // Edits more than a minute apart are recorded separately.
+        clock.advance(&Duration::from_minutes(2));
+        task.record(edited("Call"), &clock);
+        assert_eq!(
+            task.history()[1].time,
+            Time::new(17, 5, 2024, 9, 0, 30).unwrap()
+        );
+
+        let kinds = task
+            .history()
+            .into_iter()
+            .map(|event| event.kind)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            vec![
+                EventKind::Created,
+                EventKind::Edited {
+                    description: "Ca".to_string()
+                },
+                EventKind::Completed,
+                EventKind::Edited {
+                    description: "Cal".to_string()
+                },
+                EventKind::Edited {
+                    description: "Call".to_string()
+                },
+            ]
+        );
+
+        let time = Time::new(17, 5, 2024, 9, 0, 0).unwrap();
+        let until = &time + &Duration::from_hours(2);
+        let event = Event {
+            time: time.clone(),
+            kind: EventKind::Snoozed {
+                until: until.clone(),
+            },
+        };
+        assert_eq!(
+            event.to_string(),
+            format!("{time}: Snoozed until {until} (for 2h 0m)")
+        );
+        let event = Event {
+            time: time.clone(),
+            kind: EventKind::Moved { from: 0, to: 1 },
+        };
+        assert_eq!(
+            event.to_string(),
+            format!("{time}: Moved from position 1 to 2")
+        );
+    }
+
+    #[test]
+    fn timers() {
+        let mut task = Task::new(1, &SystemClock);
+        let time = |hour| Time::new(13, 5, 2024, hour, 0, 0).unwrap();
+        task.start_timer(&time(9));
+        task.start_timer(&time(10));
+        assert!(task.timer_running());
+        assert_eq!(task.tracked(&time(11)), Duration::from_hours(2));
+
+        task.pause_timer(&time(11));
+        assert!(!task.timer_running());
+        assert!(task.timer_paused);
+        task.start_timer(&time(12));
+        assert!(!task.timer_paused);
+        task.stop_timer(&time(13));
+        assert!(!task.timer_running());
+        assert!(!task.timer_paused);
+        assert_eq!(task.time_entries.len(), 2);
+        assert_eq!(task.tracked(&time(20)), Duration::from_hours(3));
+
+        // Stopping or pausing a stopped timer does nothing.
+        task.pause_timer(&time(14));
+        task.stop_timer(&time(15));
+        assert!(!task.timer_paused);
+        assert_eq!(task.tracked(&time(20)), Duration::from_hours(3));
+    }
+
+    #[test]
+    fn details() {
+        let mut task = Task::new(1, &SystemClock);
+        assert_eq!(task.details(), "");
+
+        task.tags = vec!["work".to_string(), "backend".to_string()];
+        task.priority = Some(Priority::High);
+        assert_eq!(task.details(), "!high #work #backend");
+
+        let due_date = Time::new(17, 5, 2024, 9, 0, 0).unwrap();
+        task.due_date = Some(due_date.clone());
+        assert_eq!(
+            task.details(),
+            format!("!high #work #backend due {due_date}")
+        );
+
+        task.tags.clear();
+        task.due_date = None;
+        task.estimate = Some(Duration::from_minutes(90));
+        assert_eq!(task.details(), "!high estimated 1h 30m");
+    }
+
     #[test]
     fn completed_or_not_completed() {
-        let mut task = Task::new(1);
+        let mut task = Task::new(1, &SystemClock);
         assert!(!task.completed());
 
-        task.set_completed(false);
+        task.set_completed(false, &SystemClock);
         assert!(!task.completed());
 
-        task.set_completed(false);
+        task.set_completed(false, &SystemClock);
         assert!(!task.completed());
 
-        task.set_completed(true);
+        task.set_completed(true, &SystemClock);
         assert!(task.completed());
 
-        task.set_completed(true);
+        task.set_completed(true, &SystemClock);
         assert!(task.completed());
 
-        task.set_completed(false);
+        task.set_completed(false, &SystemClock);
         assert!(!task.completed());
     }
 
     #[test]
     fn set_completion_time() {
-        let mut task = Task::new(1);
+        let mut task = Task::new(1, &SystemClock);
         assert!(task.completion_time.is_none());
 
-        task.set_completed(true);
+        task.set_completed(true, &SystemClock);
         assert!(task.completion_time.is_some());
     }
 
+    #[test]
+    fn snooze() {
+        let mut clock = FakeClock::new(Time::new(13, 5, 2024, 9, 0, 0).unwrap());
+        let mut task = Task::new(1, &clock);
+        assert!(!task.hidden_because_of_snooze(&clock));
+
+        task.snooze(Time::new(13, 5, 2024, 10, 0, 0).unwrap());
+        assert!(task.hidden_because_of_snooze(&clock));
+        clock.advance(&Duration::from_minutes(59));
+        assert!(task.hidden_because_of_snooze(&clock));
+        clock.advance(&Duration::from_minutes(1));
+        assert!(!task.hidden_because_of_snooze(&clock));
+        assert_eq!(task.snooze_information.count, 1);
+    }
+
     #[test]
     fn visible_as_pending_test() {
-        let mut task = Task::new(1);
+        let mut clock = FakeClock::new(Time::new(13, 5, 2024, 9, 0, 0).unwrap());
+        let mut task = Task::new(1, &clock);
 
-        task.set_completed(false);
-        assert!(task.visible_as_pending());
+        task.set_completed(false, &clock);
+        assert!(task.visible_as_pending(&clock));
 
-        task.set_completed(true);
-        task.completion_time = Option::Some(Time::now());
-        assert!(task.visible_as_pending());
+        task.set_completed(true, &clock);
+        assert_eq!(task.completion_time, Some(clock.now()));
+        assert!(task.visible_as_pending(&clock));
 
-        let far_away_time = Time::from("Sat, 21 Jan 2023 12:25:20 +0100");
-        task.completion_time = Option::Some(far_away_time);
-        assert!(!task.visible_as_pending());
+        clock.advance(&Duration::from_minutes(FADE_OUT_TIME - 1));
+        assert!(task.visible_as_pending(&clock));
+        clock.advance(&Duration::from_minutes(1));
+        assert!(!task.visible_as_pending(&clock));
     }
 }
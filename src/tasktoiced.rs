@@ -1,8 +1,10 @@
-use crate::task::{self, Task};
+use crate::clock::Clock;
+use crate::task::{self, EventKind, Task};
 use crate::time::Duration;
 use crate::time::Time;
-use crate::toiced::add_button;
+use crate::time_tracking::TimeEntry;
 use crate::toiced::ToIced;
+use crate::toiced::{add_button, date_preview};
 use iced::widget::text_input::StyleSheet;
 use iced::widget::{checkbox, column, row, text_input};
 use iced::Element;
@@ -72,9 +74,72 @@ impl StyleSheet for TextInputStyle {
     }
 }
 
+impl Task {
+    fn view_time_entries(&self, clock: &dyn Clock) -> Element<'_, task::Message> {
+        let now = clock.now();
+        let mut entries = column(vec![]).spacing(5).padding([0, 0, 10, 60]);
+        let estimate_row = row![
+            iced::widget::text(format!("Tracked: {}    Estimate:", self.tracked(&now))),
+            text_input(
+                "e.g. 2h, 1h30m",
+                &self.time_entry_input.estimate,
+                task::Message::SetEstimateInput
+            )
+            .on_submit(task::Message::ConfirmEstimate)
+            .width(120)
+            .padding(10),
+            add_button("Set", task::Message::ConfirmEstimate),
+        ]
+        .spacing(10)
+        .align_items(iced::Alignment::Center);
+        entries = entries.push(estimate_row);
+        for (index, entry) in self.time_entries.iter().enumerate() {
+            let end = entry
+                .end
+                .as_ref()
+                .map_or("running".to_string(), |end| end.to_string());
+            let entry_row = row![
+                iced::widget::text(format!(
+                    "{} - {end} ({})",
+                    entry.start,
+                    entry.duration(&now)
+                )),
+                add_button("Edit", task::Message::EditTimeEntry(index))
+                    .style(iced::theme::Button::Secondary),
+                add_button("Delete", task::Message::DeleteTimeEntry(index))
+                    .style(iced::theme::Button::Destructive),
+            ]
+            .spacing(10)
+            .align_items(iced::Alignment::Center);
+            entries = entries.push(entry_row);
+        }
+        let input_row = row![
+            text_input(
+                "Start (e.g. today 9am)",
+                &self.time_entry_input.start,
+                task::Message::SetTimeEntryStart
+            )
+            .padding(10),
+            date_preview(&self.time_entry_input.start, clock),
+            text_input(
+                "End (e.g. today 11:30)",
+                &self.time_entry_input.end,
+                task::Message::SetTimeEntryEnd
+            )
+            .on_submit(task::Message::AddTimeEntry)
+            .padding(10),
+            date_preview(&self.time_entry_input.end, clock),
+            add_button("Add", task::Message::AddTimeEntry),
+        ]
+        .spacing(10)
+        .align_items(iced::Alignment::Center);
+        entries.push(input_row).into()
+    }
+}
+
 impl ToIced for Task {
     type Message = task::Message;
-    fn view(&self) -> Element<task::Message> {
+    fn view(&self, clock: &dyn Clock) -> Element<'_, task::Message> {
         let a_checkbox = checkbox(
             "".to_string(),
             self.completed(),
@@ -85,7 +150,7 @@ impl ToIced for Task {
             if !self.completed() {
                 1.
             } else if let Some(ref completion_time) = self.completion_time {
-                let elapsed_time = &Time::now() - completion_time;
+                let elapsed_time = &clock.now() - completion_time;
                 let fade_out_time = Duration::from_minutes(FADE_OUT_TIME);
                 if elapsed_time < fade_out_time {
                     1. - elapsed_time / fade_out_time
@@ -121,9 +186,30 @@ impl ToIced for Task {
                 .padding(10)
                 .align_items(iced::Alignment::Center)
                 .push(a_checkbox)
-                .push(a_text_input)
-                .push(delete_button);
-            if self.hidden_because_of_snooze() {
+                .push(a_text_input);
+            let details = self.details();
+            if !details.is_empty() {
+                a_row = a_row.push(iced::widget::text(details));
+            }
+            a_row = a_row.push(delete_button);
+            let history_button = add_button("History", task::Message::ToggleHistory)
+                .style(iced::theme::Button::Secondary);
+            a_row = a_row.push(history_button);
+            if self.timer_running() {
+                a_row = a_row
+                    .push(add_button("Pause", task::Message::PauseTimer))
+                    .push(add_button("Stop", task::Message::StopTimer));
+            } else if self.timer_paused {
+                a_row = a_row
+                    .push(add_button("Resume", task::Message::StartTimer))
+                    .push(add_button("Stop", task::Message::StopTimer));
+            } else {
+                a_row = a_row.push(add_button("Start", task::Message::StartTimer));
+            }
+            let time_button = add_button("Time", task::Message::ToggleTimeEntries)
+                .style(iced::theme::Button::Secondary);
+            a_row = a_row.push(time_button);
+            if self.hidden_because_of_snooze(clock) {
                 let unsnooze_button = add_button("Unsnooze", task::Message::Unsnooze)
                     .style(iced::theme::Button::Secondary);
                 a_row = a_row.push(unsnooze_button);
@@ -138,38 +224,24 @@ impl ToIced for Task {
         let mut a_column = column(vec![]).push(a_row);
 
         let snooze_duration_row = if self.snooze_information.visible {
-            let quantity = self.snooze_information.quantity.to_string();
-            let quantity_input =
-                iced::widget::text_input(&quantity, &quantity, task::Message::SetSnoozeQuantity)
-                    .padding(10);
-            let hour = add_button(
-                "Hour",
-                task::Message::SetSnoozeDuration(task::SnoozeDuration::Hour),
-            )
-            .style(iced::theme::Button::Secondary);
-            let day = add_button(
-                "Day",
-                task::Message::SetSnoozeDuration(task::SnoozeDuration::Day),
-            )
-            .style(iced::theme::Button::Secondary);
-            let week = add_button(
-                "Week",
-                task::Message::SetSnoozeDuration(task::SnoozeDuration::Week),
+            let snooze_input = iced::widget::text_input(
+                "Snooze until... (e.g. tomorrow 9am, in 2h)",
+                &self.snooze_information.input,
+                task::Message::SetSnoozeInput,
             )
-            .style(iced::theme::Button::Secondary);
-            let month = add_button(
-                "Month",
-                task::Message::SetSnoozeDuration(task::SnoozeDuration::Month),
-            )
-            .style(iced::theme::Button::Secondary);
+            .on_submit(task::Message::ConfirmSnooze)
+            .padding(10);
+            let preview = date_preview(&self.snooze_information.input, clock);
+            let confirm = add_button("Snooze", task::Message::ConfirmSnooze)
+                .style(iced::theme::Button::Secondary);
 
             Some(
                 row(vec![])
-                    .push(quantity_input)
-                    .push(hour)
-                    .push(day)
-                    .push(week)
-                    .push(month),
+                    .spacing(10)
+                    .align_items(iced::Alignment::Center)
+                    .push(snooze_input)
+                    .push(preview)
+                    .push(confirm),
             )
         } else {
             None
@@ -179,53 +251,125 @@ impl ToIced for Task {
             a_column = a_column.push(snooze_duration_row);
         }
 
+        if self.time_entry_input.visible {
+            a_column = a_column.push(self.view_time_entries(clock));
+        }
+
+        if self.history_visible {
+            let events = self
+                .history()
+                .iter()
+                .map(|event| iced::widget::text(event.to_string()).size(16).into())
+                .collect();
+            a_column = a_column.push(column(events).spacing(5).padding([0, 0, 10, 60]));
+        }
+
         a_column.into()
     }
 
-    fn update(&mut self, message: Self::Message) {
+    fn update(&mut self, message: Self::Message, clock: &dyn Clock) {
         match message {
             task::Message::ToggleTaskCompletion(completed) => {
-                self.set_completed(completed);
+                if completed != self.completed() {
+                    self.record(
+                        if completed {
+                            EventKind::Completed
+                        } else {
+                            EventKind::Reopened
+                        },
+                        clock,
+                    );
+                }
+                self.set_completed(completed, clock);
                 if !completed {
                     self.completion_time = None
                 }
             }
-            task::Message::TextInput(description) => self.edit(&description),
+            task::Message::TextInput(description) => {
+                self.edit(&description);
+                self.record(EventKind::Edited { description }, clock);
+            }
             task::Message::DeleteTask => {
                 unreachable!();
             }
             task::Message::AddSnoozeTime => {
                 self.snooze_information.visible = true;
-                self.snooze_information.snooze_until = Some(Time::now());
             }
-            task::Message::SetSnoozeQuantity(value) => {
-                let value = {
-                    let mut result: u32 = 1;
-                    if value.is_empty() {
-                        result = 1;
-                    } else if let Ok(value) = value.parse::<u32>() {
-                        result = value
-                    }
-                    result
-                };
-                self.snooze_information.quantity = value;
+            task::Message::SetSnoozeInput(value) => {
+                self.snooze_information.input = value;
             }
             task::Message::Unsnooze => {
+                if self.snooze_information.snooze_until.is_some() {
+                    self.record(EventKind::Unsnoozed, clock);
+                }
                 self.snooze_information.visible = false;
                 self.snooze_information.snooze_until = None;
             }
-            task::Message::SetSnoozeDuration(duration) => {
-                self.snooze_information.visible = false;
-                let quantity = self.snooze_information.quantity;
-                let duration: Duration = match duration {
-                    task::SnoozeDuration::Hour => Duration::from_hours(quantity as i64 * 1),
-                    task::SnoozeDuration::Day => Duration::from_hours(quantity as i64 * 24),
-                    task::SnoozeDuration::Week => Duration::from_hours(quantity as i64 * 24 * 7),
-                    task::SnoozeDuration::Month => Duration::from_hours(quantity as i64 * 24 * 30),
-                };
-                self.snooze_information.snooze_until =
-                    Some(self.snooze_information.snooze_until.as_ref().unwrap() + &duration);
+            task::Message::ConfirmSnooze => {
+                if let Ok(snooze_until) =
+                    Time::from_natural_language(&self.snooze_information.input, &clock.now())
+                {
+                    self.snooze_information.visible = false;
+                    self.snooze_information.input.clear();
+                    self.record(
+                        EventKind::Snoozed {
+                            until: snooze_until.clone(),
+                        },
+                        clock,
+                    );
+                    self.snooze(snooze_until);
+                }
+            }
+            task::Message::ToggleHistory => self.history_visible = !self.history_visible,
+            task::Message::StartTimer => self.start_timer(&clock.now()),
+            task::Message::PauseTimer => self.pause_timer(&clock.now()),
+            task::Message::StopTimer => self.stop_timer(&clock.now()),
+            task::Message::ToggleTimeEntries => {
+                self.time_entry_input.visible = !self.time_entry_input.visible;
+                self.time_entry_input.estimate = self
+                    .estimate
+                    .as_ref()
+                    .map(Duration::as_input_text)
+                    .unwrap_or_default();
+            }
+            task::Message::SetEstimateInput(value) => self.time_entry_input.estimate = value,
+            task::Message::ConfirmEstimate => {
+                let input = self.time_entry_input.estimate.trim();
+                if input.is_empty() {
+                    self.estimate = None;
+                } else if let Ok(estimate) = Duration::parse(input) {
+                    self.estimate = Some(estimate);
+                }
+            }
+            task::Message::SetTimeEntryStart(value) => self.time_entry_input.start = value,
+            task::Message::SetTimeEntryEnd(value) => self.time_entry_input.end = value,
+            task::Message::AddTimeEntry => {
+                let now = clock.now();
+                let start = Time::from_natural_language(&self.time_entry_input.start, &now);
+                let end = Time::from_natural_language(&self.time_entry_input.end, &now);
+                if let (Ok(start), Ok(end)) = (start, end) {
+                    if start < end {
+                        let entry = TimeEntry {
+                            start,
+                            end: Some(end),
+                        };
+                        // Kept before a running entry, which must stay last.
+                        let position = self.time_entries.len() - usize::from(self.timer_running());
+                        self.time_entries.insert(position, entry);
+                        self.time_entry_input.start.clear();
+                        self.time_entry_input.end.clear();
+                    }
+                }
+            }
+            task::Message::EditTimeEntry(index) => {
+                let entry = self.time_entries.remove(index);
+                self.time_entry_input.start = entry.start.as_input_text();
+                self.time_entry_input.end = entry
+                    .end
+                    .as_ref()
+                    .map_or("now".to_string(), Time::as_input_text);
             }
+            task::Message::DeleteTimeEntry(index) => _ = self.time_entries.remove(index),
         }
     }
 }
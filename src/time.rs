@@ -1,15 +1,125 @@
 use core::fmt;
 
-use chrono::{DateTime, Datelike, Local, Timelike};
-use chrono::{LocalResult, TimeZone};
+use chrono::{DateTime, Datelike, Local, NaiveDateTime, SecondsFormat, Timelike, Utc};
+use chrono::{FixedOffset, LocalResult, NaiveDate, Offset, TimeZone};
+use chrono_tz::Tz;
 use serde::{Deserialize, Serialize};
+use std::cell::Cell;
 
-#[derive(Debug, PartialEq, PartialOrd, Clone)]
+mod natural_language;
+
+/// Timezone that calendar fields are read and written in: the one named in the settings of the
+/// list, or the local timezone of the machine.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum DisplayTimezone {
+    Local,
+    Named(Tz),
+}
+
+/// Offset of the display timezone at some instant.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct DisplayOffset {
+    timezone: DisplayTimezone,
+    offset: FixedOffset,
+}
+
+thread_local! {
+    /// Display timezone of the thread showing the list, so that tests running in parallel each
+    /// have their own.
+    static DISPLAY_TIMEZONE: Cell<DisplayTimezone> = const { Cell::new(DisplayTimezone::Local) };
+}
+
+impl DisplayTimezone {
+    /// The timezone named `name` in the IANA database, such as "Europe/Madrid", or the local
+    /// timezone for `None`.
+    pub(crate) fn from_setting(name: Option<&str>) -> Result<Self, TimeError> {
+        match name {
+            Some(name) => Ok(DisplayTimezone::Named(
+                name.parse().map_err(|_| TimeError {})?,
+            )),
+            None => Ok(DisplayTimezone::Local),
+        }
+    }
+
+    pub(crate) fn current() -> Self {
+        DISPLAY_TIMEZONE.with(Cell::get)
+    }
+
+    /// Shows and reads times in this timezone from now on.
+    pub(crate) fn set(self) {
+        DISPLAY_TIMEZONE.with(|timezone| timezone.set(self));
+    }
+
+    fn offset(self, offset: FixedOffset) -> DisplayOffset {
+        DisplayOffset {
+            timezone: self,
+            offset,
+        }
+    }
+}
+
+impl Offset for DisplayOffset {
+    fn fix(&self) -> FixedOffset {
+        self.offset
+    }
+}
+
+impl fmt::Display for DisplayOffset {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.offset.fmt(f)
+    }
+}
+
+impl TimeZone for DisplayTimezone {
+    type Offset = DisplayOffset;
+
+    fn from_offset(offset: &DisplayOffset) -> Self {
+        offset.timezone
+    }
+
+    fn offset_from_local_date(&self, local: &NaiveDate) -> LocalResult<DisplayOffset> {
+        match self {
+            DisplayTimezone::Local => Local.offset_from_local_date(local),
+            DisplayTimezone::Named(timezone) => timezone
+                .offset_from_local_date(local)
+                .map(|offset| offset.fix()),
+        }
+        .map(|offset| self.offset(offset))
+    }
+
+    fn offset_from_local_datetime(&self, local: &NaiveDateTime) -> LocalResult<DisplayOffset> {
+        match self {
+            DisplayTimezone::Local => Local.offset_from_local_datetime(local),
+            DisplayTimezone::Named(timezone) => timezone
+                .offset_from_local_datetime(local)
+                .map(|offset| offset.fix()),
+        }
+        .map(|offset| self.offset(offset))
+    }
+
+    fn offset_from_utc_date(&self, utc: &NaiveDate) -> DisplayOffset {
+        self.offset(match self {
+            DisplayTimezone::Local => Local.offset_from_utc_date(utc),
+            DisplayTimezone::Named(timezone) => timezone.offset_from_utc_date(utc).fix(),
+        })
+    }
+
+    fn offset_from_utc_datetime(&self, utc: &NaiveDateTime) -> DisplayOffset {
+        self.offset(match self {
+            DisplayTimezone::Local => Local.offset_from_utc_datetime(utc),
+            DisplayTimezone::Named(timezone) => timezone.offset_from_utc_datetime(utc).fix(),
+        })
+    }
+}
+
+/// An instant in time. It is stored in UTC, and its calendar fields (day, hour...) are read in
+/// the [`DisplayTimezone`].
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone)]
 pub(crate) struct Time {
-    pub time: DateTime<Local>,
+    time: DateTime<Utc>,
 }
 
-#[derive(Debug, PartialEq, PartialOrd)]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone)]
 pub(crate) struct Duration {
     duration: chrono::Duration,
 }
@@ -36,6 +146,16 @@ impl Duration {
             duration: chrono::Duration::seconds(seconds),
         }
     }
+
+    pub(crate) fn num_seconds(&self) -> i64 {
+        self.duration.num_seconds()
+    }
+}
+
+impl std::iter::Sum for Duration {
+    fn sum<I: Iterator<Item = Duration>>(durations: I) -> Self {
+        Duration::new(durations.map(|duration| duration.duration).sum())
+    }
 }
 
 impl fmt::Display for Time {
@@ -55,7 +175,18 @@ impl fmt::Display for Time {
 
 impl fmt::Display for Duration {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self.duration.num_hours())
+        let sign = if self.duration < chrono::Duration::zero() {
+            "-"
+        } else {
+            ""
+        };
+        let minutes = self.duration.num_minutes().abs();
+        let (days, hours, minutes) = (minutes / (60 * 24), minutes / 60 % 24, minutes % 60);
+        match (days, hours) {
+            (0, 0) => write!(f, "{sign}{minutes}m"),
+            (0, _) => write!(f, "{sign}{hours}h {minutes}m"),
+            _ => write!(f, "{sign}{days}d {hours}h {minutes}m"),
+        }
     }
 }
 
@@ -63,6 +194,7 @@ impl fmt::Display for Duration {
 pub struct TimeError {}
 
 impl Time {
+    /// Creates a time from calendar fields in the display timezone. See [`Time::from_calendar`].
     pub(crate) fn new(
         day: u32,
         month: u32,
@@ -71,80 +203,221 @@ impl Time {
         minute: u32,
         second: u32,
     ) -> Result<Time, TimeError> {
-        let year: i32 = year.try_into().unwrap();
-        let time = chrono::Local.with_ymd_and_hms(year, month, day, hour, minute, second);
-        if let LocalResult::Single(time) = time {
-            Ok(Time { time })
-        } else {
-            Err(TimeError {})
+        Self::from_calendar(
+            &DisplayTimezone::current(),
+            day,
+            month,
+            year,
+            hour,
+            minute,
+            second,
+        )
+    }
+
+    /// Creates a time from calendar fields in the given timezone. Fails only if the date does
+    /// not exist in the calendar. Wall-clock times skipped by a DST transition are moved forward
+    /// by the length of the gap, and repeated ones resolve to their earliest instant.
+    pub(crate) fn from_calendar<Tz: TimeZone>(
+        timezone: &Tz,
+        day: u32,
+        month: u32,
+        year: u32,
+        hour: u32,
+        minute: u32,
+        second: u32,
+    ) -> Result<Time, TimeError> {
+        let year: i32 = year.try_into().map_err(|_| TimeError {})?;
+        let naive = chrono::NaiveDate::from_ymd_opt(year, month, day)
+            .and_then(|date| date.and_hms_opt(hour, minute, second))
+            .ok_or(TimeError {})?;
+        Ok(Self::from_naive(timezone, &naive))
+    }
+
+    fn from_naive<Tz: TimeZone>(timezone: &Tz, naive: &NaiveDateTime) -> Time {
+        match timezone.from_local_datetime(naive) {
+            LocalResult::Single(time) | LocalResult::Ambiguous(time, _) => Time {
+                time: time.with_timezone(&Utc),
+            },
+            LocalResult::None => {
+                // The wall-clock time falls in a DST gap: the offset before the gap tells which
+                // instant the user meant, and that instant is displayed after the gap.
+                let offset_before = timezone
+                    .offset_from_utc_datetime(&(*naive - chrono::Duration::days(1)))
+                    .fix();
+                let time = Utc.from_utc_datetime(&(*naive - offset_before));
+                Time { time }
+            }
         }
     }
 
     pub(crate) fn now() -> Time {
-        Time {
-            time: { chrono::Local::now() },
-        }
+        Time { time: Utc::now() }
+    }
+
+    /// This time plus `duration`, or an error if that is out of the supported range, for
+    /// durations that come from user input.
+    pub(crate) fn checked_add(&self, duration: &Duration) -> Result<Time, TimeError> {
+        let time = self.time.checked_add_signed(duration.duration);
+        time.map(|time| Time { time }).ok_or(TimeError {})
+    }
+
+    /// This time minus `duration`, or an error if that is out of the supported range.
+    pub(crate) fn checked_sub(&self, duration: &Duration) -> Result<Time, TimeError> {
+        let time = self.time.checked_sub_signed(duration.duration);
+        time.map(|time| Time { time }).ok_or(TimeError {})
+    }
+
+    /// Parses an RFC 3339 or RFC 2822 timestamp.
+    pub(crate) fn parse(time: &str) -> Result<Time, TimeError> {
+        DateTime::parse_from_rfc3339(time)
+            .or_else(|_| DateTime::parse_from_rfc2822(time))
+            .map(|time| Time {
+                time: time.with_timezone(&Utc),
+            })
+            .map_err(|_| TimeError {})
+    }
+
+    /// Parses a UTC timestamp in the compact ISO 8601 form used by iCalendar and Taskwarrior,
+    /// such as "20240515T103000Z".
+    pub(crate) fn parse_compact_utc(time: &str) -> Result<Time, TimeError> {
+        let naive =
+            NaiveDateTime::parse_from_str(time, "%Y%m%dT%H%M%SZ").map_err(|_| TimeError {})?;
+        Ok(Time {
+            time: Utc.from_utc_datetime(&naive),
+        })
+    }
+
+    /// Compact ISO 8601 representation in UTC, such as "20240515T103000Z".
+    pub(crate) fn to_compact_utc(&self) -> String {
+        self.time.format("%Y%m%dT%H%M%SZ").to_string()
+    }
+
+    /// RFC 3339 representation in UTC, used for storage.
+    pub(crate) fn to_rfc3339(&self) -> String {
+        self.time.to_rfc3339_opts(SecondsFormat::AutoSi, true)
+    }
+
+    pub(crate) fn in_timezone<Tz: TimeZone>(&self, timezone: &Tz) -> DateTime<Tz> {
+        self.time.with_timezone(timezone)
+    }
+
+    fn displayed(&self) -> DateTime<DisplayTimezone> {
+        self.in_timezone(&DisplayTimezone::current())
+    }
+
+    /// Midnight of the same day in the display timezone.
+    pub(crate) fn start_of_day(&self) -> Time {
+        let date = self.displayed().date_naive();
+        Self::from_naive(
+            &DisplayTimezone::current(),
+            &date.and_time(chrono::NaiveTime::MIN),
+        )
+    }
+
+    /// Midnight of the Monday of the same week in the display timezone.
+    pub(crate) fn start_of_week(&self) -> Time {
+        let days_since_monday = self.displayed().weekday().num_days_from_monday();
+        self.start_of_day().add_days(-(days_since_monday as i64))
+    }
+
+    /// Midnight of the first day of the same month in the display timezone.
+    pub(crate) fn start_of_month(&self) -> Time {
+        let date = self.displayed().date_naive().with_day(1).unwrap();
+        Self::from_naive(
+            &DisplayTimezone::current(),
+            &date.and_time(chrono::NaiveTime::MIN),
+        )
+    }
+
+    /// Midnight of the first day of the month `months` months later in the display timezone.
+    pub(crate) fn add_months(&self, months: i32) -> Time {
+        let date = self.displayed().date_naive();
+        let month = date.year() * 12 + date.month0() as i32 + months;
+        let date = chrono::NaiveDate::from_ymd_opt(
+            month.div_euclid(12),
+            month.rem_euclid(12) as u32 + 1,
+            1,
+        )
+        .unwrap();
+        Self::from_naive(
+            &DisplayTimezone::current(),
+            &date.and_time(chrono::NaiveTime::MIN),
+        )
+    }
+
+    /// Same wall-clock time in the display timezone, `days` days later.
+    pub(crate) fn add_days(&self, days: i64) -> Time {
+        let naive = self.displayed().naive_local() + chrono::Duration::days(days);
+        Self::from_naive(&DisplayTimezone::current(), &naive)
+    }
+
+    /// Day and month, such as "15/5".
+    pub(crate) fn short_date(&self) -> String {
+        format!("{}/{}", self.day(), self.month())
+    }
+
+    /// Month and year, such as "May 2024".
+    pub(crate) fn month_and_year(&self) -> String {
+        self.displayed().format("%B %Y").to_string()
+    }
+
+    /// Date in the display timezone, such as "2024-05-15".
+    pub(crate) fn iso_date(&self) -> String {
+        self.displayed().format("%Y-%m-%d").to_string()
+    }
+
+    /// Midnight of a date such as "2024-05-15" in the display timezone.
+    pub(crate) fn from_iso_date(date: &str) -> Result<Time, TimeError> {
+        let date = chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|_| TimeError {})?;
+        Ok(Self::from_naive(
+            &DisplayTimezone::current(),
+            &date.and_time(chrono::NaiveTime::MIN),
+        ))
     }
 
     pub(crate) fn day(&self) -> u32 {
-        self.time.day()
+        self.displayed().day()
     }
 
     pub(crate) fn month(&self) -> u32 {
-        self.time.month()
+        self.displayed().month()
     }
 
     pub(crate) fn year(&self) -> u32 {
-        self.time.year().try_into().unwrap()
+        self.displayed().year().try_into().unwrap_or(0)
     }
 
     pub(crate) fn hour(&self) -> u32 {
-        self.time.hour()
+        self.displayed().hour()
     }
 
     pub(crate) fn minute(&self) -> u32 {
-        self.time.minute()
+        self.displayed().minute()
     }
 
     pub(crate) fn second(&self) -> u32 {
-        self.time.second()
+        self.displayed().second()
     }
 }
 
 impl From<&str> for Time {
     fn from(time: &str) -> Time {
-        Time {
-            //time: DateTime::parse_from_rfc2822(time).unwrap(),
-            time: DateTime::parse_from_rfc2822(time)
-                .unwrap()
-                .with_timezone(&Local),
-        }
+        Time::parse(time).unwrap()
     }
 }
 
-impl<'a, 'b> std::ops::Sub<&'b Time> for &'a Time {
+impl<'b> std::ops::Sub<&'b Time> for &Time {
     type Output = Duration;
     fn sub(self, other: &'b Time) -> Self::Output {
         Duration::new(self.time - other.time)
     }
 }
 
-impl<'a, 'b> std::ops::Sub<&'b Duration> for &'a Time {
+/// Panics if the result is out of range. See [`Time::checked_sub`].
+impl<'b> std::ops::Sub<&'b Duration> for &Time {
     type Output = Time;
     fn sub(self, other: &'b Duration) -> Self::Output {
-        let time = self
-            .time
-            .checked_sub_days(chrono::naive::Days::new(other.duration.num_days() as u64))
-            .unwrap();
-        Time::new(
-            time.day(),
-            time.month(),
-            time.year() as u32,
-            time.hour(),
-            time.minute(),
-            time.second(),
-        )
-        .unwrap()
+        self.checked_sub(other).expect("time out of range")
     }
 }
 
@@ -158,19 +431,11 @@ impl std::ops::Div<Self> for Duration {
     }
 }
 
-impl<'a, 'b> std::ops::Add<&'b Duration> for &'a Time {
+/// Panics if the result is out of range. See [`Time::checked_add`].
+impl<'b> std::ops::Add<&'b Duration> for &Time {
     type Output = Time;
-    fn add(self, rhs: &Duration) -> Self::Output {
-        let new_time = self.time + rhs.duration;
-        Time::new(
-            new_time.day(),
-            new_time.month(),
-            new_time.year() as u32,
-            new_time.hour(),
-            new_time.minute(),
-            new_time.second(),
-        )
-        .unwrap()
+    fn add(self, rhs: &'b Duration) -> Self::Output {
+        self.checked_add(rhs).expect("time out of range")
     }
 }
 
@@ -179,7 +444,7 @@ impl Serialize for Time {
     where
         S: serde::Serializer,
     {
-        serializer.serialize_str(&self.time.to_rfc2822())
+        serializer.serialize_str(&self.to_rfc3339())
     }
 }
 
@@ -189,29 +454,60 @@ impl<'de> Deserialize<'de> for Time {
         D: serde::Deserializer<'de>,
     {
         let description = String::deserialize(deserializer)?;
-        match chrono::DateTime::parse_from_rfc2822(&description) {
-            Ok(_) => Ok(Time::from(description.as_str())),
-            Err(message) => Err(serde::de::Error::custom(message)),
+        Time::parse(&description)
+            .map_err(|_| serde::de::Error::custom(format!("invalid time: {description}")))
+    }
+}
+
+/// Durations are stored as seconds. Older lists hold text such as "1h30m".
+impl Serialize for Duration {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_i64(self.num_seconds())
+    }
+}
+
+impl<'de> Deserialize<'de> for Duration {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Stored {
+            Seconds(i64),
+            Text(String),
+        }
+        match Stored::deserialize(deserializer)? {
+            Stored::Seconds(seconds) => chrono::Duration::try_seconds(seconds)
+                .map(Duration::new)
+                .ok_or_else(|| serde::de::Error::custom(format!("invalid duration: {seconds}"))),
+            Stored::Text(description) => Duration::parse(&description)
+                .map_err(|_| serde::de::Error::custom(format!("invalid duration: {description}"))),
         }
     }
 }
 
+/// The start of an hour of a day of May 2024, for tests.
+#[cfg(test)]
+pub(crate) fn test_time(day: u32, hour: u32) -> Time {
+    Time::new(day, 5, 2024, hour, 0, 0).unwrap()
+}
+
 #[cfg(test)]
 mod tests {
 
     use super::*;
-    use std::fmt;
-    //impl fmt::Display for Time {
-    //    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-    //        write!(f, "{}", self.time.to_rfc2822())
-    //    }
-    //}
+    use chrono::FixedOffset;
 
     #[test]
     fn new_from_string() {
         let time_string = "Sat, 21 Jan 2023 12:25:20 +0100";
         let time = Time::from(time_string);
-        assert_eq!(time.to_string(), time_string);
+        let offset = FixedOffset::east_opt(3600).unwrap();
+        assert_eq!(time.in_timezone(&offset).to_rfc2822(), time_string);
     }
 
     #[test]
@@ -234,6 +530,13 @@ mod tests {
         assert_eq!(duration_1, duration_2);
     }
 
+    #[test]
+    fn same_instant_in_different_offsets() {
+        let time_1 = Time::from("Sat, 21 Jan 2023 12:25:20 +0100");
+        let time_2 = Time::from("2023-01-21T11:25:20Z");
+        assert_eq!(time_1, time_2);
+    }
+
     #[test]
     fn duration_ratio() {
         let duration_1 = Duration::from_minutes(30);
@@ -265,38 +568,183 @@ mod tests {
         let year = 2023;
         let month = 4;
         let day = 29;
-        let date = chrono::Utc
-            .with_ymd_and_hms(year, month, day, 14, 09, 0)
-            .unwrap();
+        let date = Local.with_ymd_and_hms(year, month, day, 14, 9, 0).unwrap();
         let time_1 = Time::from(date.to_rfc2822().as_str());
 
-        let time_2 = Time::new(29, 4, 2023, 14, 09, 0);
+        let time_2 = Time::new(29, 4, 2023, 14, 9, 0);
         assert!(time_1 == time_2.unwrap());
 
-        let time_2 = Time::new(29, 4, 2023, 14, 09, 1);
+        let time_2 = Time::new(29, 4, 2023, 14, 9, 1);
         assert!(time_1 < time_2.unwrap());
 
-        let time_2 = Time::new(29, 4, 2023, 14, 08, 59);
+        let time_2 = Time::new(29, 4, 2023, 14, 8, 59);
         assert!(time_1 > time_2.unwrap());
     }
 
+    #[test]
+    fn new_with_inexistent_date() {
+        assert!(Time::new(30, 2, 2023, 12, 0, 0).is_err());
+        assert!(Time::new(1, 13, 2023, 12, 0, 0).is_err());
+        assert!(Time::new(1, 1, 2023, 24, 0, 0).is_err());
+    }
+
     #[test]
     fn reference_comparison() {
         let year = 2023;
         let month = 4;
         let day = 29;
-        let date = chrono::Utc
-            .with_ymd_and_hms(year, month, day, 14, 09, 0)
-            .unwrap();
+        let date = Local.with_ymd_and_hms(year, month, day, 14, 9, 0).unwrap();
         let time_1 = Time::from(date.to_rfc2822().as_str());
 
-        let time_2 = Time::new(29, 4, 2023, 14, 09, 0);
+        let time_2 = Time::new(29, 4, 2023, 14, 9, 0);
         assert!(&time_1 == time_2.as_ref().unwrap());
 
-        let time_2 = Time::new(29, 4, 2023, 14, 09, 1);
+        let time_2 = Time::new(29, 4, 2023, 14, 9, 1);
         assert!(&time_1 < time_2.as_ref().unwrap());
 
-        let time_2 = Time::new(29, 4, 2023, 14, 08, 59);
+        let time_2 = Time::new(29, 4, 2023, 14, 8, 59);
         assert!(&time_1 > time_2.as_ref().unwrap());
     }
+
+    #[test]
+    fn checked_arithmetic() {
+        let time = Time::from("2024-05-15T10:30:00Z");
+        let huge = Duration::new(chrono::Duration::MAX);
+        assert!(time.checked_add(&huge).is_err());
+        assert!(time.checked_sub(&huge).is_err());
+        assert_eq!(
+            time.checked_add(&Duration::from_hours(1)),
+            Ok(Time::from("2024-05-15T11:30:00Z"))
+        );
+    }
+
+    #[test]
+    fn durations_are_stored_as_seconds() {
+        for duration in [Duration::from_seconds(-1830), Duration::from_seconds(45)] {
+            let stored = serde_json::to_string(&duration).unwrap();
+            assert_eq!(stored, duration.num_seconds().to_string());
+            assert_eq!(serde_json::from_str::<Duration>(&stored).unwrap(), duration);
+        }
+        let older: Duration = serde_json::from_str("\"1h30m\"").unwrap();
+        assert_eq!(older, Duration::from_minutes(90));
+    }
+
+    #[test]
+    fn display_timezone() {
+        let time = Time::from("2024-05-15T22:30:00Z");
+        DisplayTimezone::from_setting(Some("Asia/Tokyo"))
+            .unwrap()
+            .set();
+        assert_eq!(time.iso_date(), "2024-05-16");
+        assert_eq!(time.hour(), 7);
+        assert_eq!(Time::new(16, 5, 2024, 7, 30, 0), Ok(time.clone()));
+        DisplayTimezone::from_setting(Some("America/New_York"))
+            .unwrap()
+            .set();
+        assert_eq!(time.iso_date(), "2024-05-15");
+        assert_eq!(time.hour(), 18);
+        DisplayTimezone::Local.set();
+        assert!(DisplayTimezone::from_setting(Some("Mars/Olympus_Mons")).is_err());
+    }
+
+    #[test]
+    fn time_in_dst_gap_moves_forward() {
+        // On 26 March 2023, clocks in Madrid jumped from 02:00 to 03:00.
+        let madrid = chrono_tz::Europe::Madrid;
+        let time = Time::from_calendar(&madrid, 26, 3, 2023, 2, 30, 0).unwrap();
+        assert_eq!(time, Time::from("2023-03-26T01:30:00Z"));
+        assert_eq!(time.in_timezone(&madrid).hour(), 3);
+    }
+
+    #[test]
+    fn ambiguous_time_resolves_to_earliest() {
+        // On 29 October 2023, clocks in Madrid went back from 03:00 to 02:00.
+        let madrid = chrono_tz::Europe::Madrid;
+        let time = Time::from_calendar(&madrid, 29, 10, 2023, 2, 30, 0).unwrap();
+        assert_eq!(time, Time::from("2023-10-29T00:30:00Z"));
+    }
+
+    #[test]
+    fn arithmetic_across_dst_transition() {
+        let madrid = chrono_tz::Europe::Madrid;
+        let before = Time::from_calendar(&madrid, 25, 3, 2023, 2, 30, 0).unwrap();
+        let after = &before + &Duration::from_hours(24);
+        assert_eq!(&after - &before, Duration::from_hours(24));
+        assert_eq!(after.in_timezone(&madrid).hour(), 3);
+        assert_eq!(&after - &Duration::from_hours(24), before);
+    }
+
+    #[test]
+    fn calendar_helpers() {
+        // Wednesday.
+        let time = Time::new(15, 5, 2024, 10, 30, 0).unwrap();
+        assert_eq!(
+            time.start_of_day(),
+            Time::new(15, 5, 2024, 0, 0, 0).unwrap()
+        );
+        assert_eq!(
+            time.start_of_week(),
+            Time::new(13, 5, 2024, 0, 0, 0).unwrap()
+        );
+        assert_eq!(time.add_days(20), Time::new(4, 6, 2024, 10, 30, 0).unwrap());
+        assert_eq!(
+            time.add_days(-15),
+            Time::new(30, 4, 2024, 10, 30, 0).unwrap()
+        );
+        assert_eq!(time.short_date(), "15/5");
+        assert_eq!(time.iso_date(), "2024-05-15");
+        assert_eq!(Time::from_iso_date("2024-05-15"), Ok(time.start_of_day()));
+        assert!(Time::from_iso_date("15/5/2024").is_err());
+
+        assert_eq!(
+            time.start_of_month(),
+            Time::new(1, 5, 2024, 0, 0, 0).unwrap()
+        );
+        assert_eq!(time.add_months(1), Time::new(1, 6, 2024, 0, 0, 0).unwrap());
+        assert_eq!(
+            time.add_months(-5),
+            Time::new(1, 12, 2023, 0, 0, 0).unwrap()
+        );
+        assert_eq!(time.add_months(8), Time::new(1, 1, 2025, 0, 0, 0).unwrap());
+        assert_eq!(time.month_and_year(), "May 2024");
+    }
+
+    #[test]
+    fn parse_compact_utc() {
+        assert_eq!(
+            Time::parse_compact_utc("20240515T103000Z"),
+            Ok(Time::from("2024-05-15T10:30:00Z"))
+        );
+        assert!(Time::parse_compact_utc("2024-05-15T10:30:00Z").is_err());
+        assert_eq!(
+            Time::from("2024-05-15T10:30:00.25Z").to_compact_utc(),
+            "20240515T103000Z"
+        );
+    }
+
+    #[test]
+    fn duration_display() {
+        assert_eq!(Duration::from_seconds(59).to_string(), "0m");
+        assert_eq!(Duration::from_minutes(75).to_string(), "1h 15m");
+        assert_eq!(Duration::from_hours(50).to_string(), "2d 2h 0m");
+        assert_eq!(Duration::from_minutes(-30).to_string(), "-30m");
+    }
+
+    #[test]
+    fn serialization_round_trip() {
+        let time = Time::now();
+        let serialized = serde_json::to_string(&time).unwrap();
+        assert!(serialized.ends_with("Z\""));
+        let deserialized: Time = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(time, deserialized);
+    }
+
+    #[test]
+    fn deserialize_legacy_format() {
+        let deserialized: Time =
+            serde_json::from_str("\"Sat, 21 Jan 2023 12:25:20 +0100\"").unwrap();
+        assert_eq!(deserialized, Time::from("2023-01-21T11:25:20Z"));
+
+        assert!(serde_json::from_str::<Time>("\"not a time\"").is_err());
+    }
 }
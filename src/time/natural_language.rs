@@ -0,0 +1,581 @@
+use super::{DisplayTimezone, Duration, Time, TimeError};
+use chrono::{Datelike, Days, Months, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Weekday};
+
+impl Time {
+    /// Parses a date written in natural language, relative to `now` and in the display timezone.
+    ///
+    /// Accepted phrases include "now", "today", "tomorrow 9am", "next friday", "monday at 14:30",
+    /// "in 3 days", "in 2h", "2 weeks ago", "2024-05-01 14:00", "1/5/2024" and "end of month".
+    /// A date without a time of day refers to its start, and a time of day without a date refers
+    /// to its next occurrence.
+    pub(crate) fn from_natural_language(text: &str, now: &Time) -> Result<Time, TimeError> {
+        parse(&DisplayTimezone::current(), text, now)
+    }
+
+    /// Parses the end of a period like [`Time::from_natural_language`], except that a date
+    /// without a time of day refers to its last second, so that "today" includes all of today.
+    pub(crate) fn end_from_natural_language(text: &str, now: &Time) -> Result<Time, TimeError> {
+        let end_of_day = NaiveTime::from_hms_opt(23, 59, 59).unwrap();
+        parse_with_default_time(&DisplayTimezone::current(), text, now, end_of_day)
+    }
+
+    /// Text that [`Time::from_natural_language`] parses back into this time, to the minute.
+    pub(crate) fn as_input_text(&self) -> String {
+        self.in_timezone(&DisplayTimezone::current())
+            .format("%Y-%m-%d %H:%M")
+            .to_string()
+    }
+}
+
+impl Duration {
+    /// Parses a duration such as "2h", "1h30m", "90 minutes" or "3 days".
+    pub(crate) fn parse(text: &str) -> Result<Duration, TimeError> {
+        let text = text.trim().to_lowercase();
+        let tokens = text.split_whitespace().collect::<Vec<_>>();
+        let amounts = match tokens.as_slice() {
+            [compact] => split_compact_amounts(compact)?,
+            [quantity, unit] => vec![(parse_quantity(quantity)?, parse_unit(unit)?)],
+            _ => return Err(TimeError {}),
+        };
+
+        let mut seconds = 0i64;
+        for (quantity, unit) in amounts {
+            seconds = unit
+                .fixed_seconds()
+                .and_then(|unit_seconds| quantity.checked_mul(unit_seconds))
+                .and_then(|amount| seconds.checked_add(amount))
+                .ok_or(TimeError {})?;
+        }
+        let duration = chrono::Duration::try_seconds(seconds).ok_or(TimeError {})?;
+        Ok(Duration::new(duration))
+    }
+
+    /// Compact text, such as "1d2h30m", that [`Duration::parse`] parses back into this duration,
+    /// to the minute.
+    pub(crate) fn as_input_text(&self) -> String {
+        let minutes = self.num_seconds() / 60;
+        let parts = [
+            (minutes / (60 * 24), "d"),
+            (minutes / 60 % 24, "h"),
+            (minutes % 60, "m"),
+        ];
+        let text = parts
+            .iter()
+            .filter(|(quantity, _)| *quantity != 0)
+            .map(|(quantity, unit)| format!("{quantity}{unit}"))
+            .collect::<String>();
+        if text.is_empty() {
+            "0m".to_string()
+        } else {
+            text
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Unit {
+    Minute,
+    Hour,
+    Day,
+    Week,
+    Month,
+    Year,
+}
+
+impl Unit {
+    fn fixed_seconds(&self) -> Option<i64> {
+        match self {
+            Unit::Minute => Some(60),
+            Unit::Hour => Some(3600),
+            Unit::Day => Some(3600 * 24),
+            Unit::Week => Some(3600 * 24 * 7),
+            Unit::Month | Unit::Year => None,
+        }
+    }
+}
+
+pub(super) fn parse<Tz: TimeZone>(
+    timezone: &Tz,
+    text: &str,
+    now: &Time,
+) -> Result<Time, TimeError> {
+    parse_with_default_time(timezone, text, now, NaiveTime::MIN)
+}
+
+/// Parses `text`, where a date without a time of day refers to `default_time` on that day.
+fn parse_with_default_time<Tz: TimeZone>(
+    timezone: &Tz,
+    text: &str,
+    now: &Time,
+    default_time: NaiveTime,
+) -> Result<Time, TimeError> {
+    let text = text.trim().to_lowercase();
+    let tokens = text.split_whitespace().collect::<Vec<_>>();
+    let now_local = now.in_timezone(timezone).naive_local();
+
+    match tokens.as_slice() {
+        [] => Err(TimeError {}),
+        ["now"] => Ok(now.clone()),
+        ["in", amount] => shift(timezone, now, split_compact_amounts(amount)?, 1),
+        ["in", quantity, unit] => shift(
+            timezone,
+            now,
+            vec![(parse_quantity(quantity)?, parse_unit(unit)?)],
+            1,
+        ),
+        [amount, "ago"] => shift(timezone, now, split_compact_amounts(amount)?, -1),
+        [quantity, unit, "ago"] => shift(
+            timezone,
+            now,
+            vec![(parse_quantity(quantity)?, parse_unit(unit)?)],
+            -1,
+        ),
+        ["end", "of", period] => {
+            let date = end_of(period, now_local.date())?;
+            at(timezone, date.and_hms_opt(23, 59, 59).ok_or(TimeError {})?)
+        }
+        _ => {
+            let (date, rest) = parse_date(&tokens, now_local.date())?;
+            let rest = match rest {
+                ["at", rest @ ..] => rest,
+                rest => rest,
+            };
+            let time_of_day = if rest.is_empty() {
+                None
+            } else {
+                Some(parse_time_of_day(&rest.concat())?)
+            };
+
+            match (date, time_of_day) {
+                (Some(date), time_of_day) => {
+                    at(timezone, date.and_time(time_of_day.unwrap_or(default_time)))
+                }
+                (None, Some(time_of_day)) => {
+                    let today = now_local.date().and_time(time_of_day);
+                    if today > now_local {
+                        at(timezone, today)
+                    } else {
+                        at(timezone, add_days(today, 1)?)
+                    }
+                }
+                (None, None) => Err(TimeError {}),
+            }
+        }
+    }
+}
+
+fn at<Tz: TimeZone>(timezone: &Tz, naive: NaiveDateTime) -> Result<Time, TimeError> {
+    Ok(Time::from_naive(timezone, &naive))
+}
+
+fn add_days(naive: NaiveDateTime, days: i64) -> Result<NaiveDateTime, TimeError> {
+    let shifted = if days >= 0 {
+        naive.checked_add_days(Days::new(days as u64))
+    } else {
+        naive.checked_sub_days(Days::new(days.unsigned_abs()))
+    };
+    shifted.ok_or(TimeError {})
+}
+
+fn add_months(naive: NaiveDateTime, months: i64) -> Result<NaiveDateTime, TimeError> {
+    let months_u32 = u32::try_from(months.unsigned_abs()).map_err(|_| TimeError {})?;
+    let shifted = if months >= 0 {
+        naive.checked_add_months(Months::new(months_u32))
+    } else {
+        naive.checked_sub_months(Months::new(months_u32))
+    };
+    shifted.ok_or(TimeError {})
+}
+
+/// Moves `now` by the given amounts. Days, weeks, months and years keep the wall-clock time in
+/// `timezone`, while hours and minutes are exact. Fails if the result is out of range.
+fn shift<Tz: TimeZone>(
+    timezone: &Tz,
+    now: &Time,
+    amounts: Vec<(i64, Unit)>,
+    sign: i64,
+) -> Result<Time, TimeError> {
+    let mut time = now.clone();
+    for (quantity, unit) in amounts {
+        let quantity = quantity.checked_mul(sign).ok_or(TimeError {})?;
+        let local = time.in_timezone(timezone).naive_local();
+        time = match unit {
+            Unit::Minute | Unit::Hour => {
+                let duration = unit
+                    .fixed_seconds()
+                    .and_then(|unit_seconds| quantity.checked_mul(unit_seconds))
+                    .and_then(chrono::Duration::try_seconds)
+                    .ok_or(TimeError {})?;
+                let time = time.time.checked_add_signed(duration).ok_or(TimeError {})?;
+                Time { time }
+            }
+            Unit::Day => at(timezone, add_days(local, quantity)?)?,
+            Unit::Week => at(timezone, add_days(local, multiply(quantity, 7)?)?)?,
+            Unit::Month => at(timezone, add_months(local, quantity)?)?,
+            Unit::Year => at(timezone, add_months(local, multiply(quantity, 12)?)?)?,
+        };
+    }
+    Ok(time)
+}
+
+fn multiply(quantity: i64, factor: i64) -> Result<i64, TimeError> {
+    quantity.checked_mul(factor).ok_or(TimeError {})
+}
+
+fn parse_quantity(text: &str) -> Result<i64, TimeError> {
+    match text {
+        "a" | "an" | "one" => Ok(1),
+        _ => text.parse::<i64>().map_err(|_| TimeError {}),
+    }
+}
+
+fn parse_unit(text: &str) -> Result<Unit, TimeError> {
+    match text {
+        "m" | "min" | "mins" | "minute" | "minutes" => Ok(Unit::Minute),
+        "h" | "hr" | "hrs" | "hour" | "hours" => Ok(Unit::Hour),
+        "d" | "day" | "days" => Ok(Unit::Day),
+        "w" | "wk" | "wks" | "week" | "weeks" => Ok(Unit::Week),
+        "mo" | "month" | "months" => Ok(Unit::Month),
+        "y" | "yr" | "yrs" | "year" | "years" => Ok(Unit::Year),
+        _ => Err(TimeError {}),
+    }
+}
+
+/// Splits amounts written without spaces, such as "2h" or "1h30m".
+fn split_compact_amounts(text: &str) -> Result<Vec<(i64, Unit)>, TimeError> {
+    let mut amounts = vec![];
+    let mut rest = text;
+    while !rest.is_empty() {
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let letters = rest[digits..]
+            .find(|c: char| c.is_ascii_digit())
+            .map_or(rest.len(), |position| position + digits);
+        if digits == 0 || letters == digits {
+            return Err(TimeError {});
+        }
+        amounts.push((
+            parse_quantity(&rest[..digits])?,
+            parse_unit(&rest[digits..letters])?,
+        ));
+        rest = &rest[letters..];
+    }
+    if amounts.is_empty() {
+        return Err(TimeError {});
+    }
+    Ok(amounts)
+}
+
+fn parse_weekday(text: &str) -> Option<Weekday> {
+    match text {
+        "monday" | "mon" => Some(Weekday::Mon),
+        "tuesday" | "tue" | "tues" => Some(Weekday::Tue),
+        "wednesday" | "wed" => Some(Weekday::Wed),
+        "thursday" | "thu" | "thurs" => Some(Weekday::Thu),
+        "friday" | "fri" => Some(Weekday::Fri),
+        "saturday" | "sat" => Some(Weekday::Sat),
+        "sunday" | "sun" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+/// First day strictly after `today` that falls on `weekday`.
+fn next_weekday(today: NaiveDate, weekday: Weekday) -> NaiveDate {
+    let days_ahead =
+        (weekday.num_days_from_monday() + 7 - today.weekday().num_days_from_monday() - 1) % 7 + 1;
+    today + chrono::Duration::days(days_ahead as i64)
+}
+
+/// Last day strictly before `today` that falls on `weekday`.
+fn previous_weekday(today: NaiveDate, weekday: Weekday) -> NaiveDate {
+    let days_behind =
+        (today.weekday().num_days_from_monday() + 7 - weekday.num_days_from_monday() - 1) % 7 + 1;
+    today - chrono::Duration::days(days_behind as i64)
+}
+
+/// Parses the date at the beginning of `tokens`, and returns the tokens that follow it.
+fn parse_date<'a, 'b>(
+    tokens: &'a [&'b str],
+    today: NaiveDate,
+) -> Result<(Option<NaiveDate>, &'a [&'b str]), TimeError> {
+    let relative_day = |days: i64| today + chrono::Duration::days(days);
+    let date = match tokens {
+        ["today", rest @ ..] => (Some(today), rest),
+        ["tomorrow", rest @ ..] => (Some(relative_day(1)), rest),
+        ["yesterday", rest @ ..] => (Some(relative_day(-1)), rest),
+        ["next", "week", rest @ ..] => (Some(relative_day(7)), rest),
+        ["next", "month", rest @ ..] => (
+            Some(
+                today
+                    .checked_add_months(Months::new(1))
+                    .ok_or(TimeError {})?,
+            ),
+            rest,
+        ),
+        ["next", "year", rest @ ..] => (
+            Some(
+                today
+                    .checked_add_months(Months::new(12))
+                    .ok_or(TimeError {})?,
+            ),
+            rest,
+        ),
+        ["next", weekday, rest @ ..] => (
+            Some(next_weekday(
+                today,
+                parse_weekday(weekday).ok_or(TimeError {})?,
+            )),
+            rest,
+        ),
+        ["last", weekday, rest @ ..] => (
+            Some(previous_weekday(
+                today,
+                parse_weekday(weekday).ok_or(TimeError {})?,
+            )),
+            rest,
+        ),
+        [first, rest @ ..] => {
+            if let Some(weekday) = parse_weekday(first) {
+                (Some(next_weekday(today, weekday)), rest)
+            } else if let Some(date) = parse_numeric_date(first) {
+                (Some(date), rest)
+            } else {
+                (None, tokens)
+            }
+        }
+        [] => (None, tokens),
+    };
+    Ok(date)
+}
+
+/// Parses "2024-05-01" or "1/5/2024" (day/month/year).
+fn parse_numeric_date(text: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(text, "%Y-%m-%d")
+        .or_else(|_| NaiveDate::parse_from_str(text, "%d/%m/%Y"))
+        .ok()
+}
+
+/// Parses "9am", "9:30pm", "14:00", "noon" or "midnight".
+fn parse_time_of_day(text: &str) -> Result<NaiveTime, TimeError> {
+    match text {
+        "noon" => return NaiveTime::from_hms_opt(12, 0, 0).ok_or(TimeError {}),
+        "midnight" => return Ok(NaiveTime::MIN),
+        _ => {}
+    }
+
+    let (clock, meridiem_offset) = if let Some(clock) = text.strip_suffix("am") {
+        (clock, Some(0))
+    } else if let Some(clock) = text.strip_suffix("pm") {
+        (clock, Some(12))
+    } else {
+        (text, None)
+    };
+
+    let (hour, minute) = match clock.split_once(':') {
+        Some((hour, minute)) => (hour, minute),
+        None if meridiem_offset.is_some() => (clock, "0"),
+        None => return Err(TimeError {}),
+    };
+    let mut hour = hour.parse::<u32>().map_err(|_| TimeError {})?;
+    let minute = minute.parse::<u32>().map_err(|_| TimeError {})?;
+
+    if let Some(meridiem_offset) = meridiem_offset {
+        if !(1..=12).contains(&hour) {
+            return Err(TimeError {});
+        }
+        hour = hour % 12 + meridiem_offset;
+    }
+    NaiveTime::from_hms_opt(hour, minute, 0).ok_or(TimeError {})
+}
+
+fn end_of(period: &str, today: NaiveDate) -> Result<NaiveDate, TimeError> {
+    match period {
+        "day" | "today" => Ok(today),
+        "week" => {
+            Ok(today + chrono::Duration::days(6 - today.weekday().num_days_from_monday() as i64))
+        }
+        "month" => {
+            let first_of_month = today.with_day(1).ok_or(TimeError {})?;
+            let first_of_next_month = first_of_month
+                .checked_add_months(Months::new(1))
+                .ok_or(TimeError {})?;
+            first_of_next_month.pred_opt().ok_or(TimeError {})
+        }
+        "year" => NaiveDate::from_ymd_opt(today.year(), 12, 31).ok_or(TimeError {}),
+        _ => Err(TimeError {}),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    // Wednesday.
+    const NOW: &str = "2024-05-15T10:30:00Z";
+
+    fn parse_utc(text: &str) -> Result<Time, TimeError> {
+        parse(&Utc, text, &Time::from(NOW))
+    }
+
+    fn assert_parses(text: &str, expected: &str) {
+        assert_eq!(parse_utc(text), Ok(Time::from(expected)), "{text}");
+    }
+
+    #[test]
+    fn relative_days() {
+        assert_parses("now", NOW);
+        assert_parses("today", "2024-05-15T00:00:00Z");
+        assert_parses("Tomorrow", "2024-05-16T00:00:00Z");
+        assert_parses("tomorrow 9am", "2024-05-16T09:00:00Z");
+        assert_parses("tomorrow at 9:15 pm", "2024-05-16T21:15:00Z");
+        assert_parses("yesterday noon", "2024-05-14T12:00:00Z");
+    }
+
+    #[test]
+    fn ends_of_periods() {
+        let end_of_day = NaiveTime::from_hms_opt(23, 59, 59).unwrap();
+        let parse_end = |text| parse_with_default_time(&Utc, text, &Time::from(NOW), end_of_day);
+        assert_eq!(parse_end("today"), Ok(Time::from("2024-05-15T23:59:59Z")));
+        assert_eq!(
+            parse_end("2024-05-07"),
+            Ok(Time::from("2024-05-07T23:59:59Z"))
+        );
+        assert_eq!(
+            parse_end("tomorrow 9am"),
+            Ok(Time::from("2024-05-16T09:00:00Z"))
+        );
+        assert_eq!(parse_end("now"), Ok(Time::from(NOW)));
+    }
+
+    #[test]
+    fn weekdays() {
+        assert_parses("friday", "2024-05-17T00:00:00Z");
+        assert_parses("next friday", "2024-05-17T00:00:00Z");
+        assert_parses("next wednesday", "2024-05-22T00:00:00Z");
+        assert_parses("last wednesday", "2024-05-08T00:00:00Z");
+        assert_parses("mon 14:00", "2024-05-20T14:00:00Z");
+        assert_parses("next week", "2024-05-22T00:00:00Z");
+        assert_parses("next month", "2024-06-15T00:00:00Z");
+    }
+
+    #[test]
+    fn offsets() {
+        assert_parses("in 3 days", "2024-05-18T10:30:00Z");
+        assert_parses("in 2h", "2024-05-15T12:30:00Z");
+        assert_parses("in 1h30m", "2024-05-15T12:00:00Z");
+        assert_parses("in an hour", "2024-05-15T11:30:00Z");
+        assert_parses("in 2 weeks", "2024-05-29T10:30:00Z");
+        assert_parses("in 1 month", "2024-06-15T10:30:00Z");
+        assert_parses("2 days ago", "2024-05-13T10:30:00Z");
+        assert_parses("1y ago", "2023-05-15T10:30:00Z");
+    }
+
+    #[test]
+    fn absolute_dates() {
+        assert_parses("2024-05-01 14:00", "2024-05-01T14:00:00Z");
+        assert_parses("2024-05-01", "2024-05-01T00:00:00Z");
+        assert_parses("1/6/2024 at 8am", "2024-06-01T08:00:00Z");
+    }
+
+    #[test]
+    fn time_of_day_only() {
+        assert_parses("11am", "2024-05-15T11:00:00Z");
+        assert_parses("9am", "2024-05-16T09:00:00Z");
+        assert_parses("12am", "2024-05-16T00:00:00Z");
+        assert_parses("midnight", "2024-05-16T00:00:00Z");
+    }
+
+    #[test]
+    fn end_of_period() {
+        assert_parses("end of day", "2024-05-15T23:59:59Z");
+        assert_parses("end of week", "2024-05-19T23:59:59Z");
+        assert_parses("end of month", "2024-05-31T23:59:59Z");
+        assert_parses("end of year", "2024-12-31T23:59:59Z");
+    }
+
+    #[test]
+    fn invalid_input() {
+        for text in [
+            "",
+            "someday",
+            "in 3 parsecs",
+            "in h",
+            "2024-02-30",
+            "13pm",
+            "25:00",
+            "tomorrow 9 apples",
+            "end of time",
+        ] {
+            assert!(parse_utc(text).is_err(), "{text}");
+        }
+    }
+
+    #[test]
+    fn days_keep_wall_clock_time_across_dst() {
+        let madrid = chrono_tz::Europe::Madrid;
+        let now = Time::from_calendar(&madrid, 25, 3, 2023, 10, 0, 0).unwrap();
+        let later = parse(&madrid, "in 1 day", &now).unwrap();
+        assert_eq!(
+            later,
+            Time::from_calendar(&madrid, 26, 3, 2023, 10, 0, 0).unwrap()
+        );
+        assert_eq!(&later - &now, Duration::from_hours(23));
+    }
+
+    #[test]
+    fn input_text_round_trip() {
+        let now = Time::now();
+        let time = Time::new(1, 5, 2024, 14, 0, 0).unwrap();
+        assert_eq!(
+            Time::from_natural_language(&time.as_input_text(), &now),
+            Ok(time)
+        );
+    }
+
+    #[test]
+    fn durations() {
+        assert_eq!(Duration::parse("2h"), Ok(Duration::from_hours(2)));
+        assert_eq!(Duration::parse("1h30m"), Ok(Duration::from_minutes(90)));
+        assert_eq!(
+            Duration::parse("90 minutes"),
+            Ok(Duration::from_minutes(90))
+        );
+        assert_eq!(Duration::parse("3 days"), Ok(Duration::from_hours(72)));
+        assert!(Duration::parse("1 month").is_err());
+        assert!(Duration::parse("soon").is_err());
+    }
+
+    #[test]
+    fn huge_amounts_are_errors() {
+        for text in [
+            "in 99999999999999h",
+            "in 9223372036854775807m",
+            "in 99999999999999 days",
+            "in 9999999999999999 weeks",
+            "in 999999999999 months",
+            "in 9999999999999999 years",
+            "99999999999999h ago",
+            "in 1h99999999999999999m",
+        ] {
+            assert!(parse_utc(text).is_err(), "{text}");
+        }
+        for text in [
+            "99999999999999h",
+            "9223372036854775807 weeks",
+            "1d9223372036854775807m",
+        ] {
+            assert!(Duration::parse(text).is_err(), "{text}");
+        }
+    }
+
+    #[test]
+    fn duration_input_text_round_trip() {
+        for minutes in [0, 45, 120, 150, 60 * 24 * 3 + 5] {
+            let duration = Duration::from_minutes(minutes);
+            assert_eq!(Duration::parse(&duration.as_input_text()), Ok(duration));
+        }
+        assert_eq!(Duration::from_minutes(150).as_input_text(), "2h30m");
+    }
+}
@@ -0,0 +1,180 @@
+use crate::data::{FileError, FileErrorKind};
+use crate::task::Task;
+use crate::time::{Duration, Time};
+use serde::{Deserialize, Serialize};
+
+/// A period of work on a task. An entry without an end is a running timer.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub(crate) struct TimeEntry {
+    pub start: Time,
+    pub end: Option<Time>,
+}
+
+impl TimeEntry {
+    /// End of the entry, or `now` if it is running.
+    fn end_or<'a>(&'a self, now: &'a Time) -> &'a Time {
+        self.end.as_ref().unwrap_or(now)
+    }
+
+    pub(crate) fn duration(&self, now: &Time) -> Duration {
+        self.end_or(now) - &self.start
+    }
+
+    /// Part of the entry between `initial_date` and `final_date`.
+    pub(crate) fn duration_between(
+        &self,
+        initial_date: &Time,
+        final_date: &Time,
+        now: &Time,
+    ) -> Duration {
+        let start = (&self.start).max(initial_date);
+        let end = self.end_or(now).min(final_date);
+        if start < end {
+            end - start
+        } else {
+            Duration::from_seconds(0)
+        }
+    }
+}
+
+/// Time tracked on `task` between `initial_date` and `final_date`.
+pub(crate) fn tracked_between(
+    task: &Task,
+    initial_date: &Time,
+    final_date: &Time,
+    now: &Time,
+) -> Duration {
+    task.time_entries
+        .iter()
+        .map(|entry| entry.duration_between(initial_date, final_date, now))
+        .sum()
+}
+
+/// Timesheet in CSV of the time entries of `tasks` that overlap the period, one row per entry
+/// and ordered by start, with the time in the period in minutes.
+pub(crate) fn timesheet(
+    tasks: &[&Task],
+    initial_date: &Time,
+    final_date: &Time,
+    now: &Time,
+) -> String {
+    let mut rows = tasks
+        .iter()
+        .flat_map(|task| task.time_entries.iter().map(move |entry| (task, entry)))
+        .filter(|(_, entry)| {
+            entry
+                .duration_between(initial_date, final_date, now)
+                .num_seconds()
+                > 0
+        })
+        .collect::<Vec<_>>();
+    rows.sort_by(|(_, entry), (_, other)| entry.start.cmp(&other.start));
+
+    let mut csv = "date,description,tags,start,end,minutes\n".to_string();
+    for (task, entry) in rows {
+        let minutes = entry
+            .duration_between(initial_date, final_date, now)
+            .num_seconds()
+            / 60;
+        let fields = [
+            entry.start.iso_date(),
+            task.description().to_string(),
+            task.tags.join(";"),
+            entry.start.to_rfc3339(),
+            entry.end.as_ref().map(Time::to_rfc3339).unwrap_or_default(),
+            minutes.to_string(),
+        ];
+        let fields = fields
+            .iter()
+            .map(|field| crate::export::csv_field(field))
+            .collect::<Vec<_>>();
+        csv.push_str(&fields.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+/// Writes the timesheet of `tasks` for the period to `file_name`, which must be a `.csv` file.
+pub(crate) fn export_timesheet(
+    tasks: &[&Task],
+    initial_date: &Time,
+    final_date: &Time,
+    now: &Time,
+    file_name: &str,
+) -> Result<(), FileError> {
+    if !file_name.to_lowercase().ends_with(".csv") {
+        return Err(FileError {
+            message: "Timesheets are exported to '.csv' files.".to_string(),
+            kind: FileErrorKind::Write,
+        });
+    }
+    let contents = timesheet(tasks, initial_date, final_date, now);
+    std::fs::write(file_name, contents).map_err(|_| FileError {
+        message: "Problem writing timesheet file.".to_string(),
+        kind: FileErrorKind::Write,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::SystemClock;
+
+    fn time(hour: u32, minute: u32) -> Time {
+        Time::new(13, 5, 2024, hour, minute, 0).unwrap()
+    }
+
+    fn entry(start: Time, end: Option<Time>) -> TimeEntry {
+        TimeEntry { start, end }
+    }
+
+    #[test]
+    fn durations() {
+        let now = time(12, 0);
+        let closed = entry(time(9, 0), Some(time(10, 30)));
+        assert_eq!(closed.duration(&now), Duration::from_minutes(90));
+        assert_eq!(
+            closed.duration_between(&time(10, 0), &time(18, 0), &now),
+            Duration::from_minutes(30)
+        );
+        assert_eq!(
+            closed.duration_between(&time(11, 0), &time(18, 0), &now),
+            Duration::from_seconds(0)
+        );
+
+        let running = entry(time(11, 0), None);
+        assert_eq!(running.duration(&now), Duration::from_hours(1));
+        assert_eq!(
+            running.duration_between(&time(0, 0), &time(23, 0), &now),
+            Duration::from_hours(1)
+        );
+    }
+
+    #[test]
+    fn timesheets() {
+        let now = time(12, 0);
+        let mut report = Task::new(0, &SystemClock);
+        report.edit("Write report, part 1");
+        report.tags = vec!["acme".to_string()];
+        report.time_entries = vec![
+            entry(time(10, 0), Some(time(10, 45))),
+            entry(time(8, 0), Some(time(8, 30))),
+        ];
+        let mut call = Task::new(1, &SystemClock);
+        call.edit("Call");
+        call.time_entries = vec![entry(time(9, 0), Some(time(9, 10)))];
+
+        assert_eq!(
+            tracked_between(&report, &time(0, 0), &time(23, 0), &now),
+            Duration::from_minutes(75)
+        );
+        let csv = timesheet(&[&report, &call], &time(8, 15), &time(23, 0), &now);
+        let lines = csv.lines().collect::<Vec<_>>();
+        assert_eq!(lines[0], "date,description,tags,start,end,minutes");
+        assert!(lines[1].starts_with("2024-05-13,\"Write report, part 1\",acme,"));
+        assert!(lines[1].ends_with(",15"));
+        assert!(lines[2].starts_with("2024-05-13,Call,,"));
+        assert!(lines[3].ends_with(",45"));
+        assert_eq!(lines.len(), 4);
+    }
+}
@@ -0,0 +1,220 @@
+use crate::clock::Clock;
+use crate::task::{Priority, Task};
+use crate::time::{Duration, Time};
+
+/// Parses a list in the [todo.txt](https://github.com/todotxt/todo.txt) format, one task per
+/// line, such as `x 2024-05-15 2024-05-10 (A) Call Ann +work @phone due:2024-05-20`.
+///
+/// Projects (`+work`) and contexts (`@phone`) become tags, priorities `(A)` and `(B)` become high
+/// and medium and any other letter becomes low. The threshold date (`t:2024-05-16`) hides the
+/// task until that day, like a snooze, `estimate:` holds the expected effort, such as `1h30m`,
+/// and `uid:` keeps the identifier of the task, which links it to what the settings file of the
+/// list holds about it.
+/// Blank lines are skipped, but not blank tasks, which have a `uid:`. Tasks without a creation
+/// date are created at the time of `clock`.
+pub(crate) fn parse(text: &str, clock: &dyn Clock) -> Vec<Task> {
+    text.lines()
+        .filter_map(|line| parse_line(line, clock))
+        .collect()
+}
+
+/// Writes `tasks` in the todo.txt format, one per line. Times are written as dates, and tags as
+/// projects.
+pub(crate) fn format(tasks: &[&Task]) -> String {
+    tasks.iter().map(|task| format_line(task) + "\n").collect()
+}
+
+fn format_line(task: &Task) -> String {
+    let mut words = vec![];
+    let priority = task.priority.map(|priority| match priority {
+        Priority::High => "A",
+        Priority::Medium => "B",
+        Priority::Low => "C",
+    });
+    if task.completed() {
+        words.push("x".to_string());
+        if let Some(completion_time) = &task.completion_time {
+            words.push(completion_time.iso_date());
+        }
+    } else if let Some(priority) = priority {
+        words.push(format!("({priority})"));
+    }
+    words.push(task.creation_time.iso_date());
+    words.extend(task.description().split_whitespace().map(str::to_string));
+    words.extend(task.tags.iter().map(|tag| format!("+{tag}")));
+    if let Some(due_date) = &task.due_date {
+        words.push(format!("due:{}", due_date.iso_date()));
+    }
+    if let Some(snooze_until) = &task.snooze_information.snooze_until {
+        words.push(format!("t:{}", snooze_until.iso_date()));
+    }
+    if let (true, Some(priority)) = (task.completed(), priority) {
+        words.push(format!("pri:{priority}"));
+    }
+    if let Some(estimate) = &task.estimate {
+        words.push(format!("estimate:{}", estimate.as_input_text()));
+    }
+    if let Some(uid) = &task.uid {
+        words.push(format!("uid:{uid}"));
+    }
+    words.join(" ")
+}
+
+fn parse_line(line: &str, clock: &dyn Clock) -> Option<Task> {
+    let mut words = line.split_whitespace().peekable();
+    let mut task = Task::new(0, clock);
+
+    let completed = words.next_if_eq(&"x").is_some();
+    let completion_date = if completed {
+        words.next_if(|word| Time::from_iso_date(word).is_ok())
+    } else {
+        None
+    };
+    if !completed {
+        if let Some(priority) = words.next_if(|word| parse_priority(word).is_some()) {
+            task.priority = parse_priority(priority);
+        }
+    }
+    if let Some(creation_date) = words.next_if(|word| Time::from_iso_date(word).is_ok()) {
+        task.creation_time = Time::from_iso_date(creation_date).unwrap();
+    }
+
+    let mut description = vec![];
+    let mut has_uid = false;
+    for word in words {
+        let tag = word
+            .strip_prefix('+')
+            .or_else(|| word.strip_prefix('@'))
+            .filter(|tag| !tag.is_empty());
+        let due_date = word
+            .strip_prefix("due:")
+            .and_then(|date| Time::from_iso_date(date).ok());
+        let priority = word
+            .strip_prefix("pri:")
+            .and_then(|letter| parse_priority(&format!("({letter})")));
+        let threshold = word
+            .strip_prefix("t:")
+            .and_then(|date| Time::from_iso_date(date).ok());
+        let estimate = word
+            .strip_prefix("estimate:")
+            .and_then(|estimate| Duration::parse(estimate).ok());
+        let uid = word.strip_prefix("uid:").filter(|uid| !uid.is_empty());
+        if let Some(uid) = uid {
+            task.uid = Some(uid.to_string());
+            has_uid = true;
+        } else if let Some(estimate) = estimate {
+            task.estimate = Some(estimate);
+        } else if let Some(threshold) = threshold {
+            task.snooze_information.snooze_until = Some(threshold);
+        } else if let Some(tag) = tag {
+            task.tags.push(tag.to_string());
+        } else if let Some(due_date) = due_date {
+            task.due_date = Some(due_date);
+        } else if let Some(priority) = priority {
+            task.priority = Some(priority);
+        } else {
+            description.push(word);
+        }
+    }
+    if description.is_empty() && task.tags.is_empty() && !has_uid {
+        return None;
+    }
+    task.edit(&description.join(" "));
+
+    if completed {
+        task.set_completed(true, clock);
+        task.completion_time = Some(match completion_date {
+            Some(date) => Time::from_iso_date(date).unwrap(),
+            None => task.creation_time.clone(),
+        });
+    }
+    Some(task)
+}
+
+fn parse_priority(word: &str) -> Option<Priority> {
+    let letter = word.strip_prefix('(')?.strip_suffix(')')?;
+    match letter {
+        "A" => Some(Priority::High),
+        "B" => Some(Priority::Medium),
+        _ if letter.len() == 1 && letter.chars().all(|c| c.is_ascii_uppercase()) => {
+            Some(Priority::Low)
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::{FakeClock, SystemClock};
+
+    #[test]
+    fn pending_task() {
+        let tasks = parse(
+            "(A) 2024-05-10 Call Ann +work @phone due:2024-05-20\n",
+            &SystemClock,
+        );
+        assert_eq!(tasks.len(), 1);
+        let task = &tasks[0];
+        assert_eq!(task.description(), "Call Ann");
+        assert!(!task.completed());
+        assert_eq!(task.priority, Some(Priority::High));
+        assert_eq!(task.tags, vec!["work", "phone"]);
+        assert_eq!(
+            task.creation_time,
+            Time::from_iso_date("2024-05-10").unwrap()
+        );
+        assert_eq!(task.due_date, Time::from_iso_date("2024-05-20").ok());
+    }
+
+    #[test]
+    fn completed_task() {
+        let tasks = parse("x 2024-05-15 2024-05-10 Pay rent pri:B", &SystemClock);
+        let task = &tasks[0];
+        assert_eq!(task.description(), "Pay rent");
+        assert!(task.completed());
+        assert_eq!(task.completion_time, Time::from_iso_date("2024-05-15").ok());
+        assert_eq!(
+            task.creation_time,
+            Time::from_iso_date("2024-05-10").unwrap()
+        );
+        assert_eq!(task.priority, Some(Priority::Medium));
+    }
+
+    #[test]
+    fn format_and_parse_again() {
+        let text = "(A) 2024-05-10 Call Ann +work +phone due:2024-05-20 t:2024-05-12 uid:ann\n\
+                    x 2024-05-15 2024-05-10 Pay rent pri:B uid:abc@example.com\n\
+                    2024-05-11 Buy milk estimate:1h30m uid:milk\n";
+        let tasks = parse(text, &SystemClock);
+        assert_eq!(tasks[2].estimate, Some(Duration::from_minutes(90)));
+        assert_eq!(
+            tasks[0].snooze_information.snooze_until,
+            Time::from_iso_date("2024-05-12").ok()
+        );
+
+        let formatted = format(&tasks.iter().collect::<Vec<_>>());
+        assert_eq!(formatted, text);
+        assert_eq!(parse(&formatted, &SystemClock), tasks);
+    }
+
+    #[test]
+    fn plain_lines() {
+        let tasks = parse(
+            "Buy milk\n\n   \n(D) xylophone lessons\nx\nhttp://example.com due:soon",
+            &FakeClock::new(Time::from("2024-05-15T10:30:00Z")),
+        );
+        let descriptions = tasks.iter().map(Task::description).collect::<Vec<_>>();
+        assert_eq!(
+            descriptions,
+            vec![
+                "Buy milk",
+                "xylophone lessons",
+                "http://example.com due:soon"
+            ]
+        );
+        assert_eq!(tasks[1].priority, Some(Priority::Low));
+        assert_eq!(tasks[0].priority, None);
+        assert_eq!(tasks[0].creation_time, Time::from("2024-05-15T10:30:00Z"));
+    }
+}
@@ -1,3 +1,5 @@
+use crate::clock::Clock;
+use crate::time::Time;
 use iced::{
     alignment,
     widget::{button, Button, Text},
@@ -5,14 +7,23 @@ use iced::{
 };
 pub(crate) trait ToIced {
     type Message;
-    fn view(&self) -> Element<Self::Message>;
-    fn update(&mut self, message: Self::Message);
+    fn view(&self, clock: &dyn Clock) -> Element<'_, Self::Message>;
+    fn update(&mut self, message: Self::Message, clock: &dyn Clock);
 }
 
-pub(crate) fn add_button<Message>(text: &str, message: Message) -> Button<Message> {
+pub(crate) fn add_button<Message>(text: &str, message: Message) -> Button<'_, Message> {
     let text = Text::new(text)
-        .width(Length::try_from(60).unwrap())
+        .width(Length::from(60))
         .horizontal_alignment(alignment::Horizontal::Center)
         .size(20);
     button(text).on_press(message).padding(10)
 }
+
+/// Text showing how a date written in natural language is understood.
+pub(crate) fn date_preview<'a>(input: &str, clock: &dyn Clock) -> Text<'a> {
+    match Time::from_natural_language(input, &clock.now()) {
+        Ok(time) => Text::new(time.to_string()),
+        Err(_) if input.trim().is_empty() => Text::new(""),
+        Err(_) => Text::new("Unrecognized date").style(iced::Color::from_rgb(1., 0., 0.)),
+    }
+}
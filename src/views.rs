@@ -1,19 +1,44 @@
+pub(crate) mod agenda_view;
+pub(crate) mod archive_view;
+pub(crate) mod board_view;
+pub(crate) mod calendar_view;
+pub(crate) mod charts;
+pub(crate) mod conflicts_view;
+pub(crate) mod focus_view;
 pub(crate) mod list_view;
 pub(crate) mod periodic_tasks_management;
 pub(crate) mod summary_view;
+pub(crate) mod trash_view;
+
+use iced::futures::channel::mpsc;
+use iced::futures::StreamExt;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ViewType {
     List,
     Summary,
     PeriodicTasksManagement,
+    Conflicts,
+    Archive,
+    Trash,
+    Focus,
+    Board,
+    Calendar,
+    Agenda,
 }
 
 impl ViewType {
-    pub const ALL: [ViewType; 3] = [
+    pub const ALL: [ViewType; 10] = [
         ViewType::List,
         ViewType::Summary,
         ViewType::PeriodicTasksManagement,
+        ViewType::Conflicts,
+        ViewType::Archive,
+        ViewType::Trash,
+        ViewType::Focus,
+        ViewType::Board,
+        ViewType::Calendar,
+        ViewType::Agenda,
     ];
 }
 
@@ -26,6 +51,13 @@ impl std::fmt::Display for ViewType {
                 ViewType::List => "List",
                 ViewType::Summary => "Summary",
                 ViewType::PeriodicTasksManagement => "Periodic Tasks Management",
+                ViewType::Conflicts => "Merge Conflicts",
+                ViewType::Archive => "Archive",
+                ViewType::Trash => "Trash",
+                ViewType::Focus => "Focus",
+                ViewType::Board => "Board",
+                ViewType::Calendar => "Calendar",
+                ViewType::Agenda => "Today",
             }
         )
     }
@@ -36,4 +68,38 @@ pub enum Message {
     List(list_view::Message),
     Summary(summary_view::Message),
     PeriodicTasksManagement(periodic_tasks_management::Message),
+    Conflicts(conflicts_view::Message),
+    Archive(archive_view::Message),
+    Trash(trash_view::Message),
+    Focus(focus_view::Message),
+    Board(board_view::Message),
+    Calendar(calendar_view::Message),
+    Agenda(agenda_view::Message),
+    /// Sent every few seconds while a list is open, to notice changes other programs make to it.
+    CheckFile,
+}
+
+/// Subscription that yields every `seconds`, from a thread since no timer runtime is available
+/// to the executor.
+pub(crate) fn every(seconds: u64) -> iced::Subscription<()> {
+    struct Ticks;
+    iced::subscription::unfold(
+        (std::any::TypeId::of::<Ticks>(), seconds),
+        None,
+        move |receiver: Option<mpsc::UnboundedReceiver<()>>| async move {
+            let mut receiver = receiver.unwrap_or_else(|| {
+                let (sender, receiver) = mpsc::unbounded();
+                std::thread::spawn(move || loop {
+                    std::thread::sleep(std::time::Duration::from_secs(seconds));
+                    // Stops once the subscription is dropped.
+                    if sender.unbounded_send(()).is_err() {
+                        break;
+                    }
+                });
+                receiver
+            });
+            let tick = receiver.next().await;
+            (tick, Some(receiver))
+        },
+    )
 }
@@ -0,0 +1,228 @@
+use crate::agenda::Agenda;
+use crate::task;
+use crate::toiced::ToIced;
+use crate::Text;
+use crate::{add_button, Organizer, ViewType};
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    SelectView(ViewType),
+    Task(usize, task::Message),
+    Unpin(usize),
+    PlanDay,
+    /// Selects or deselects a task to move to Today while planning the day.
+    ToggleSelection(usize, bool),
+    MoveToToday,
+    CancelPlanning,
+}
+
+/// Tasks being selected to plan the day, while planning.
+#[derive(Default)]
+pub(crate) struct DayPlan {
+    planning: bool,
+    selected: Vec<usize>,
+}
+
+pub(crate) trait AgendaView {
+    fn view_as_agenda(&self) -> iced::Element<'_, Message>;
+    fn update_agenda_view(&mut self, message: Message);
+}
+
+impl Organizer {
+    fn view_agenda_section<'a>(
+        &'a self,
+        title: &str,
+        tasks: &[usize],
+        pinned: bool,
+    ) -> Option<iced::Element<'a, Message>> {
+        if tasks.is_empty() {
+            return None;
+        }
+        let mut section =
+            iced::widget::column(vec![Text::new(title.to_string()).size(24).into()]).spacing(10);
+        for &index in tasks {
+            let task = self.data.tasks[index]
+                .view(self.clock.as_ref())
+                .map(move |message| Message::Task(index, message));
+            let mut row = iced::widget::row![task]
+                .spacing(10)
+                .align_items(iced::Alignment::Center);
+            if pinned {
+                row = row.push(
+                    add_button("Unpin", Message::Unpin(index))
+                        .style(iced::theme::Button::Secondary),
+                );
+            }
+            section = section.push(row);
+        }
+        Some(section.into())
+    }
+
+    /// Pending tasks that can be moved to Today, in list order.
+    fn planning_candidates(&self, agenda: &Agenda) -> Vec<usize> {
+        self.data
+            .visible_tasks(self.clock.as_ref())
+            .into_iter()
+            .filter(|(index, task)| !task.completed() && !agenda.contains(*index))
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    fn view_day_planning(&self, agenda: &Agenda) -> iced::Element<'_, Message> {
+        let mut column =
+            iced::widget::column(vec![Text::new("Plan my day").size(24).into()]).spacing(10);
+        let candidates = self.planning_candidates(agenda);
+        if candidates.is_empty() {
+            column = column.push(Text::new("No other pending tasks."));
+        }
+        for index in candidates {
+            let task = &self.data.tasks[index];
+            let label = match task.details() {
+                details if details.is_empty() => task.description().to_string(),
+                details => format!("{} {details}", task.description()),
+            };
+            column = column.push(iced::widget::checkbox(
+                label,
+                self.day_plan.selected.contains(&index),
+                move |selected| Message::ToggleSelection(index, selected),
+            ));
+        }
+        column
+            .push(
+                iced::widget::row![
+                    add_button("Move to Today", Message::MoveToToday),
+                    add_button("Cancel", Message::CancelPlanning)
+                        .style(iced::theme::Button::Secondary),
+                ]
+                .spacing(10),
+            )
+            .into()
+    }
+}
+
+impl AgendaView for Organizer {
+    fn view_as_agenda(&self) -> iced::Element<'_, Message> {
+        let view_pick_list =
+            iced::widget::pick_list(&ViewType::ALL[..], self.view_type, Message::SelectView);
+        let now = self.clock.now();
+        let agenda = Agenda::new(&self.data.tasks, &now);
+        let mut column = iced::widget::column(vec![
+            view_pick_list.into(),
+            Text::new(format!("Today, {}", now.short_date()))
+                .size(30)
+                .into(),
+        ])
+        .spacing(20)
+        .padding(10)
+        .align_items(iced::Alignment::Center);
+
+        let sections = [
+            self.view_agenda_section("Planned for today", &agenda.pinned, true),
+            self.view_agenda_section("Overdue", &agenda.overdue, false),
+            self.view_agenda_section("Due today", &agenda.due, false),
+            self.view_agenda_section("Back from snooze", &agenda.unsnoozed, false),
+            self.view_agenda_section("Repeating today", &agenda.periodic, false),
+        ];
+        let mut empty = true;
+        for section in sections.into_iter().flatten() {
+            column = column.push(section);
+            empty = false;
+        }
+        if empty {
+            column = column.push(Text::new("Nothing planned for today."));
+        }
+
+        if self.day_plan.planning {
+            column.push(self.view_day_planning(&agenda)).into()
+        } else {
+            column
+                .push(add_button("Plan my day", Message::PlanDay).width(150))
+                .into()
+        }
+    }
+
+    fn update_agenda_view(&mut self, message: Message) {
+        match message {
+            Message::SelectView(value) => self.view_type = Some(value),
+            Message::Task(index, task::Message::DeleteTask) => {
+                if index < self.data.tasks.len() {
+                    self.data.delete_task(index, &self.clock.now());
+                }
+                self.day_plan.selected.clear();
+            }
+            Message::Task(index, message) => {
+                if let Some(task) = self.data.tasks.get_mut(index) {
+                    task.update(message, self.clock.as_ref())
+                }
+            }
+            Message::Unpin(index) => {
+                if let Some(task) = self.data.tasks.get_mut(index) {
+                    task.pinned_to = None
+                }
+            }
+            Message::PlanDay => self.day_plan.planning = true,
+            Message::ToggleSelection(index, true) => self.day_plan.selected.push(index),
+            Message::ToggleSelection(index, false) => {
+                self.day_plan.selected.retain(|&selected| selected != index)
+            }
+            Message::MoveToToday => {
+                let today = self.clock.now().start_of_day();
+                for index in std::mem::take(&mut self.day_plan.selected) {
+                    if let Some(task) = self.data.tasks.get_mut(index) {
+                        task.pinned_to = Some(today.clone());
+                    }
+                }
+                self.day_plan.planning = false;
+            }
+            Message::CancelPlanning => self.day_plan = DayPlan::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::FakeClock;
+    use crate::task::Task;
+    use crate::time::Time;
+    use iced::Application;
+
+    #[test]
+    fn plan_my_day() {
+        let mut organizer = Organizer::new(()).0;
+        let now = Time::new(15, 5, 2024, 8, 0, 0).unwrap();
+        organizer.clock = Box::new(FakeClock::new(now.clone()));
+        for index in 0..3 {
+            let mut task = Task::new(index, organizer.clock.as_ref());
+            task.edit(&format!("Task {index}"));
+            organizer.data.tasks.push(task);
+        }
+        organizer.data.tasks[0].due_date = Some(Time::new(15, 5, 2024, 17, 0, 0).unwrap());
+        let agenda = Agenda::new(&organizer.data.tasks, &now);
+        assert_eq!(organizer.planning_candidates(&agenda), vec![1, 2]);
+
+        organizer.update_agenda_view(Message::PlanDay);
+        organizer.update_agenda_view(Message::ToggleSelection(1, true));
+        organizer.update_agenda_view(Message::ToggleSelection(2, true));
+        organizer.update_agenda_view(Message::ToggleSelection(1, false));
+        organizer.update_agenda_view(Message::MoveToToday);
+        assert!(!organizer.day_plan.planning);
+        let agenda = Agenda::new(&organizer.data.tasks, &now);
+        assert_eq!(agenda.pinned, vec![2]);
+        assert_eq!(agenda.due, vec![0]);
+        assert_eq!(organizer.planning_candidates(&agenda), vec![1]);
+
+        organizer.update_agenda_view(Message::Unpin(2));
+        let agenda = Agenda::new(&organizer.data.tasks, &now);
+        assert!(agenda.pinned.is_empty());
+        // Plans are for one day only.
+        organizer.data.tasks[1].pinned_to = Some(now.start_of_day());
+        let tomorrow = Agenda::new(&organizer.data.tasks, &now.add_days(1));
+        assert!(tomorrow.pinned.is_empty());
+
+        organizer.update_agenda_view(Message::Task(2, task::Message::DeleteTask));
+        organizer.update_agenda_view(Message::Task(2, task::Message::DeleteTask));
+        assert_eq!(organizer.data.tasks.len(), 2);
+        assert_eq!(organizer.data.trash.len(), 1);
+    }
+}
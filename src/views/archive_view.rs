@@ -0,0 +1,101 @@
+use crate::Text;
+use crate::{Organizer, ViewType};
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    SelectView(ViewType),
+    UpdateSearch(String),
+    UpdateArchiveAfterDays(String),
+    ArchiveNow,
+    Restore(usize),
+}
+
+fn archive_button(text: &str, message: Message) -> iced::widget::Button<'_, Message> {
+    iced::widget::button(Text::new(text).size(20))
+        .on_press(message)
+        .padding(10)
+}
+
+pub(crate) trait ArchiveView {
+    fn view_as_archive(&self) -> iced::Element<'_, Message>;
+    fn update_archive_view(&mut self, message: Message);
+}
+
+impl ArchiveView for Organizer {
+    fn view_as_archive(&self) -> iced::Element<'_, Message> {
+        let view_pick_list =
+            iced::widget::pick_list(&ViewType::ALL[..], self.view_type, Message::SelectView);
+        let archive_after_days = self
+            .data
+            .settings
+            .archive_after_days
+            .map(|days| days.to_string())
+            .unwrap_or_default();
+        let settings_row = iced::widget::row![
+            Text::new("Archive tasks completed more than"),
+            iced::widget::text_input(
+                "never",
+                &archive_after_days,
+                Message::UpdateArchiveAfterDays
+            )
+            .width(80)
+            .padding(10),
+            Text::new("days ago"),
+            archive_button("Archive now", Message::ArchiveNow),
+        ]
+        .spacing(10)
+        .align_items(iced::Alignment::Center);
+        let search_input = iced::widget::text_input(
+            "Search the archive",
+            &self.archive_search,
+            Message::UpdateSearch,
+        )
+        .padding(10);
+
+        let mut column = iced::widget::column(vec![
+            view_pick_list.into(),
+            settings_row.into(),
+            search_input.into(),
+        ])
+        .spacing(20)
+        .padding(10)
+        .align_items(iced::Alignment::Center);
+
+        let found = self.data.search_archive(&self.archive_search);
+        if found.is_empty() {
+            column = column.push(Text::new("No archived tasks."));
+        }
+        for index in found {
+            let task = &self.data.archive[index];
+            let completion = task.completion_time.as_ref().map_or(String::new(), |time| {
+                format!(" (completed {})", time.short_date())
+            });
+            let row = iced::widget::row![
+                Text::new(format!("{}{completion}", task.description())).width(iced::Length::Fill),
+                archive_button("Restore", Message::Restore(index)),
+            ]
+            .spacing(10)
+            .align_items(iced::Alignment::Center);
+            column = column.push(row);
+        }
+        column.into()
+    }
+
+    fn update_archive_view(&mut self, message: Message) {
+        match message {
+            Message::SelectView(value) => self.view_type = Some(value),
+            Message::UpdateSearch(search) => self.archive_search = search,
+            Message::UpdateArchiveAfterDays(days) => {
+                if days.is_empty() {
+                    self.data.settings.archive_after_days = None;
+                } else if let Ok(days) = days.parse() {
+                    self.data.settings.archive_after_days = Some(days);
+                }
+            }
+            Message::ArchiveNow => {
+                self.data.archive_old_tasks(&self.clock.now());
+            }
+            Message::Restore(index) => self.data.restore_archived(index, self.clock.as_ref()),
+        }
+    }
+}
@@ -0,0 +1,346 @@
+use crate::data::BoardColumn;
+use crate::Text;
+use crate::{Organizer, ViewType};
+use iced::keyboard::KeyCode;
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    SelectView(ViewType),
+    SelectTask(usize),
+    MoveTask(usize, usize),
+    /// A key pressed while no widget used it, to move the selected task.
+    KeyPressed(KeyCode),
+    EditColumnName(usize, String),
+    /// Renames a column to the name typed for it.
+    RenameColumn(usize),
+    UpdateWipLimit(usize, String),
+    UpdateNewColumn(String),
+    AddColumn,
+    RemoveColumn(usize),
+}
+
+/// Task selected on the board, which the arrow keys move, the name of a column being added and
+/// the new name of a column being renamed, which is applied when submitted.
+#[derive(Default)]
+pub(crate) struct Board {
+    selected: Option<usize>,
+    new_column: String,
+    renaming: Option<(usize, String)>,
+}
+
+pub(crate) trait BoardView {
+    fn view_as_board(&self) -> iced::Element<'_, Message>;
+    fn update_board_view(&mut self, message: Message);
+}
+
+/// Subscription to the arrow keys that the board uses.
+pub(crate) fn key_presses() -> iced::Subscription<Message> {
+    iced::subscription::events_with(|event, status| match (event, status) {
+        (
+            iced::Event::Keyboard(iced::keyboard::Event::KeyPressed { key_code, .. }),
+            iced::event::Status::Ignored,
+        ) if matches!(key_code, KeyCode::Left | KeyCode::Right | KeyCode::Escape) => {
+            Some(Message::KeyPressed(key_code))
+        }
+        _ => None,
+    })
+}
+
+fn board_button(text: &str, message: Message) -> iced::widget::Button<'_, Message> {
+    iced::widget::button(Text::new(text).size(16))
+        .on_press(message)
+        .padding(5)
+}
+
+impl Organizer {
+    fn view_board_column(&self, column: usize) -> iced::Element<'_, Message> {
+        let columns = &self.data.settings.board_columns;
+        let BoardColumn { name, wip_limit } = &columns[column];
+        let tasks = self.data.board_tasks(column);
+        let count = match wip_limit {
+            Some(limit) => format!("{}/{limit}", tasks.len()),
+            None => tasks.len().to_string(),
+        };
+        let name = match &self.board.renaming {
+            Some((renamed, new_name)) if *renamed == column => new_name,
+            _ => name,
+        };
+        let header = iced::widget::row![
+            iced::widget::text_input("Column name", name, move |name| {
+                Message::EditColumnName(column, name)
+            })
+            .on_submit(Message::RenameColumn(column))
+            .padding(5),
+            Text::new(count),
+        ]
+        .spacing(5)
+        .align_items(iced::Alignment::Center);
+        let wip_limit = wip_limit.map(|limit| limit.to_string()).unwrap_or_default();
+        let settings_row = iced::widget::row![
+            Text::new("WIP limit").size(16),
+            iced::widget::text_input("none", &wip_limit, move |limit| {
+                Message::UpdateWipLimit(column, limit)
+            })
+            .width(50)
+            .padding(5),
+            board_button("Remove", Message::RemoveColumn(column))
+                .style(iced::theme::Button::Destructive),
+        ]
+        .spacing(5)
+        .align_items(iced::Alignment::Center);
+
+        let mut cards = iced::widget::column(vec![header.into(), settings_row.into()])
+            .spacing(10)
+            .width(220);
+        for index in tasks {
+            let task = &self.data.tasks[index];
+            let description = match task.description() {
+                "" => "(no description)",
+                description => description,
+            };
+            let style = if self.board.selected == Some(index) {
+                iced::theme::Button::Primary
+            } else {
+                iced::theme::Button::Secondary
+            };
+            let card = iced::widget::button(
+                iced::widget::column![Text::new(description), Text::new(task.details()).size(14)]
+                    .spacing(5),
+            )
+            .on_press(Message::SelectTask(index))
+            .style(style)
+            .width(iced::Length::Fill)
+            .padding(10);
+            let mut moves = iced::widget::row(vec![]).spacing(5);
+            if column > 0 {
+                moves = moves.push(board_button("<", Message::MoveTask(index, column - 1)));
+            }
+            if column + 1 < columns.len() {
+                moves = moves.push(board_button(">", Message::MoveTask(index, column + 1)));
+            }
+            cards = cards.push(iced::widget::column![card, moves].spacing(2));
+        }
+        cards.into()
+    }
+
+    fn move_on_board(&mut self, index: usize, column: usize) {
+        if let Err(message) = self.data.move_to_column(index, column, self.clock.as_ref()) {
+            self.error_text = Some(message);
+        }
+    }
+}
+
+impl BoardView for Organizer {
+    fn view_as_board(&self) -> iced::Element<'_, Message> {
+        let view_pick_list =
+            iced::widget::pick_list(&ViewType::ALL[..], self.view_type, Message::SelectView);
+        let new_column_row = iced::widget::row![
+            iced::widget::text_input(
+                "New column",
+                &self.board.new_column,
+                Message::UpdateNewColumn
+            )
+            .on_submit(Message::AddColumn)
+            .width(200)
+            .padding(10),
+            board_button("Add column", Message::AddColumn),
+        ]
+        .spacing(10)
+        .align_items(iced::Alignment::Center);
+
+        let mut column = iced::widget::column(vec![view_pick_list.into(), new_column_row.into()])
+            .spacing(20)
+            .padding(10)
+            .align_items(iced::Alignment::Center);
+        if let Some(ref error_text) = self.error_text {
+            column = column.push(Text::new(error_text).style(iced::Color::from_rgb(1., 0., 0.)));
+        }
+        let columns = (0..self.data.settings.board_columns.len())
+            .map(|index| self.view_board_column(index))
+            .collect();
+        column
+            .push(Text::new(
+                "Select a task and use the left and right arrow keys to move it.",
+            ))
+            .push(iced::widget::row(columns).spacing(20))
+            .into()
+    }
+
+    fn update_board_view(&mut self, message: Message) {
+        let columns = &mut self.data.settings.board_columns;
+        match message {
+            Message::SelectView(value) => self.view_type = Some(value),
+            Message::SelectTask(index) => self.board.selected = Some(index),
+            Message::MoveTask(index, column) => {
+                self.board.selected = Some(index);
+                self.move_on_board(index, column);
+            }
+            Message::KeyPressed(KeyCode::Escape) => self.board.selected = None,
+            Message::KeyPressed(key_code) => {
+                let Some(index) = self
+                    .board
+                    .selected
+                    .filter(|&index| index < self.data.tasks.len())
+                else {
+                    return;
+                };
+                let column = self.data.board_column(&self.data.tasks[index]);
+                let target = match key_code {
+                    KeyCode::Left => column.checked_sub(1),
+                    _ => Some(column + 1)
+                        .filter(|&target| target < self.data.settings.board_columns.len()),
+                };
+                if let Some(target) = target {
+                    self.move_on_board(index, target);
+                }
+            }
+            Message::EditColumnName(column, name) => self.board.renaming = Some((column, name)),
+            Message::RenameColumn(column) => {
+                let Some((renamed, name)) = self.board.renaming.take() else {
+                    return;
+                };
+                if renamed == column {
+                    if let Err(message) = self.data.rename_column(column, &name) {
+                        self.error_text = Some(message);
+                    }
+                }
+            }
+            Message::UpdateWipLimit(column, limit) => {
+                if limit.is_empty() {
+                    columns[column].wip_limit = None;
+                } else if let Ok(limit) = limit.parse() {
+                    columns[column].wip_limit = Some(limit);
+                }
+            }
+            Message::UpdateNewColumn(name) => self.board.new_column = name,
+            Message::AddColumn => {
+                match self.data.check_column_name(&self.board.new_column, None) {
+                    Ok(name) => {
+                        // Added before the last column, which holds the completed tasks.
+                        let columns = &mut self.data.settings.board_columns;
+                        let position = columns.len().saturating_sub(1);
+                        let column = BoardColumn {
+                            name,
+                            wip_limit: None,
+                        };
+                        columns.insert(position, column);
+                        self.board.new_column.clear();
+                    }
+                    Err(message) => self.error_text = Some(message),
+                }
+            }
+            Message::RemoveColumn(column) => {
+                if columns.len() > 2 {
+                    columns.remove(column);
+                } else {
+                    self.error_text = Some("The board needs at least two columns.".to_string());
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::{FakeClock, SystemClock};
+    use crate::task::Task;
+    use crate::time::Time;
+    use iced::Application;
+
+    fn organizer_with_tasks(count: usize) -> Organizer {
+        let mut organizer = Organizer::new(()).0;
+        organizer.clock = Box::new(FakeClock::new(Time::new(13, 5, 2024, 9, 0, 0).unwrap()));
+        for index in 0..count {
+            organizer.data.tasks.push(Task::new(index, &SystemClock));
+        }
+        organizer
+    }
+
+    #[test]
+    fn moves_between_columns() {
+        let mut organizer = organizer_with_tasks(1);
+        assert_eq!(organizer.data.board_tasks(0), vec![0]);
+
+        organizer.update_board_view(Message::SelectTask(0));
+        organizer.update_board_view(Message::KeyPressed(KeyCode::Right));
+        let task = &organizer.data.tasks[0];
+        assert_eq!(task.status.as_deref(), Some("In progress"));
+        assert_eq!(organizer.data.board_column(task), 1);
+
+        organizer.update_board_view(Message::MoveTask(0, 3));
+        assert!(organizer.data.tasks[0].completed());
+        assert_eq!(
+            organizer.data.tasks[0].completion_time,
+            Some(organizer.clock.now())
+        );
+        organizer.update_board_view(Message::KeyPressed(KeyCode::Right));
+        assert_eq!(organizer.data.board_column(&organizer.data.tasks[0]), 3);
+
+        organizer.update_board_view(Message::KeyPressed(KeyCode::Left));
+        assert!(!organizer.data.tasks[0].completed());
+        assert_eq!(organizer.data.board_column(&organizer.data.tasks[0]), 2);
+    }
+
+    #[test]
+    fn wip_limits() {
+        let mut organizer = organizer_with_tasks(3);
+        organizer.update_board_view(Message::UpdateWipLimit(1, "2".to_string()));
+        for index in 0..3 {
+            organizer.update_board_view(Message::MoveTask(index, 1));
+        }
+        assert_eq!(organizer.data.board_tasks(1), vec![0, 1]);
+        assert_eq!(
+            organizer.error_text.as_deref(),
+            Some("In progress is at its limit of 2 tasks.")
+        );
+    }
+
+    #[test]
+    fn editing_columns() {
+        let mut organizer = organizer_with_tasks(1);
+        organizer.update_board_view(Message::MoveTask(0, 2));
+        // Names typed so far are not applied, even when they match another column.
+        organizer.update_board_view(Message::EditColumnName(2, "In progress".to_string()));
+        assert_eq!(organizer.data.tasks[0].status.as_deref(), Some("Review"));
+        organizer.update_board_view(Message::RenameColumn(2));
+        assert_eq!(
+            organizer.error_text.as_deref(),
+            Some("There is already a column named In progress.")
+        );
+        organizer.update_board_view(Message::EditColumnName(2, " ".to_string()));
+        organizer.update_board_view(Message::RenameColumn(2));
+        assert_eq!(
+            organizer.error_text.as_deref(),
+            Some("Board columns need a name.")
+        );
+        assert_eq!(organizer.data.settings.board_columns[2].name, "Review");
+
+        organizer.update_board_view(Message::EditColumnName(2, "Testing".to_string()));
+        organizer.update_board_view(Message::RenameColumn(2));
+        assert_eq!(organizer.data.tasks[0].status.as_deref(), Some("Testing"));
+        assert_eq!(organizer.data.board_column(&organizer.data.tasks[0]), 2);
+
+        organizer.update_board_view(Message::UpdateNewColumn("Blocked".to_string()));
+        organizer.update_board_view(Message::AddColumn);
+        let names = |organizer: &Organizer| {
+            organizer
+                .data
+                .settings
+                .board_columns
+                .iter()
+                .map(|column| column.name.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            names(&organizer),
+            ["Backlog", "In progress", "Testing", "Blocked", "Done"]
+        );
+        organizer.update_board_view(Message::UpdateNewColumn("Done".to_string()));
+        organizer.update_board_view(Message::AddColumn);
+        assert_eq!(names(&organizer).len(), 5);
+
+        organizer.update_board_view(Message::RemoveColumn(2));
+        assert_eq!(organizer.data.board_column(&organizer.data.tasks[0]), 0);
+    }
+}
@@ -0,0 +1,325 @@
+use crate::calendar::{self, Entry};
+use crate::task;
+use crate::time::{DisplayTimezone, Time};
+use crate::toiced::ToIced;
+use crate::Text;
+use crate::{Organizer, ViewType};
+
+const WEEKDAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    SelectView(ViewType),
+    Previous,
+    Next,
+    Today,
+    ShowWeeks(bool),
+    EditTask(usize),
+    EditPeriodicTask,
+    CloseEditor,
+    Task(usize, task::Message),
+    UpdateTimezone(String),
+}
+
+/// Period shown by the calendar and the task being edited from it.
+#[derive(Default)]
+pub(crate) struct Calendar {
+    /// Whether a week is shown rather than a month.
+    week: bool,
+    /// A time in the period shown, or `None` for the current one.
+    date: Option<Time>,
+    editing: Option<usize>,
+    /// Timezone being typed, which is only saved once it is known.
+    timezone_input: Option<String>,
+}
+
+pub(crate) trait CalendarView {
+    fn view_as_calendar(&self) -> iced::Element<'_, Message>;
+    fn update_calendar_view(&mut self, message: Message);
+}
+
+fn calendar_button(text: &str, message: Message) -> iced::widget::Button<'_, Message> {
+    iced::widget::button(Text::new(text).size(18))
+        .on_press(message)
+        .padding(8)
+}
+
+impl Organizer {
+    /// First day shown, the title of the period and the number of days shown.
+    fn calendar_period(&self) -> (Time, String, usize) {
+        let date = self
+            .calendar
+            .date
+            .clone()
+            .unwrap_or_else(|| self.clock.now());
+        if self.calendar.week {
+            let first_day = date.start_of_week();
+            let title = format!("Week of {}", first_day.short_date());
+            (first_day, title, 7)
+        } else {
+            let month = date.start_of_month();
+            let first_day = month.start_of_week();
+            let next_month = month.add_months(1);
+            let weeks = (0..)
+                .take_while(|week| first_day.add_days(7 * week) < next_month)
+                .count();
+            (first_day, month.month_and_year(), weeks * 7)
+        }
+    }
+
+    fn view_entry(&self, entry: &Entry) -> iced::Element<'_, Message> {
+        let (text, message) = match *entry {
+            Entry::Due(index) => (
+                format!("Due: {}", self.data.tasks[index].description()),
+                Message::EditTask(index),
+            ),
+            Entry::Completed(index) => (
+                format!("Done: {}", self.data.tasks[index].description()),
+                Message::EditTask(index),
+            ),
+            Entry::Occurrence(index) => (
+                format!("Repeats: {}", self.data.periodic_tasks[index].description()),
+                Message::EditPeriodicTask,
+            ),
+        };
+        iced::widget::button(Text::new(text).size(14))
+            .on_press(message)
+            .style(iced::theme::Button::Text)
+            .padding(2)
+            .into()
+    }
+}
+
+impl CalendarView for Organizer {
+    fn view_as_calendar(&self) -> iced::Element<'_, Message> {
+        let view_pick_list =
+            iced::widget::pick_list(&ViewType::ALL[..], self.view_type, Message::SelectView);
+        let (first_day, title, count) = self.calendar_period();
+        let navigation = iced::widget::row![
+            calendar_button("<", Message::Previous),
+            Text::new(title).size(24).width(220),
+            calendar_button(">", Message::Next),
+            calendar_button("Today", Message::Today),
+            iced::widget::radio("Month", false, Some(self.calendar.week), Message::ShowWeeks),
+            iced::widget::radio("Week", true, Some(self.calendar.week), Message::ShowWeeks),
+        ]
+        .spacing(20)
+        .align_items(iced::Alignment::Center);
+
+        let today = self.clock.now().start_of_day();
+        let shown_month = self
+            .calendar
+            .date
+            .clone()
+            .unwrap_or_else(|| self.clock.now())
+            .month();
+        let header = WEEKDAYS
+            .iter()
+            .map(|weekday| Text::new(*weekday).width(iced::Length::Fill).into())
+            .collect();
+        let mut grid = iced::widget::column(vec![iced::widget::row(header).into()]).spacing(5);
+        let days = calendar::days(&self.data, &first_day, count);
+        for week in days.chunks(7) {
+            let cells = week
+                .iter()
+                .map(|day| {
+                    let mut number = Text::new(day.start.day().to_string()).size(18);
+                    if day.start == today {
+                        number = number.style(iced::Color::from_rgb(0.2, 0.4, 0.8));
+                    } else if !self.calendar.week && day.start.month() != shown_month {
+                        number = number.style(iced::Color::from_rgb(0.6, 0.6, 0.6));
+                    }
+                    let cell = day
+                        .entries
+                        .iter()
+                        .fold(iced::widget::column![number], |cell, entry| {
+                            cell.push(self.view_entry(entry))
+                        });
+                    iced::widget::container(cell.spacing(2))
+                        .width(iced::Length::Fill)
+                        .height(iced::Length::Shrink)
+                        .padding(5)
+                        .style(iced::theme::Container::Box)
+                        .into()
+                })
+                .collect();
+            grid = grid.push(iced::widget::row(cells).spacing(5));
+        }
+
+        let timezone_input = self
+            .calendar
+            .timezone_input
+            .clone()
+            .or_else(|| self.data.settings.timezone.clone())
+            .unwrap_or_default();
+        let mut timezone_row = iced::widget::row![
+            Text::new("Timezone"),
+            iced::widget::text_input("local", &timezone_input, Message::UpdateTimezone)
+                .width(220)
+                .padding(8),
+        ]
+        .spacing(10)
+        .align_items(iced::Alignment::Center);
+        if !timezone_input.is_empty()
+            && DisplayTimezone::from_setting(Some(&timezone_input)).is_err()
+        {
+            timezone_row = timezone_row
+                .push(Text::new("Unknown timezone").style(iced::Color::from_rgb(1., 0., 0.)));
+        }
+
+        let mut column = iced::widget::column(vec![
+            view_pick_list.into(),
+            navigation.into(),
+            timezone_row.into(),
+        ])
+        .spacing(20)
+        .padding(10)
+        .align_items(iced::Alignment::Center);
+        let editing = self
+            .calendar
+            .editing
+            .and_then(|index| Some((index, self.data.tasks.get(index)?)));
+        if let Some((index, task)) = editing {
+            let editor = task
+                .view(self.clock.as_ref())
+                .map(move |message| Message::Task(index, message));
+            column = column.push(
+                iced::widget::row![editor, calendar_button("Close", Message::CloseEditor)]
+                    .spacing(10)
+                    .align_items(iced::Alignment::Center),
+            );
+        }
+        column.push(grid).into()
+    }
+
+    fn update_calendar_view(&mut self, message: Message) {
+        let (first_day, _, _) = self.calendar_period();
+        match message {
+            Message::SelectView(value) => self.view_type = Some(value),
+            Message::Previous if self.calendar.week => {
+                self.calendar.date = Some(first_day.add_days(-7))
+            }
+            Message::Previous => {
+                let month = self.calendar.date.clone().unwrap_or(self.clock.now());
+                self.calendar.date = Some(month.add_months(-1))
+            }
+            Message::Next if self.calendar.week => self.calendar.date = Some(first_day.add_days(7)),
+            Message::Next => {
+                let month = self.calendar.date.clone().unwrap_or(self.clock.now());
+                self.calendar.date = Some(month.add_months(1))
+            }
+            Message::Today => self.calendar.date = None,
+            Message::ShowWeeks(week) => self.calendar.week = week,
+            Message::EditTask(index) => self.calendar.editing = Some(index),
+            Message::EditPeriodicTask => self.view_type = Some(ViewType::PeriodicTasksManagement),
+            Message::CloseEditor => self.calendar.editing = None,
+            Message::Task(index, task::Message::DeleteTask) => {
+                if index < self.data.tasks.len() {
+                    self.data.delete_task(index, &self.clock.now());
+                }
+                self.calendar.editing = None;
+            }
+            Message::Task(index, message) => {
+                if let Some(task) = self.data.tasks.get_mut(index) {
+                    task.update(message, self.clock.as_ref())
+                }
+            }
+            Message::UpdateTimezone(input) => {
+                let name = input.trim();
+                if name.is_empty() {
+                    self.data.settings.timezone = None;
+                } else if DisplayTimezone::from_setting(Some(name)).is_ok() {
+                    self.data.settings.timezone = Some(name.to_string());
+                }
+                self.calendar.timezone_input = Some(input);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::FakeClock;
+    use crate::task::Task;
+    use iced::Application;
+
+    #[test]
+    fn navigation() {
+        let mut organizer = Organizer::new(()).0;
+        // Wednesday.
+        let now = Time::new(15, 5, 2024, 10, 0, 0).unwrap();
+        organizer.clock = Box::new(FakeClock::new(now));
+
+        let (first_day, title, count) = organizer.calendar_period();
+        assert_eq!(first_day, Time::new(29, 4, 2024, 0, 0, 0).unwrap());
+        assert_eq!(title, "May 2024");
+        assert_eq!(count, 35);
+
+        organizer.update_calendar_view(Message::Next);
+        let (first_day, title, count) = organizer.calendar_period();
+        assert_eq!(first_day, Time::new(27, 5, 2024, 0, 0, 0).unwrap());
+        assert_eq!(title, "June 2024");
+        assert_eq!(count, 35);
+
+        organizer.update_calendar_view(Message::ShowWeeks(true));
+        organizer.update_calendar_view(Message::Previous);
+        let (first_day, title, count) = organizer.calendar_period();
+        assert_eq!(first_day, Time::new(20, 5, 2024, 0, 0, 0).unwrap());
+        assert_eq!(title, "Week of 20/5");
+        assert_eq!(count, 7);
+
+        organizer.update_calendar_view(Message::Today);
+        let (first_day, _, _) = organizer.calendar_period();
+        assert_eq!(first_day, Time::new(13, 5, 2024, 0, 0, 0).unwrap());
+    }
+
+    #[test]
+    fn editing_from_the_calendar() {
+        let mut organizer = Organizer::new(()).0;
+        organizer
+            .data
+            .tasks
+            .push(Task::new(0, organizer.clock.as_ref()));
+        organizer.update_calendar_view(Message::EditTask(0));
+        organizer.update_calendar_view(Message::Task(
+            0,
+            task::Message::TextInput("Renew passport".to_string()),
+        ));
+        assert_eq!(organizer.data.tasks[0].description(), "Renew passport");
+
+        organizer.update_calendar_view(Message::Task(0, task::Message::DeleteTask));
+        assert!(organizer.data.tasks.is_empty());
+        assert_eq!(organizer.calendar.editing, None);
+        // The task was already deleted by a second click.
+        organizer.update_calendar_view(Message::Task(0, task::Message::DeleteTask));
+        assert_eq!(organizer.data.trash.len(), 1);
+    }
+
+    #[test]
+    fn timezone_setting() {
+        let mut organizer = Organizer::new(()).0;
+        let _ = organizer.update(crate::Message::Calendar(Message::UpdateTimezone(
+            "Asia/Tok".to_string(),
+        )));
+        assert_eq!(organizer.data.settings.timezone, None);
+
+        let _ = organizer.update(crate::Message::Calendar(Message::UpdateTimezone(
+            "Asia/Tokyo".to_string(),
+        )));
+        assert_eq!(
+            organizer.data.settings.timezone.as_deref(),
+            Some("Asia/Tokyo")
+        );
+        assert_eq!(
+            DisplayTimezone::current(),
+            DisplayTimezone::Named(chrono_tz::Asia::Tokyo)
+        );
+        assert_eq!(Time::from("2024-05-15T22:30:00Z").hour(), 7);
+
+        let _ = organizer.update(crate::Message::Calendar(Message::UpdateTimezone(
+            String::new(),
+        )));
+        assert_eq!(DisplayTimezone::current(), DisplayTimezone::Local);
+    }
+}
@@ -0,0 +1,161 @@
+use iced::widget::canvas::{self, Cursor, Frame, Geometry, Path, Stroke};
+use iced::{alignment, Color, Point, Rectangle, Size, Theme};
+
+const MARGIN: f32 = 30.;
+const LABEL_SIZE: f32 = 12.;
+/// Horizontal space needed by a label, used to skip labels when they would overlap.
+const LABEL_WIDTH: f32 = 40.;
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum ChartKind {
+    Bars,
+    Lines,
+}
+
+pub(crate) struct Series {
+    pub name: String,
+    pub color: Color,
+    pub values: Vec<f32>,
+}
+
+/// Bar or line chart of several series sharing the same labels.
+pub(crate) struct Chart {
+    pub kind: ChartKind,
+    pub title: String,
+    pub labels: Vec<String>,
+    pub series: Vec<Series>,
+}
+
+impl Chart {
+    fn maximum(&self) -> f32 {
+        self.series
+            .iter()
+            .flat_map(|series| series.values.iter().copied())
+            .fold(0., f32::max)
+            .max(1.)
+    }
+
+    fn draw_axes(&self, frame: &mut Frame, plot: &Rectangle, maximum: f32) {
+        let axes = Path::new(|builder| {
+            builder.move_to(Point::new(plot.x, plot.y));
+            builder.line_to(Point::new(plot.x, plot.y + plot.height));
+            builder.line_to(Point::new(plot.x + plot.width, plot.y + plot.height));
+        });
+        frame.stroke(&axes, Stroke::default().with_color(Color::BLACK));
+
+        frame.fill_text(canvas::Text {
+            content: format!("{maximum}"),
+            position: Point::new(plot.x - 4., plot.y),
+            size: LABEL_SIZE,
+            horizontal_alignment: alignment::Horizontal::Right,
+            vertical_alignment: alignment::Vertical::Center,
+            ..canvas::Text::default()
+        });
+
+        let slot_width = plot.width / self.labels.len().max(1) as f32;
+        let label_step = (LABEL_WIDTH / slot_width).ceil().max(1.) as usize;
+        for (index, label) in self.labels.iter().enumerate().step_by(label_step) {
+            frame.fill_text(canvas::Text {
+                content: label.clone(),
+                position: Point::new(
+                    plot.x + slot_width * (index as f32 + 0.5),
+                    plot.y + plot.height + 4.,
+                ),
+                size: LABEL_SIZE,
+                horizontal_alignment: alignment::Horizontal::Center,
+                ..canvas::Text::default()
+            });
+        }
+    }
+
+    fn draw_legend(&self, frame: &mut Frame) {
+        let mut x = MARGIN;
+        frame.fill_text(canvas::Text {
+            content: self.title.clone(),
+            position: Point::new(x, 4.),
+            ..canvas::Text::default()
+        });
+        x += 10. * self.title.len() as f32;
+        for series in &self.series {
+            frame.fill_rectangle(Point::new(x, 8.), Size::new(10., 10.), series.color);
+            frame.fill_text(canvas::Text {
+                content: series.name.clone(),
+                position: Point::new(x + 14., 6.),
+                size: LABEL_SIZE,
+                ..canvas::Text::default()
+            });
+            x += 20. + 8. * series.name.len() as f32;
+        }
+    }
+
+    fn draw_bars(&self, frame: &mut Frame, plot: &Rectangle, maximum: f32) {
+        let slot_width = plot.width / self.labels.len().max(1) as f32;
+        let bar_width = 0.8 * slot_width / self.series.len().max(1) as f32;
+        for (series_index, series) in self.series.iter().enumerate() {
+            for (index, value) in series.values.iter().enumerate() {
+                let height = plot.height * value / maximum;
+                let x =
+                    plot.x + slot_width * (index as f32 + 0.1) + bar_width * series_index as f32;
+                frame.fill_rectangle(
+                    Point::new(x, plot.y + plot.height - height),
+                    Size::new(bar_width, height),
+                    series.color,
+                );
+            }
+        }
+    }
+
+    fn draw_lines(&self, frame: &mut Frame, plot: &Rectangle, maximum: f32) {
+        let slot_width = plot.width / self.labels.len().max(1) as f32;
+        for series in &self.series {
+            let point = |(index, value): (usize, &f32)| {
+                Point::new(
+                    plot.x + slot_width * (index as f32 + 0.5),
+                    plot.y + plot.height * (1. - value / maximum),
+                )
+            };
+            let mut points = series.values.iter().enumerate().map(point);
+            if let Some(first) = points.next() {
+                let line = Path::new(|builder| {
+                    builder.move_to(first);
+                    points.for_each(|point| builder.line_to(point));
+                });
+                frame.stroke(
+                    &line,
+                    Stroke::default().with_color(series.color).with_width(2.),
+                );
+            }
+        }
+    }
+}
+
+impl<Message> canvas::Program<Message> for Chart {
+    type State = ();
+
+    fn draw(
+        &self,
+        _state: &Self::State,
+        _theme: &Theme,
+        bounds: Rectangle,
+        _cursor: Cursor,
+    ) -> Vec<Geometry> {
+        let mut frame = Frame::new(bounds.size());
+        let plot = Rectangle::new(
+            Point::new(MARGIN, MARGIN),
+            Size::new(
+                (bounds.width - 2. * MARGIN).max(0.),
+                (bounds.height - 2. * MARGIN).max(0.),
+            ),
+        );
+        let maximum = self.maximum();
+
+        self.draw_legend(&mut frame);
+        self.draw_axes(&mut frame, &plot, maximum);
+        match self.kind {
+            ChartKind::Bars => self.draw_bars(&mut frame, &plot, maximum),
+            ChartKind::Lines => self.draw_lines(&mut frame, &plot, maximum),
+        }
+
+        vec![frame.into_geometry()]
+    }
+}
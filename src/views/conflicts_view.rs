@@ -0,0 +1,97 @@
+use crate::merge::{self, Resolution};
+use crate::task::Task;
+use crate::Text;
+use crate::{Organizer, ViewType};
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    SelectView(ViewType),
+    Resolve(usize, Resolution),
+}
+
+pub(crate) trait ConflictsView {
+    fn view_as_conflicts(&self) -> iced::Element<'_, Message>;
+    fn update_conflicts_view(&mut self, message: Message);
+}
+
+/// One line describing a version of a conflicting task.
+fn summary(task: Option<&Task>) -> String {
+    let Some(task) = task else {
+        return "Removed".to_string();
+    };
+    let mut summary = task.description().to_string();
+    if task.completed() {
+        summary.push_str(" (completed)");
+    }
+    for tag in &task.tags {
+        summary.push_str(&format!(" #{tag}"));
+    }
+    if let Some(priority) = task.priority {
+        summary.push_str(&format!(" !{priority}"));
+    }
+    if let Some(due_date) = &task.due_date {
+        summary.push_str(&format!(" due {}", due_date.iso_date()));
+    }
+    summary
+}
+
+fn resolve_button(text: &str, message: Message) -> iced::widget::Button<'_, Message> {
+    iced::widget::button(Text::new(text).size(20))
+        .on_press(message)
+        .padding(10)
+}
+
+impl ConflictsView for Organizer {
+    fn view_as_conflicts(&self) -> iced::Element<'_, Message> {
+        let view_pick_list =
+            iced::widget::pick_list(&ViewType::ALL[..], self.view_type, Message::SelectView);
+        let mut column = iced::widget::column(vec![view_pick_list.into()])
+            .spacing(20)
+            .padding(10)
+            .align_items(iced::Alignment::Center);
+
+        if self.data.conflicts.is_empty() {
+            column = column.push(Text::new("No merge conflicts."));
+        }
+        for (index, conflict) in self.data.conflicts.iter().enumerate() {
+            let versions = iced::widget::column![
+                Text::new(format!("Mine: {}", summary(conflict.ours.as_ref()))),
+                Text::new(format!("Theirs: {}", summary(conflict.theirs.as_ref()))),
+            ]
+            .spacing(5);
+            let buttons = iced::widget::row![
+                resolve_button("Keep mine", Message::Resolve(index, Resolution::Ours)),
+                resolve_button("Keep theirs", Message::Resolve(index, Resolution::Theirs)),
+                resolve_button("Keep merged", Message::Resolve(index, Resolution::Merged)),
+            ]
+            .spacing(10);
+            column = column.push(iced::widget::column![versions, buttons].spacing(10));
+        }
+        column.into()
+    }
+
+    fn update_conflicts_view(&mut self, message: Message) {
+        match message {
+            Message::SelectView(value) => self.view_type = Some(value),
+            Message::Resolve(index, resolution) => {
+                merge::resolve(&mut self.data, index, resolution)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::SystemClock;
+
+    #[test]
+    fn task_summaries() {
+        let mut task = Task::new(0, &SystemClock);
+        task.edit("Call Ann");
+        task.tags = vec!["work".to_string()];
+        task.set_completed(true, &SystemClock);
+        assert_eq!(summary(Some(&task)), "Call Ann (completed) #work");
+        assert_eq!(summary(None), "Removed");
+    }
+}
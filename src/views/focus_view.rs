@@ -0,0 +1,317 @@
+use crate::pomodoro::{FocusSession, Phase};
+use crate::task::new_uid;
+use crate::time::Time;
+use crate::Text;
+use crate::{add_button, Organizer, ViewType};
+use iced::Command;
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    SelectView(ViewType),
+    SelectTask(TaskChoice),
+    SetWorkMinutes(String),
+    SetShortBreakMinutes(String),
+    SetLongBreakMinutes(String),
+    SetLongBreakEvery(String),
+    Start,
+    Skip,
+    Stop,
+    /// Sent every second while a session runs.
+    Tick,
+}
+
+/// A visible task offered in the task picker.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaskChoice {
+    index: usize,
+    description: String,
+}
+
+impl std::fmt::Display for TaskChoice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.description)
+    }
+}
+
+/// Task picked for the focus mode, the session running on it and the alert of the last interval
+/// that ended.
+#[derive(Default)]
+pub(crate) struct Focus {
+    task: Option<TaskChoice>,
+    pub(crate) session: Option<FocusSession>,
+    alert: Option<String>,
+}
+
+pub(crate) trait FocusView {
+    fn view_as_focus(&self) -> iced::Element<'_, Message>;
+    fn update_focus_view(&mut self, message: Message, now: &Time) -> Command<Message>;
+}
+
+/// Minutes and seconds, as in "04:59".
+fn countdown(seconds: i64) -> String {
+    format!("{:02}:{:02}", seconds / 60, seconds % 60)
+}
+
+/// Sets `count` to the number in `text` unless it is not a whole number of at least one.
+fn set_count(count: &mut u32, text: &str) {
+    if let Some(number) = text.trim().parse().ok().filter(|number| *number >= 1) {
+        *count = number;
+    }
+}
+
+fn minutes_input<'a>(
+    label: &str,
+    minutes: u32,
+    message: impl Fn(String) -> Message + 'a,
+) -> iced::widget::Row<'a, Message> {
+    iced::widget::row![
+        Text::new(label.to_string()),
+        iced::widget::text_input("", &minutes.to_string(), message)
+            .width(60)
+            .padding(10),
+    ]
+    .spacing(10)
+    .align_items(iced::Alignment::Center)
+}
+
+impl FocusView for Organizer {
+    fn view_as_focus(&self) -> iced::Element<'_, Message> {
+        let view_pick_list =
+            iced::widget::pick_list(&ViewType::ALL[..], self.view_type, Message::SelectView);
+        let mut column = iced::widget::column(vec![view_pick_list.into()])
+            .spacing(20)
+            .padding(10)
+            .align_items(iced::Alignment::Center);
+
+        if let Some(alert) = &self.focus.alert {
+            column = column.push(
+                Text::new(alert)
+                    .size(28)
+                    .style(iced::Color::from_rgb(0.8, 0.2, 0.2)),
+            );
+        }
+
+        let Some(session) = &self.focus.session else {
+            let choices = self
+                .data
+                .visible_tasks(self.clock.as_ref())
+                .into_iter()
+                .map(|(index, task)| TaskChoice {
+                    index,
+                    description: task.description().to_string(),
+                })
+                .collect::<Vec<_>>();
+            let settings = &self.data.settings.pomodoro;
+            let settings_row = iced::widget::row![
+                minutes_input("Work", settings.work_minutes, Message::SetWorkMinutes),
+                minutes_input(
+                    "Short break",
+                    settings.short_break_minutes,
+                    Message::SetShortBreakMinutes
+                ),
+                minutes_input(
+                    "Long break",
+                    settings.long_break_minutes,
+                    Message::SetLongBreakMinutes
+                ),
+                minutes_input(
+                    "Long break every",
+                    settings.long_break_every,
+                    Message::SetLongBreakEvery
+                ),
+            ]
+            .spacing(20);
+            let task_pick_list =
+                iced::widget::pick_list(choices, self.focus.task.clone(), Message::SelectTask)
+                    .placeholder("Pick a task to focus on");
+            return column
+                .push(Text::new("Minutes of each interval"))
+                .push(settings_row)
+                .push(task_pick_list)
+                .push(add_button("Start", Message::Start))
+                .into();
+        };
+
+        let description = self
+            .data
+            .tasks
+            .iter()
+            .find(|task| task.uid.as_ref() == Some(&session.task))
+            .map_or("", |task| task.description());
+        let remaining = session.remaining(&self.clock.now()).num_seconds();
+        let phase = match session.phase {
+            Phase::Work => format!("Working on {description}"),
+            phase => phase.to_string(),
+        };
+        column
+            .push(Text::new(phase).size(24))
+            .push(Text::new(countdown(remaining)).size(60))
+            .push(Text::new(format!(
+                "Pomodoros completed in this session: {}",
+                session.completed
+            )))
+            .push(
+                iced::widget::row![
+                    add_button("Skip", Message::Skip),
+                    add_button("Stop", Message::Stop).style(iced::theme::Button::Destructive),
+                ]
+                .spacing(10),
+            )
+            .into()
+    }
+
+    fn update_focus_view(&mut self, message: Message, now: &Time) -> Command<Message> {
+        let settings = &mut self.data.settings.pomodoro;
+        match message {
+            Message::SelectView(value) => self.view_type = Some(value),
+            Message::SelectTask(choice) => self.focus.task = Some(choice),
+            Message::SetWorkMinutes(minutes) => set_count(&mut settings.work_minutes, &minutes),
+            Message::SetShortBreakMinutes(minutes) => {
+                set_count(&mut settings.short_break_minutes, &minutes)
+            }
+            Message::SetLongBreakMinutes(minutes) => {
+                set_count(&mut settings.long_break_minutes, &minutes)
+            }
+            Message::SetLongBreakEvery(count) => set_count(&mut settings.long_break_every, &count),
+            Message::Start => {
+                let task = self
+                    .focus
+                    .task
+                    .as_ref()
+                    .and_then(|choice| self.data.tasks.get_mut(choice.index));
+                match task {
+                    Some(task) => {
+                        // Tasks of older lists may have no UID yet.
+                        let uid = task.uid.get_or_insert_with(new_uid).clone();
+                        self.focus.session = Some(FocusSession::start(uid, settings, now));
+                        self.focus.alert = None;
+                    }
+                    None => self.focus.alert = Some("Pick a task first.".to_string()),
+                }
+            }
+            Message::Skip => {
+                if let Some(session) = self.focus.session.as_mut() {
+                    session.skip(settings, now);
+                    self.focus.alert = None;
+                }
+            }
+            Message::Stop => {
+                self.focus.session = None;
+                self.focus.alert = None;
+            }
+            Message::Tick => {
+                let Some(session) = self.focus.session.as_mut() else {
+                    return Command::none();
+                };
+                let Some(ended) = session.tick(settings, now) else {
+                    return Command::none();
+                };
+                if ended == Phase::Work {
+                    let task = self
+                        .data
+                        .tasks
+                        .iter_mut()
+                        .find(|task| task.uid.as_ref() == Some(&session.task));
+                    if let Some(task) = task {
+                        task.pomodoros.push(now.clone());
+                    }
+                }
+                self.focus.alert = Some(format!(
+                    "{ended} is over. {} for {} minutes.",
+                    session.phase,
+                    settings.length(session.phase).num_seconds() / 60
+                ));
+                return iced::window::request_user_attention(Some(
+                    iced::window::UserAttention::Informational,
+                ));
+            }
+        }
+        Command::none()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::SystemClock;
+    use crate::pomodoro::PomodoroSettings;
+    use crate::task::Task;
+    use crate::time::Duration;
+    use iced::Application;
+
+    #[test]
+    fn pomodoros_are_recorded_on_the_task() {
+        let (mut organizer, _) = Organizer::new(());
+        let mut task = Task::new(0, &SystemClock);
+        task.edit("Write report");
+        organizer.data.tasks.push(task);
+        let start = Time::new(13, 5, 2024, 9, 0, 0).unwrap();
+        let minutes = |minutes| &start + &Duration::from_minutes(minutes);
+
+        let _ = organizer.update_focus_view(Message::Start, &start);
+        assert!(organizer.focus.session.is_none());
+        let choice = TaskChoice {
+            index: 0,
+            description: "Write report".to_string(),
+        };
+        let _ = organizer.update_focus_view(Message::SelectTask(choice), &start);
+        let _ = organizer.update_focus_view(Message::Start, &start);
+        let _ = organizer.update_focus_view(Message::Tick, &minutes(10));
+        assert!(organizer.focus.alert.is_none());
+
+        let _ = organizer.update_focus_view(Message::Tick, &minutes(25));
+        assert_eq!(organizer.data.tasks[0].pomodoros, vec![minutes(25)]);
+        assert_eq!(
+            organizer.focus.alert.as_deref(),
+            Some("Work is over. Short break for 5 minutes.")
+        );
+
+        let _ = organizer.update_focus_view(Message::Stop, &minutes(26));
+        assert!(organizer.focus.session.is_none());
+        assert_eq!(countdown(299), "04:59");
+    }
+
+    #[test]
+    fn session_follows_the_task_by_uid() {
+        let (mut organizer, _) = Organizer::new(());
+        let start = Time::new(13, 5, 2024, 9, 0, 0).unwrap();
+        for (index, description) in ["Write report", "Read mail"].into_iter().enumerate() {
+            let mut task = Task::new(index, &SystemClock);
+            task.edit(description);
+            // Tasks created in the same second share their creation time.
+            task.creation_time = start.clone();
+            organizer.data.tasks.push(task);
+        }
+        organizer.data.tasks[1].uid = None;
+        let choice = TaskChoice {
+            index: 1,
+            description: "Read mail".to_string(),
+        };
+        let _ = organizer.update_focus_view(Message::SelectTask(choice), &start);
+        let _ = organizer.update_focus_view(Message::Start, &start);
+        assert!(organizer.data.tasks[1].uid.is_some());
+
+        organizer.data.tasks.swap(0, 1);
+        let _ = organizer.update_focus_view(Message::Tick, &(&start + &Duration::from_minutes(25)));
+        assert_eq!(organizer.data.tasks[0].pomodoros.len(), 1);
+        assert!(organizer.data.tasks[1].pomodoros.is_empty());
+    }
+
+    #[test]
+    fn intervals_last_at_least_a_minute() {
+        let (mut organizer, _) = Organizer::new(());
+        let now = Time::new(13, 5, 2024, 9, 0, 0).unwrap();
+        for text in ["0", "-5", "ten", ""] {
+            let _ = organizer.update_focus_view(Message::SetWorkMinutes(text.to_string()), &now);
+            let _ = organizer.update_focus_view(Message::SetLongBreakEvery(text.to_string()), &now);
+        }
+        let _ = organizer.update_focus_view(Message::SetShortBreakMinutes("0".to_string()), &now);
+        assert_eq!(
+            organizer.data.settings.pomodoro,
+            PomodoroSettings::default()
+        );
+
+        let _ = organizer.update_focus_view(Message::SetWorkMinutes("50".to_string()), &now);
+        assert_eq!(organizer.data.settings.pomodoro.work_minutes, 50);
+    }
+}
@@ -1,8 +1,16 @@
+use crate::clock::FakeClock;
+use crate::data::FileErrorKind;
 use crate::datatoiced::DataToIced;
+use crate::lock;
+use crate::merge;
+use crate::quick_add::QuickAdd;
+use crate::storage;
+use crate::task::EventKind;
+use crate::time::Duration;
 use crate::toiced::ToIced;
+use crate::Text;
 use crate::ViewType;
 use crate::{add_button, task, Organizer};
-use crate::{Data, Text};
 
 #[derive(Debug, Clone)]
 pub enum Message {
@@ -17,15 +25,27 @@ pub enum Message {
     SwapWithPrevious(usize),
     SwapWithNext(usize),
     SelectView(ViewType),
+    UpdateQuickAdd(String),
+    QuickAdd,
+    MergeExternalChange,
+    KeepLocalList,
+    ReloadExternalChange,
+    RetryLock,
+    UpdatePassphrase(String),
+    Unlock,
+    Encrypt,
+    Decrypt,
+    /// Moves the simulated time forward by some hours.
+    AdvanceSimulatedTime(i64),
 }
 
 pub(crate) trait ListView {
-    fn view_as_list(&self) -> iced::Element<Message>;
+    fn view_as_list(&self) -> iced::Element<'_, Message>;
     fn update_list_view(&mut self, message: Message);
 }
 
 impl ListView for Organizer {
-    fn view_as_list(&self) -> iced::Element<Message> {
+    fn view_as_list(&self) -> iced::Element<'_, Message> {
         let button_todo_tasks = iced::widget::Checkbox::new(
             "Todo",
             self.data.filters.todo,
@@ -49,7 +69,7 @@ impl ListView for Organizer {
         ]
         .spacing(40);
 
-        let data_view = self.data.view();
+        let data_view = self.data.view(self.clock.as_ref());
         let mut a_column =
             iced::widget::column(vec![a_row.into()]).align_items(iced::Alignment::Center);
         if let Some(ref error_text) = self.error_text {
@@ -57,8 +77,80 @@ impl ListView for Organizer {
                 .push(Text::new(error_text).style(iced::Color::from_rgb(1., 0., 0.)))
                 .align_items(iced::Alignment::Center);
         }
+        let passphrase_input = iced::widget::text_input(
+            "Passphrase",
+            &self.passphrase.input,
+            Message::UpdatePassphrase,
+        )
+        .password()
+        .padding(10);
+        let passphrase_row = match &self.passphrase.locked_file {
+            Some(locked_file) => {
+                a_column = a_column.push(Text::new(format!(
+                    "{locked_file} is encrypted. Enter its passphrase to open it."
+                )));
+                iced::widget::row![
+                    passphrase_input.on_submit(Message::Unlock),
+                    add_button("Unlock", Message::Unlock)
+                ]
+            }
+            None => iced::widget::row![
+                passphrase_input.on_submit(Message::Encrypt),
+                add_button("Encrypt", Message::Encrypt),
+                add_button("Decrypt", Message::Decrypt)
+            ],
+        }
+        .spacing(10)
+        .padding(10);
+        if let Some(holder) = &self.read_only {
+            let message = Text::new(format!(
+                "Read-only: this list is open in {holder}. Changes are not saved until it is \
+                 closed."
+            ));
+            a_column = a_column
+                .push(message)
+                .push(add_button("Retry", Message::RetryLock));
+        }
+        if self.external_change.is_some() {
+            let buttons = iced::widget::row![
+                add_button("Merge", Message::MergeExternalChange),
+                add_button("Keep mine", Message::KeepLocalList),
+                add_button("Reload", Message::ReloadExternalChange),
+            ]
+            .spacing(10);
+            a_column = a_column
+                .push(Text::new(
+                    "The list changed on disk while some of your changes were unsaved. Changes \
+                     are not saved until you choose what to do.",
+                ))
+                .push(buttons);
+        }
+        let now = self.clock.now();
+        if self.simulated_time {
+            let simulation_row = iced::widget::row![
+                Text::new(format!("Simulated time: {now}. Changes are not saved.")),
+                add_button("+1 hour", Message::AdvanceSimulatedTime(1)),
+                add_button("+1 day", Message::AdvanceSimulatedTime(24)),
+            ]
+            .spacing(10)
+            .align_items(iced::Alignment::Center);
+            a_column = a_column.push(simulation_row);
+        }
+        for task in self.data.tasks.iter().filter(|task| task.timer_running()) {
+            a_column = a_column.push(Text::new(format!(
+                "Timer running: {} ({})",
+                task.description(),
+                task.tracked(&now)
+            )));
+        }
+        if !self.data.conflicts.is_empty() {
+            a_column = a_column.push(Text::new(format!(
+                "{} merge conflicts to resolve in the Merge Conflicts view.",
+                self.data.conflicts.len()
+            )));
+        }
 
-        let file_name = &self.file_name.clone().unwrap_or(String::new());
+        let file_name = &self.file_name.clone().unwrap_or_default();
         let file_name_input = iced::widget::text_input(
             "Name of the task list",
             file_name,
@@ -74,8 +166,22 @@ impl ListView for Organizer {
         let view_pick_list =
             iced::widget::pick_list(&ViewType::ALL[..], self.view_type, Message::SelectView);
 
+        let quick_add_input = iced::widget::text_input(
+            "Quick add: Review PR #backend !high due:fri snooze:2h estimate:1h",
+            &self.quick_add_input,
+            Message::UpdateQuickAdd,
+        )
+        .on_submit(Message::QuickAdd)
+        .padding(10);
+        let quick_add_row =
+            iced::widget::row!(quick_add_input, add_button("Add", Message::QuickAdd))
+                .spacing(10)
+                .padding(10);
+
         a_column
             .push(a_row)
+            .push(passphrase_row)
+            .push(quick_add_row)
             .push(data_view)
             .push(view_pick_list)
             .spacing(10)
@@ -83,19 +189,6 @@ impl ListView for Organizer {
     }
 
     fn update_list_view(&mut self, message: Message) {
-        self.data
-            .periodic_tasks
-            .iter_mut()
-            .map(|periodic_task| periodic_task.create_tasks())
-            .enumerate()
-            .for_each(|(index, tasks)| {
-                let size = self.data.tasks.len();
-                tasks.into_iter().for_each(|mut task| {
-                    task.id = size + index + 1usize;
-                    self.data.tasks.push(task);
-                })
-            });
-
         match message {
             Message::AddTask => self.add_task(),
             Message::Task(task_id, task_message) => {
@@ -103,18 +196,23 @@ impl ListView for Organizer {
                     panic!("Tried to update inexisting task.")
                 };
                 if let task::Message::DeleteTask = task_message {
-                    self.data.tasks.remove(task_id);
+                    self.data.delete_task(task_id, &self.clock.now());
                 } else {
-                    self.data.tasks[task_id].update(task_message);
+                    self.data.tasks[task_id].update(task_message, self.clock.as_ref());
                 }
             }
             Message::UpdateSaveFileName(file_name) => {
                 self.file_name = Some(file_name);
             }
             Message::Load => {
-                let loaded_data = Data::load(&self.file_name.clone().unwrap_or(String::new()));
-                match loaded_data {
-                    Ok(loaded_data) => self.data = loaded_data,
+                let file_name = self.file_name.clone().unwrap_or_default();
+                match Organizer::open_storage(&file_name, None) {
+                    Ok((storage, loaded_data)) => {
+                        self.open_list(storage, loaded_data, lock::acquire(&file_name))
+                    }
+                    Err(error) if matches!(error.kind, FileErrorKind::Decryption) => {
+                        self.passphrase.locked_file = Some(file_name)
+                    }
                     Err(error) => {
                         self.error_text =
                             Some(format!("{0:?} problem: {1:?}", error.kind, error.message))
@@ -122,10 +220,29 @@ impl ListView for Organizer {
                 }
             }
             Message::Save => {
-                let save_result = self.data.save(self.file_name.as_ref().unwrap());
-                if let Err(error) = save_result {
+                if self.simulated_time {
                     self.error_text =
-                        Some(format!("{0:?} problem: {1:?}", error.kind, error.message));
+                        Some("Lists are not saved while time is simulated.".to_string());
+                    return;
+                }
+                let file_name = self.file_name.clone().unwrap_or_default();
+                let lock = match lock::acquire(&file_name) {
+                    Ok(lock) => lock,
+                    Err(holder) => {
+                        self.error_text = Some(format!("{file_name} is open in {holder}."));
+                        return;
+                    }
+                };
+                let save_result = storage::open(&file_name).and_then(|mut storage| {
+                    storage.save(&self.data)?;
+                    Ok(storage)
+                });
+                match save_result {
+                    Ok(storage) => self.open_list(storage, self.data.clone(), Ok(lock)),
+                    Err(error) => {
+                        self.error_text =
+                            Some(format!("{0:?} problem: {1:?}", error.kind, error.message))
+                    }
                 }
             }
             Message::ToggleActiveFilter(value) => {
@@ -144,23 +261,124 @@ impl ListView for Organizer {
             Message::SwapWithPrevious(index) => {
                 let first_visible = index == 0;
                 if !first_visible {
-                    let visible_tasks = self.data.visible_tasks();
+                    let visible_tasks = self.data.visible_tasks(self.clock.as_ref());
                     let (current_index, _) = visible_tasks[index];
                     let (previous_index, _) = visible_tasks[index - 1];
                     self.data.tasks.swap(current_index, previous_index);
+                    self.data.tasks[previous_index].record(
+                        EventKind::Moved {
+                            from: current_index,
+                            to: previous_index,
+                        },
+                        self.clock.as_ref(),
+                    );
                 }
             }
             Message::SwapWithNext(index) => {
-                let visible_tasks = self.data.visible_tasks();
+                let visible_tasks = self.data.visible_tasks(self.clock.as_ref());
                 let last_visible = index + 1 == visible_tasks.len();
                 if !last_visible {
                     let (current_index, _) = visible_tasks[index];
                     let (next_index, _) = visible_tasks[index + 1];
                     self.data.tasks.swap(current_index, next_index);
+                    self.data.tasks[next_index].record(
+                        EventKind::Moved {
+                            from: current_index,
+                            to: next_index,
+                        },
+                        self.clock.as_ref(),
+                    );
                 }
             }
 
             Message::SelectView(value) => self.view_type = Some(value),
+            Message::UpdateQuickAdd(value) => self.quick_add_input = value,
+            Message::QuickAdd => {
+                match QuickAdd::parse(&self.quick_add_input, self.clock.as_ref()) {
+                    Ok(quick_add) => {
+                        quick_add.add_to(&mut self.data);
+                        self.quick_add_input.clear();
+                    }
+                    Err(error) => self.error_text = Some(error.message),
+                }
+            }
+            Message::MergeExternalChange => {
+                if let Some(external_change) = self.external_change.take() {
+                    self.data = merge::merge(&self.saved_data, &self.data, &external_change);
+                }
+            }
+            Message::KeepLocalList => self.external_change = None,
+            Message::UpdatePassphrase(passphrase) => self.passphrase.input = passphrase,
+            Message::Unlock => {
+                let Some(file_name) = self.passphrase.locked_file.clone() else {
+                    return;
+                };
+                match Organizer::open_storage(&file_name, Some(&self.passphrase.input)) {
+                    Ok((storage, loaded_data)) => {
+                        self.open_list(storage, loaded_data, lock::acquire(&file_name));
+                        self.file_name = Some(file_name);
+                        self.passphrase = Default::default();
+                    }
+                    Err(error) => {
+                        self.error_text =
+                            Some(format!("{0:?} problem: {1:?}", error.kind, error.message))
+                    }
+                }
+            }
+            Message::Encrypt | Message::Decrypt => {
+                let passphrase = match message {
+                    Message::Encrypt if self.passphrase.input.is_empty() => {
+                        self.error_text = Some("Enter a passphrase to encrypt with.".to_string());
+                        return;
+                    }
+                    Message::Encrypt => Some(std::mem::take(&mut self.passphrase.input)),
+                    _ => None,
+                };
+                let encrypted = passphrase.is_some();
+                if self.read_only.is_some() || self.external_change.is_some() || self.simulated_time
+                {
+                    self.error_text = Some("The list cannot be saved right now.".to_string());
+                    return;
+                }
+                let Some(storage) = self.storage.as_mut() else {
+                    self.error_text = Some("Save the list first.".to_string());
+                    return;
+                };
+                // The list is saved right away, as nothing else changed to trigger a save.
+                let result = storage
+                    .set_passphrase(passphrase)
+                    .and_then(|()| storage.save(&self.data));
+                match result {
+                    Ok(()) => {
+                        self.saved_data = self.data.clone();
+                        self.error_text = Some(
+                            match encrypted {
+                                true => "The list is now encrypted.",
+                                false => "The list is no longer encrypted.",
+                            }
+                            .to_string(),
+                        );
+                    }
+                    Err(error) => self.error_text = Some(error.message),
+                }
+            }
+            Message::RetryLock => {
+                if let Some(file_name) = &self.file_name {
+                    match lock::acquire(file_name) {
+                        Ok(lock) => (self.lock, self.read_only) = (Some(lock), None),
+                        Err(holder) => self.read_only = Some(holder),
+                    }
+                }
+            }
+            Message::ReloadExternalChange => {
+                if let Some(external_change) = self.external_change.take() {
+                    self.data = external_change;
+                }
+            }
+            Message::AdvanceSimulatedTime(hours) => {
+                let now = &self.clock.now() + &Duration::from_hours(hours);
+                self.clock = Box::new(FakeClock::new(now));
+            }
         }
     }
 }
@@ -10,12 +10,12 @@ pub enum Message {
 }
 
 pub(crate) trait PeriodicTasksManagementView {
-    fn view_as_periodic_tasks_manager(&self) -> iced::Element<Message>;
+    fn view_as_periodic_tasks_manager(&self) -> iced::Element<'_, Message>;
     fn update_periodic_tasks_manager(&mut self, message: Message);
 }
 
 impl PeriodicTasksManagementView for Organizer {
-    fn view_as_periodic_tasks_manager(&self) -> iced::Element<Message> {
+    fn view_as_periodic_tasks_manager(&self) -> iced::Element<'_, Message> {
         let view_pick_list =
             iced::widget::pick_list(&ViewType::ALL[..], self.view_type, Message::SelectView);
 
@@ -29,13 +29,13 @@ impl PeriodicTasksManagementView for Organizer {
             .enumerate()
             .map(move |(index, periodic_task)| {
                 periodic_task
-                    .view()
+                    .view(self.clock.as_ref())
                     .map(move |message| Message::PeriodicTask(index, message))
             })
             .collect::<Vec<_>>();
 
         let create_text = iced::widget::Text::new("Add a new task")
-            .width(iced::Length::try_from(120).unwrap())
+            .width(iced::Length::from(120))
             .horizontal_alignment(iced::alignment::Horizontal::Center)
             .size(20);
         let create_button = iced::widget::button(create_text)
@@ -57,10 +57,10 @@ impl PeriodicTasksManagementView for Organizer {
             Message::Create => self
                 .data
                 .periodic_tasks
-                .push(PeriodicTask::new("".to_string())),
+                .push(PeriodicTask::new("".to_string(), self.clock.as_ref())),
             Message::PeriodicTask(index, message) => match message {
                 periodic_task::Message::DeleteTask => _ = self.data.periodic_tasks.remove(index),
-                _ => self.data.periodic_tasks[index].update(message),
+                _ => self.data.periodic_tasks[index].update(message, self.clock.as_ref()),
             },
         }
     }
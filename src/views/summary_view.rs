@@ -1,4 +1,9 @@
+use crate::export::{export_to_file, Grouping};
+use crate::statistics::Statistics;
 use crate::time::Duration;
+use crate::time_tracking;
+use crate::toiced::date_preview;
+use crate::views::charts::{Chart, ChartKind, Series};
 use crate::Time;
 use crate::ViewType;
 use crate::{add_button, Organizer, SummaryDates};
@@ -6,24 +11,227 @@ use crate::{add_button, Organizer, SummaryDates};
 #[derive(Debug, Clone)]
 pub enum Message {
     SelectView(ViewType),
-    UpdateInitialDay(String),
-    UpdateInitialMonth(String),
-    UpdateInitialYear(String),
-    UpdateFinalDay(String),
-    UpdateFinalMonth(String),
-    UpdateFinalYear(String),
+    UpdateInitialDate(String),
+    UpdateFinalDate(String),
     LastDay,
     LastWeek,
     LastTwoWeeks,
+    UpdateExportFileName(String),
+    SelectGrouping(Grouping),
+    Export,
+    ExportTimesheet,
 }
 
 pub(crate) trait SummaryView {
-    fn view_as_summary(&self) -> iced::Element<Message>;
+    fn view_as_summary(&self) -> iced::Element<'_, Message>;
     fn update_summary_view(&mut self, message: Message);
 }
 
+/// Periods longer than this many days are charted per week instead of per day.
+const MAXIMUM_DAYS_CHARTED: usize = 31;
+
+/// Total estimated and tracked effort of the estimated tasks, with the share of the estimate that
+/// was tracked.
+fn estimate_accuracy(statistics: &Statistics) -> Option<String> {
+    let estimated: Duration = statistics
+        .estimates
+        .iter()
+        .map(|comparison| comparison.estimated.clone())
+        .sum();
+    let tracked: Duration = statistics
+        .estimates
+        .iter()
+        .map(|comparison| comparison.tracked.clone())
+        .sum();
+    if estimated.num_seconds() == 0 {
+        return None;
+    }
+    let share = (tracked.clone() / estimated.clone() * 100.).round();
+    Some(format!(
+        "Total: estimated {estimated}, tracked {tracked} ({share}% of the estimate)"
+    ))
+}
+
+impl Organizer {
+    fn view_statistics(&self, statistics: Statistics) -> iced::Element<'_, Message> {
+        let average_time_to_completion = statistics
+            .average_time_to_completion
+            .as_ref()
+            .map_or("-".to_string(), |duration| duration.to_string());
+        let totals = iced::widget::column![
+            iced::widget::text(format!(
+                "Created: {}    Completed: {}    Reopened: {}    Snoozes: {}",
+                statistics.created, statistics.completed, statistics.reopened, statistics.snoozes
+            )),
+            iced::widget::text(format!(
+                "Average time to completion: {average_time_to_completion}    Tracked: {}    \
+                 Pomodoros: {}",
+                statistics.tracked, statistics.pomodoros
+            )),
+        ]
+        .spacing(10)
+        .align_items(iced::Alignment::Center);
+
+        let (periods, period_name) = if statistics.per_day.len() > MAXIMUM_DAYS_CHARTED {
+            (&statistics.per_week, "week")
+        } else {
+            (&statistics.per_day, "day")
+        };
+        let labels = periods
+            .iter()
+            .map(|period| period.start.short_date())
+            .collect::<Vec<_>>();
+        let created = Series {
+            name: "Created".to_string(),
+            color: iced::Color::from_rgb(0.3, 0.5, 0.9),
+            values: periods.iter().map(|period| period.created as f32).collect(),
+        };
+        let completed = Series {
+            name: "Completed".to_string(),
+            color: iced::Color::from_rgb(0.2, 0.7, 0.3),
+            values: periods
+                .iter()
+                .map(|period| period.completed as f32)
+                .collect(),
+        };
+
+        let completed_chart = Chart {
+            kind: ChartKind::Bars,
+            title: format!("Completed per {period_name}"),
+            labels: labels.clone(),
+            series: vec![Series {
+                name: completed.name.clone(),
+                color: completed.color,
+                values: completed.values.clone(),
+            }],
+        };
+        let created_and_completed_chart = Chart {
+            kind: ChartKind::Lines,
+            title: format!("Created vs. completed per {period_name}"),
+            labels,
+            series: vec![created, completed],
+        };
+
+        let task_list = |title: &str, indices: &[usize]| {
+            let mut column = iced::widget::column![iced::widget::text(title).size(24)]
+                .spacing(5)
+                .align_items(iced::Alignment::Center);
+            for &index in indices {
+                let task = &self.data.tasks[index];
+                column = column.push(iced::widget::text(format!(
+                    "{} (created {}, snoozed {} times)",
+                    task.description(),
+                    task.creation_time.short_date(),
+                    task.snooze_information.count
+                )));
+            }
+            column
+        };
+
+        let mut tracked_list = iced::widget::column![iced::widget::text("Time tracked").size(24)]
+            .spacing(5)
+            .align_items(iced::Alignment::Center);
+        for (index, tracked) in &statistics.tracked_tasks {
+            tracked_list = tracked_list.push(iced::widget::text(format!(
+                "{}: {tracked}",
+                self.data.tasks[*index].description()
+            )));
+        }
+
+        let mut estimates =
+            iced::widget::column![iced::widget::text("Estimated vs. actual").size(24)]
+                .spacing(5)
+                .align_items(iced::Alignment::Center);
+        for comparison in &statistics.estimates {
+            estimates = estimates.push(iced::widget::text(format!(
+                "{}: estimated {}, tracked {}, open for {}",
+                comparison.description,
+                comparison.estimated,
+                comparison.tracked,
+                comparison.elapsed
+            )));
+        }
+        if let Some(accuracy) = estimate_accuracy(&statistics) {
+            estimates = estimates.push(iced::widget::text(accuracy));
+        }
+
+        iced::widget::column![
+            totals,
+            iced::widget::Canvas::new(completed_chart)
+                .width(iced::Length::Fill)
+                .height(250),
+            iced::widget::Canvas::new(created_and_completed_chart)
+                .width(iced::Length::Fill)
+                .height(250),
+            tracked_list,
+            estimates,
+            task_list("Oldest open tasks", &statistics.oldest_open_tasks),
+            task_list("Most snoozed tasks", &statistics.most_snoozed_tasks),
+        ]
+        .spacing(20)
+        .padding(20)
+        .align_items(iced::Alignment::Center)
+        .into()
+    }
+
+    fn export_summary(&mut self) {
+        let (Ok(initial_date), Ok(final_date)) = (
+            &self.summary_dates.initial_date,
+            &self.summary_dates.final_date,
+        ) else {
+            self.summary_export.result = Some("Fix the dates before exporting.".to_string());
+            return;
+        };
+        let tasks = self.data.completed_between(initial_date, final_date);
+        let file_name = &self.summary_export.file_name;
+        self.summary_export.result = Some(
+            match export_to_file(
+                &tasks,
+                &[],
+                file_name,
+                self.summary_export.grouping,
+                self.clock.as_ref(),
+            ) {
+                Ok(()) => format!("Exported {} tasks to {file_name}.", tasks.len()),
+                Err(error) => format!("{0:?} problem: {1:?}", error.kind, error.message),
+            },
+        );
+    }
+}
+
+impl Organizer {
+    fn export_timesheet(&mut self) {
+        let (Ok(initial_date), Ok(final_date)) = (
+            &self.summary_dates.initial_date,
+            &self.summary_dates.final_date,
+        ) else {
+            self.summary_export.result = Some("Fix the dates before exporting.".to_string());
+            return;
+        };
+        let tasks = self
+            .data
+            .tasks
+            .iter()
+            .chain(&self.data.archive)
+            .collect::<Vec<_>>();
+        let file_name = &self.summary_export.file_name;
+        self.summary_export.result = Some(
+            match time_tracking::export_timesheet(
+                &tasks,
+                initial_date,
+                final_date,
+                &self.clock.now(),
+                file_name,
+            ) {
+                Ok(()) => format!("Exported the timesheet to {file_name}."),
+                Err(error) => format!("{0:?} problem: {1:?}", error.kind, error.message),
+            },
+        );
+    }
+}
+
 impl SummaryView for Organizer {
-    fn view_as_summary(&self) -> iced::Element<Message> {
+    fn view_as_summary(&self) -> iced::Element<'_, Message> {
         let view_pick_list =
             iced::widget::pick_list(&ViewType::ALL[..], self.view_type, Message::SelectView);
 
@@ -37,75 +245,45 @@ impl SummaryView for Organizer {
                 .spacing(10)
                 .padding(10);
 
-        let initial_day = self.summary_dates.initial_day.to_string();
-        let initial_day_input =
-            iced::widget::text_input("Initial day", &initial_day, Message::UpdateInitialDay)
-                .padding(10);
+        let initial_date_input = iced::widget::text_input(
+            "Initial date (e.g. last monday, 2024-05-01)",
+            &self.summary_dates.initial_input,
+            Message::UpdateInitialDate,
+        )
+        .padding(10);
+        let initial_date_row = iced::widget::row![
+            initial_date_input,
+            date_preview(&self.summary_dates.initial_input, self.clock.as_ref())
+        ]
+        .spacing(10)
+        .align_items(iced::Alignment::Center);
 
-        let initial_month = self.summary_dates.initial_month.to_string();
-        let initial_month_input =
-            iced::widget::text_input("Initial month", &initial_month, Message::UpdateInitialMonth)
-                .padding(10);
+        let initial_date_label = iced::widget::row![iced::widget::text("Initial date")];
 
-        let initial_year = self.summary_dates.initial_year.to_string();
-        let initial_year_input =
-            iced::widget::text_input("Initial year", &initial_year, Message::UpdateInitialYear)
-                .padding(10);
-        let initial_date_row =
-            iced::widget::row![initial_day_input, initial_month_input, initial_year_input];
-
-        let initial_date_label =
-            iced::widget::row![iced::widget::text("Initial date: Day/Month/Year")];
-
-        let initial_date = Time::new(
-            self.summary_dates.initial_day,
-            self.summary_dates.initial_month,
-            self.summary_dates.initial_year,
-            0,
-            0,
-            0,
-        );
+        let final_date_input = iced::widget::text_input(
+            "Final date (e.g. now, end of day)",
+            &self.summary_dates.final_input,
+            Message::UpdateFinalDate,
+        )
+        .padding(10);
+        let final_date_row = iced::widget::row![
+            final_date_input,
+            date_preview(&self.summary_dates.final_input, self.clock.as_ref())
+        ]
+        .spacing(10)
+        .align_items(iced::Alignment::Center);
 
-        let final_day = self.summary_dates.final_day.to_string();
-        let final_day_input =
-            iced::widget::text_input("Final day", &final_day, Message::UpdateFinalDay).padding(10);
+        let final_date_label = iced::widget::row![iced::widget::text("Final date")];
 
-        let final_month = self.summary_dates.final_month.to_string();
-        let final_month_input =
-            iced::widget::text_input("Initial month", &final_month, Message::UpdateFinalMonth)
-                .padding(10);
-
-        let final_year = self.summary_dates.final_year.to_string();
-        let final_year_input =
-            iced::widget::text_input("Initial year", &final_year, Message::UpdateFinalYear)
-                .padding(10);
-        let final_date_row =
-            iced::widget::row![final_day_input, final_month_input, final_year_input];
-
-        let final_date_label = iced::widget::row![iced::widget::text("Final date: Day/Month/Year")];
-
-        let final_date = Time::new(
-            self.summary_dates.final_day,
-            self.summary_dates.final_month,
-            self.summary_dates.final_year,
-            23,
-            59,
-            59,
-        );
+        let initial_date = &self.summary_dates.initial_date;
+        let final_date = &self.summary_dates.final_date;
 
         let mut a_column = iced::widget::column(vec![]);
 
-        let descriptions = if let (Ok(initial_date), Ok(final_date)) = (&initial_date, &final_date)
-        {
+        let descriptions = if let (Ok(initial_date), Ok(final_date)) = (initial_date, final_date) {
             self.data
-                .tasks
-                .iter()
-                .filter(|task| task.completed())
-                .filter(|task| task.completion_time.is_some())
-                .filter(|task| {
-                    let completion_time = &task.completion_time.clone().unwrap();
-                    initial_date < completion_time && completion_time < final_date
-                })
+                .completed_between(initial_date, final_date)
+                .into_iter()
                 .map(|task| iced::widget::row![iced::widget::text(task.description())])
                 .collect::<Vec<_>>()
         } else {
@@ -115,23 +293,63 @@ impl SummaryView for Organizer {
         a_column = a_column
             .push(pick_list_row)
             .push(periods_row)
-            .push(initial_date_row)
-            .push(initial_date_label);
+            .push(initial_date_label)
+            .push(initial_date_row);
 
         if initial_date.is_err() {
             a_column = a_column.push(iced::widget::row![iced::widget::text(
-                "WRONG INITIAL DATE: date does not exist!"
+                "WRONG INITIAL DATE: date not recognized!"
             )]);
         }
 
-        a_column = a_column.push(final_date_row).push(final_date_label);
+        a_column = a_column.push(final_date_label).push(final_date_row);
 
         if final_date.is_err() {
             a_column = a_column.push(iced::widget::row![iced::widget::text(
-                "WRONG FINAL DATE: date does not exist!"
+                "WRONG FINAL DATE: date not recognized!"
             )]);
         }
 
+        if let (Ok(initial_date), Ok(final_date)) = (initial_date, final_date) {
+            let statistics = Statistics::new(
+                &self.data.tasks,
+                &self.data.archive,
+                initial_date,
+                final_date,
+                &self.clock.now(),
+            );
+            a_column = a_column.push(self.view_statistics(statistics));
+        }
+
+        let export_file_input = iced::widget::text_input(
+            "Export to (e.g. summary.md, summary.csv, summary.html, summary.json, summary.ics)",
+            &self.summary_export.file_name,
+            Message::UpdateExportFileName,
+        )
+        .on_submit(Message::Export)
+        .padding(10);
+        let grouping_pick_list = iced::widget::pick_list(
+            &Grouping::ALL[..],
+            Some(self.summary_export.grouping),
+            Message::SelectGrouping,
+        );
+        let export_row = iced::widget::row![
+            export_file_input,
+            grouping_pick_list,
+            add_button("Export", Message::Export),
+            iced::widget::button(iced::widget::text("Export timesheet").size(20))
+                .on_press(Message::ExportTimesheet)
+                .padding(10)
+        ]
+        .spacing(10)
+        .padding(10)
+        .align_items(iced::Alignment::Center);
+        a_column = a_column.push(export_row);
+        if let Some(result) = &self.summary_export.result {
+            a_column = a_column.push(iced::widget::text(result));
+        }
+
+        a_column = a_column.push(iced::widget::text("Completed tasks").size(24));
         for description in descriptions {
             a_column = a_column.push(description);
         }
@@ -143,98 +361,75 @@ impl SummaryView for Organizer {
     }
 
     fn update_summary_view(&mut self, message: Message) {
-        let handle_update = |value: &str, max_value: u32, result: &mut u32| {
-            if value.is_empty() {
-                *result = 0;
-            } else if let Ok(day) = value.parse::<u32>() {
-                if day <= max_value {
-                    *result = day
-                }
-            }
-        };
         match message {
             Message::SelectView(value) => self.view_type = Some(value),
-            Message::UpdateInitialDay(value) => {
-                handle_update(&value, 31, &mut self.summary_dates.initial_day);
-                self.summary_dates.initial_date = Time::new(
-                    self.summary_dates.initial_day,
-                    self.summary_dates.initial_month,
-                    self.summary_dates.initial_year,
-                    0,
-                    0,
-                    0,
-                );
-            }
-            Message::UpdateInitialMonth(value) => {
-                handle_update(&value, 12, &mut self.summary_dates.initial_month);
-                self.summary_dates.initial_date = Time::new(
-                    self.summary_dates.initial_day,
-                    self.summary_dates.initial_month,
-                    self.summary_dates.initial_year,
-                    0,
-                    0,
-                    0,
-                );
-            }
-            Message::UpdateInitialYear(value) => {
-                handle_update(&value, 10000, &mut self.summary_dates.initial_year);
-                self.summary_dates.initial_date = Time::new(
-                    self.summary_dates.initial_day,
-                    self.summary_dates.initial_month,
-                    self.summary_dates.initial_year,
-                    0,
-                    0,
-                    0,
-                );
-            }
-            Message::UpdateFinalDay(value) => {
-                handle_update(&value, 31, &mut self.summary_dates.final_day);
-                self.summary_dates.final_date = Time::new(
-                    self.summary_dates.final_day,
-                    self.summary_dates.final_month,
-                    self.summary_dates.final_year,
-                    23,
-                    59,
-                    59,
-                );
-            }
-            Message::UpdateFinalMonth(value) => {
-                handle_update(&value, 12, &mut self.summary_dates.final_month);
-                self.summary_dates.final_date = Time::new(
-                    self.summary_dates.final_day,
-                    self.summary_dates.final_month,
-                    self.summary_dates.final_year,
-                    23,
-                    59,
-                    59,
-                );
+            Message::UpdateInitialDate(value) => {
+                self.summary_dates.initial_date =
+                    Time::from_natural_language(&value, &self.clock.now());
+                self.summary_dates.initial_input = value;
             }
-            Message::UpdateFinalYear(value) => {
-                handle_update(&value, 10000, &mut self.summary_dates.final_year);
-                self.summary_dates.final_date = Time::new(
-                    self.summary_dates.final_day,
-                    self.summary_dates.final_month,
-                    self.summary_dates.final_year,
-                    23,
-                    59,
-                    59,
-                );
+            Message::UpdateFinalDate(value) => {
+                self.summary_dates.final_date =
+                    Time::end_from_natural_language(&value, &self.clock.now());
+                self.summary_dates.final_input = value;
             }
             Message::LastDay => {
-                let now = Time::now();
+                let now = self.clock.now();
                 let before = &now - &Duration::from_hours(24);
                 self.summary_dates = SummaryDates::new(&before, &now);
             }
             Message::LastWeek => {
-                let now = Time::now();
+                let now = self.clock.now();
                 let before = &now - &Duration::from_hours(24 * 7);
                 self.summary_dates = SummaryDates::new(&before, &now);
             }
             Message::LastTwoWeeks => {
-                let now = Time::now();
+                let now = self.clock.now();
                 let before = &now - &Duration::from_hours(24 * 14);
                 self.summary_dates = SummaryDates::new(&before, &now);
             }
+            Message::UpdateExportFileName(value) => self.summary_export.file_name = value,
+            Message::SelectGrouping(grouping) => self.summary_export.grouping = grouping,
+            Message::Export => self.export_summary(),
+            Message::ExportTimesheet => self.export_timesheet(),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::FakeClock;
+    use crate::task::Task;
+    use iced::Application;
+
+    #[test]
+    fn final_date_includes_its_day() {
+        let mut organizer = Organizer::new(()).0;
+        let clock = FakeClock::new(Time::new(15, 5, 2024, 18, 0, 0).unwrap());
+        let mut task = Task::new(0, &clock);
+        task.set_completed(true, &clock);
+        organizer.data.tasks.push(task);
+        organizer.clock = Box::new(clock);
+
+        organizer.update_summary_view(Message::UpdateInitialDate("2024-05-15".to_string()));
+        organizer.update_summary_view(Message::UpdateFinalDate("today".to_string()));
+        let (Ok(initial_date), Ok(final_date)) = (
+            &organizer.summary_dates.initial_date,
+            &organizer.summary_dates.final_date,
+        ) else {
+            panic!("Dates not recognized");
+        };
+        assert_eq!(final_date, &Time::new(15, 5, 2024, 23, 59, 59).unwrap());
+        let completed = organizer.data.completed_between(initial_date, final_date);
+        let statistics = Statistics::new(
+            &organizer.data.tasks,
+            &[],
+            initial_date,
+            final_date,
+            &organizer.clock.now(),
+        );
+        assert_eq!(completed.len(), 1);
+        assert_eq!(statistics.completed, 1);
+    }
+}
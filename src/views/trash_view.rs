@@ -0,0 +1,89 @@
+use crate::Text;
+use crate::{Organizer, ViewType};
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    SelectView(ViewType),
+    UpdateRetentionDays(String),
+    Restore(usize),
+    Purge(usize),
+    EmptyTrash,
+}
+
+fn trash_button(text: &str, message: Message) -> iced::widget::Button<'_, Message> {
+    iced::widget::button(Text::new(text).size(20))
+        .on_press(message)
+        .padding(10)
+}
+
+pub(crate) trait TrashView {
+    fn view_as_trash(&self) -> iced::Element<'_, Message>;
+    fn update_trash_view(&mut self, message: Message);
+}
+
+impl TrashView for Organizer {
+    fn view_as_trash(&self) -> iced::Element<'_, Message> {
+        let view_pick_list =
+            iced::widget::pick_list(&ViewType::ALL[..], self.view_type, Message::SelectView);
+        let retention_days = self
+            .data
+            .settings
+            .trash_retention_days
+            .map(|days| days.to_string())
+            .unwrap_or_default();
+        let settings_row = iced::widget::row![
+            Text::new("Purge deleted tasks after"),
+            iced::widget::text_input("never", &retention_days, Message::UpdateRetentionDays)
+                .width(80)
+                .padding(10),
+            Text::new("days"),
+            trash_button("Empty trash", Message::EmptyTrash)
+                .style(iced::theme::Button::Destructive),
+        ]
+        .spacing(10)
+        .align_items(iced::Alignment::Center);
+
+        let mut column = iced::widget::column(vec![view_pick_list.into(), settings_row.into()])
+            .spacing(20)
+            .padding(10)
+            .align_items(iced::Alignment::Center);
+
+        if self.data.trash.is_empty() {
+            column = column.push(Text::new("The trash is empty."));
+        }
+        // Most recently deleted first.
+        for (index, trashed) in self.data.trash.iter().enumerate().rev() {
+            let row = iced::widget::row![
+                Text::new(format!(
+                    "{} (deleted {})",
+                    trashed.task.description(),
+                    trashed.deletion_time.short_date()
+                ))
+                .width(iced::Length::Fill),
+                trash_button("Restore", Message::Restore(index)),
+                trash_button("Delete forever", Message::Purge(index))
+                    .style(iced::theme::Button::Destructive),
+            ]
+            .spacing(10)
+            .align_items(iced::Alignment::Center);
+            column = column.push(row);
+        }
+        column.into()
+    }
+
+    fn update_trash_view(&mut self, message: Message) {
+        match message {
+            Message::SelectView(value) => self.view_type = Some(value),
+            Message::UpdateRetentionDays(days) => {
+                if days.is_empty() {
+                    self.data.settings.trash_retention_days = None;
+                } else if let Ok(days) = days.parse() {
+                    self.data.settings.trash_retention_days = Some(days);
+                }
+            }
+            Message::Restore(index) => self.data.restore_trashed(index),
+            Message::Purge(index) => _ = self.data.trash.remove(index),
+            Message::EmptyTrash => self.data.trash.clear(),
+        }
+    }
+}